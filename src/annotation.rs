@@ -0,0 +1,115 @@
+use graphics::Rectangle;
+use serde::{Deserialize, Serialize};
+
+use crate::game::{Collide, Draw, DrawCtx, PosF};
+use crate::ui::{draw_text, rgba};
+
+pub const FRAME_TITLE_HEIGHT: f64 = 20.;
+const RESIZE_HANDLE_SIZE: f64 = 8.;
+
+// free text and named frames on the canvas, they are saved with the circuit but not simulated
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub enum Annotation {
+    Label { text: String, position: PosF },
+    Frame { name: String, rect: [f64; 4] },
+}
+
+impl Annotation {
+    pub fn text(&self) -> &str {
+        match self {
+            Annotation::Label { text, .. } => text,
+            Annotation::Frame { name, .. } => name,
+        }
+    }
+
+    pub fn set_text(&mut self, new_text: String) {
+        match self {
+            Annotation::Label { text, .. } => *text = new_text,
+            Annotation::Frame { name, .. } => *name = new_text,
+        }
+    }
+
+    pub fn position(&self) -> PosF {
+        match self {
+            Annotation::Label { position, .. } => *position,
+            Annotation::Frame { rect, .. } => [rect[0], rect[1]],
+        }
+    }
+
+    pub fn set_position(&mut self, new_position: PosF) {
+        match self {
+            Annotation::Label { position, .. } => *position = new_position,
+            Annotation::Frame { rect, .. } => {
+                rect[0] = new_position[0];
+                rect[1] = new_position[1];
+            }
+        }
+    }
+
+    // the area covered, for labels estimated from the text length
+    pub fn rect(&self) -> [f64; 4] {
+        match self {
+            Annotation::Label { text, position } => [position[0], position[1], 8. * text.chars().count().max(1) as f64, 20.],
+            Annotation::Frame { rect, .. } => *rect,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AnnotationCollideDesc {
+    Move,
+    Resize,
+}
+
+impl Collide for Annotation {
+    type CollideDesc = AnnotationCollideDesc;
+
+    fn collide(&self, point: PosF) -> Option<AnnotationCollideDesc> {
+        match self {
+            Annotation::Label { .. } => self.rect().collide(point).map(|_| AnnotationCollideDesc::Move),
+            Annotation::Frame { rect, .. } => {
+                let handle = [rect[0] + rect[2] - RESIZE_HANDLE_SIZE, rect[1] + rect[3] - RESIZE_HANDLE_SIZE, RESIZE_HANDLE_SIZE, RESIZE_HANDLE_SIZE];
+                let title = [rect[0], rect[1], rect[2], FRAME_TITLE_HEIGHT];
+                if handle.collide(point).is_some() {
+                    Some(AnnotationCollideDesc::Resize)
+                } else {
+                    title.collide(point).map(|_| AnnotationCollideDesc::Move)
+                }
+            }
+        }
+    }
+}
+
+pub struct AnnotationDraw<'a> {
+    annotation: &'a Annotation,
+    pub highlighted: bool,
+}
+
+impl<'a> AnnotationDraw<'a> {
+    pub fn new(annotation: &'a Annotation) -> Self {
+        AnnotationDraw {
+            annotation,
+            highlighted: false,
+        }
+    }
+}
+
+impl Draw for AnnotationDraw<'_> {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        let color = if self.highlighted { rgba(253, 203, 110, 1.0) } else { rgba(45, 52, 54, 1.0) };
+        match self.annotation {
+            Annotation::Label { text, position } => {
+                draw_text(text, 16, [position[0], position[1] + 10.], color, ctx);
+            }
+            Annotation::Frame { name, rect } => {
+                Rectangle::new_border(color, 1.)
+                    .draw_tri(*rect, &Default::default(), ctx.c.transform, ctx.g);
+                Rectangle::new(rgba(99, 110, 114, 0.3))
+                    .draw_tri([rect[0], rect[1], rect[2], FRAME_TITLE_HEIGHT], &Default::default(), ctx.c.transform, ctx.g);
+                Rectangle::new(color)
+                    .draw_tri([rect[0] + rect[2] - RESIZE_HANDLE_SIZE, rect[1] + rect[3] - RESIZE_HANDLE_SIZE, RESIZE_HANDLE_SIZE, RESIZE_HANDLE_SIZE], &Default::default(), ctx.c.transform, ctx.g);
+                draw_text(name, 14, [rect[0] + 5., rect[1] + FRAME_TITLE_HEIGHT / 2.], color, ctx);
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::container::Container;
+use crate::net_builder::NetBuilder;
+use crate::netlist::{CellKind, Design, Signal};
+
+// the ISCAS BENCH format: INPUT(a), OUTPUT(y) and y = NAND(a, b) lines
+pub fn import_bench(text: &str) -> Result<Container, String> {
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut gates = Vec::new();
+    let mut unsupported = Vec::new();
+
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = || format!("line {}: cannot read {:?}", i + 1, line);
+        let (output, call) = match line.split_once('=') {
+            Some((output, call)) => (Some(output.trim()), call.trim()),
+            None => (None, line),
+        };
+        let (function, arguments) = call.strip_suffix(')').and_then(|c| c.split_once('(')).ok_or_else(error)?;
+        let arguments: Vec<String> = arguments.split(',').map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect();
+        let function = function.trim().to_uppercase();
+
+        match (output, function.as_str(), &arguments[..]) {
+            (None, "INPUT", [name]) => inputs.push(name.clone()),
+            (None, "OUTPUT", [name]) => outputs.push(name.clone()),
+            (Some(output), "AND" | "NAND" | "OR" | "NOR" | "XOR" | "XNOR", [_, ..]) => {
+                gates.push((function.to_lowercase(), arguments, output.to_string()))
+            }
+            (Some(output), "NOT", [_]) => gates.push(("not".into(), arguments, output.to_string())),
+            // a buffer is an and gate with one input
+            (Some(output), "BUF" | "BUFF", [_]) => gates.push(("and".into(), arguments, output.to_string())),
+            (Some(output), _, _) => unsupported.push(format!("{} ({})", output, function)),
+            _ => return Err(error()),
+        }
+    }
+    if !unsupported.is_empty() {
+        return Err(format!("unsupported gates: {}", unsupported.join(", ")));
+    }
+
+    let mut builder = NetBuilder::new(&inputs)?;
+    for (kind, arguments, output) in gates {
+        builder.gate(&kind, &arguments, &output)?;
+    }
+    builder.finish(&outputs)
+}
+
+// BENCH has no hierarchy and no constants, clocks become inputs
+pub fn to_bench(container: &Container, name: &str) -> Result<String, String> {
+    let design = Design::new(container, name)?;
+    let module = design.modules.last().unwrap();
+    let mut out = format!("# {}\n", module.name);
+    let signal = |signal: &Signal| match signal {
+        Signal::Net(net) => Ok(net.clone()),
+        Signal::Constant(_) => Err("BENCH has no constants, connect every input and replace the 0 and 1 boxes".to_string()),
+    };
+
+    for input in module.inputs.iter().chain(&module.clocks) {
+        out.push_str(&format!("INPUT({})\n", input));
+    }
+    for (port, _) in &module.outputs {
+        out.push_str(&format!("OUTPUT({})\n", port));
+    }
+    out.push('\n');
+    for cell in &module.cells {
+        let gate = match cell.kind {
+            CellKind::Gate(gate) => gate,
+            CellKind::Module(_) => return Err(format!("BENCH has no sub-circuits, {} is one", cell.name)),
+        };
+        let inputs: Vec<String> = cell.inputs.iter().map(|(_, s)| signal(s)).collect::<Result<_, _>>()?;
+        let (_, first) = &cell.outputs[0];
+        out.push_str(&format!("{} = {}({})\n", first, gate.to_uppercase(), inputs.join(", ")));
+        for (_, net) in &cell.outputs[1..] {
+            out.push_str(&format!("{} = BUFF({})\n", net, first));
+        }
+    }
+    for (port, value) in &module.outputs {
+        out.push_str(&format!("{} = BUFF({})\n", port, signal(value)?));
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::netlist::fixtures::evaluate;
+
+    fn c17(i: &[bool]) -> Vec<bool> {
+        let nand = |a: bool, b: bool| !(a && b);
+        let (n10, n11) = (nand(i[0], i[2]), nand(i[2], i[3]));
+        let (n16, n19) = (nand(i[1], n11), nand(n11, i[4]));
+        vec![nand(n10, n16), nand(n16, n19)]
+    }
+
+    fn assert_c17(container: &mut Container) {
+        for v in 0..32 {
+            let inputs: Vec<bool> = (0..5).map(|i| v >> i & 1 == 1).collect();
+            assert_eq!(evaluate(container, &inputs), c17(&inputs), "{:?}", inputs);
+        }
+    }
+
+    #[test]
+    fn imports_c17() {
+        let mut container = import_bench(include_str!("../tests/data/c17.bench")).unwrap();
+        assert_eq!(container.graph.node_count(), 8);
+        assert_c17(&mut container);
+    }
+
+    #[test]
+    fn exported_circuit_imports_again() {
+        let container = import_bench(include_str!("../tests/data/c17.bench")).unwrap();
+        let text = to_bench(&container, "c17").unwrap();
+        assert_c17(&mut import_bench(&text).unwrap());
+    }
+
+    #[test]
+    fn reports_flip_flops_and_missing_drivers() {
+        let error = import_bench("INPUT(a)\nOUTPUT(q)\nq = DFF(d)\nd = NOT(a)\n").unwrap_err();
+        assert!(error.contains("q (DFF)"), "{}", error);
+        let error = import_bench("INPUT(a)\nOUTPUT(y)\ny = AND(a, b)\n").unwrap_err();
+        assert!(error.contains("without a driver: b"), "{}", error);
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+
+use crate::container::Container;
+use crate::function_box::FunctionBox;
+use crate::net_builder::NetBuilder;
+use crate::netlist::{CellKind, Design, Netlist, Signal};
+
+// a .names command, each row of the cover is an input pattern of 0, 1 and - with the output value
+struct Names {
+    inputs: Vec<String>,
+    output: String,
+    rows: Vec<(String, char)>,
+}
+
+struct Subckt {
+    model: String,
+    connections: Vec<(String, String)>,
+}
+
+#[derive(Default)]
+struct Model {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    names: Vec<Names>,
+    subckts: Vec<Subckt>,
+}
+
+// lines without comments, continued lines joined, with their line number
+fn logical_lines(text: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap();
+        let (line, continued) = match line.trim_end().strip_suffix('\\') {
+            Some(line) => (line, true),
+            None => (line, false),
+        };
+        let (_, joined) = current.get_or_insert_with(|| (i + 1, String::new()));
+        joined.push(' ');
+        joined.push_str(line);
+        if !continued {
+            let (number, joined) = current.take().unwrap();
+            if !joined.trim().is_empty() {
+                lines.push((number, joined.trim().to_string()));
+            }
+        }
+    }
+    lines.extend(current);
+    lines
+}
+
+fn parse(text: &str) -> Result<Vec<(String, Model)>, String> {
+    let mut models: Vec<(String, Model)> = Vec::new();
+    let mut unsupported = Vec::new();
+
+    for (number, line) in logical_lines(text) {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let error = |message: &str| format!("line {}: {}", number, message);
+        if !tokens[0].starts_with('.') {
+            match models.last_mut().and_then(|(_, m)| m.names.last_mut()) {
+                Some(names) => {
+                    let (pattern, output) = match tokens[..] {
+                        [output] if names.inputs.is_empty() => ("", output),
+                        [pattern, output] if pattern.len() == names.inputs.len() => (pattern, output),
+                        _ => return Err(error("the row doesn't match the inputs of .names")),
+                    };
+                    if !pattern.chars().all(|c| "01-".contains(c)) || (output != "0" && output != "1") {
+                        return Err(error("a row has to be 0, 1 and - followed by 0 or 1"));
+                    }
+                    names.rows.push((pattern.to_string(), output.chars().next().unwrap()));
+                }
+                None => return Err(error("a cover row outside of .names")),
+            }
+            continue;
+        }
+
+        if tokens[0] == ".model" {
+            models.push((tokens.get(1).unwrap_or(&"").to_string(), Model::default()));
+            continue;
+        }
+        if models.is_empty() {
+            models.push((String::new(), Model::default()));
+        }
+        let model = &mut models.last_mut().unwrap().1;
+        let arguments = tokens[1..].iter().map(|t| t.to_string());
+        match tokens[0] {
+            ".inputs" => model.inputs.extend(arguments),
+            ".outputs" => model.outputs.extend(arguments),
+            ".names" => {
+                let mut inputs: Vec<String> = arguments.collect();
+                let output = inputs.pop().ok_or_else(|| error(".names without an output"))?;
+                model.names.push(Names { inputs, output, rows: Vec::new() });
+            }
+            ".subckt" => {
+                let connections = tokens[2..].iter()
+                    .map(|c| c.split_once('=').map(|(formal, actual)| (formal.to_string(), actual.to_string())))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| error("a .subckt connection has to be formal=actual"))?;
+                model.subckts.push(Subckt { model: tokens.get(1).unwrap_or(&"").to_string(), connections });
+            }
+            ".end" => {}
+            command => unsupported.push(format!("{} (line {})", command, number)),
+        }
+    }
+    if !unsupported.is_empty() {
+        return Err(format!("unsupported commands: {}", unsupported.join(", ")));
+    }
+    if models.is_empty() {
+        return Err("no model".into());
+    }
+    Ok(models)
+}
+
+// the first model is the top level, the others can be instantiated with .subckt
+pub fn import_blif(text: &str) -> Result<Container, String> {
+    let models = parse(text)?;
+    let mut imported = HashMap::new();
+    build(&models, &models[0].0, &mut imported, &mut Vec::new())
+}
+
+fn build(models: &[(String, Model)], name: &str, imported: &mut HashMap<String, Container>, stack: &mut Vec<String>) -> Result<Container, String> {
+    if let Some(container) = imported.get(name) {
+        return Ok(container.clone());
+    }
+    if stack.iter().any(|s| s == name) {
+        return Err(format!("model {} instantiates itself", name));
+    }
+    let model = &models.iter().find(|(n, _)| n == name).ok_or_else(|| format!("unknown model {}", name))?.1;
+    stack.push(name.into());
+
+    let mut builder = NetBuilder::new(&model.inputs)?;
+    for names in &model.names {
+        cover(&mut builder, names).map_err(|e| format!("model {}: {}", name, e))?;
+    }
+    for (i, subckt) in model.subckts.iter().enumerate() {
+        let circuit = build(models, &subckt.model, imported, stack)?;
+        let sub_model = &models.iter().find(|(n, _)| *n == subckt.model).unwrap().1;
+        if let Some((formal, _)) = subckt.connections.iter().find(|(f, _)| !sub_model.inputs.contains(f) && !sub_model.outputs.contains(f)) {
+            return Err(format!("model {}: {} has no port {}", name, subckt.model, formal));
+        }
+        let actual = |formal: &String| subckt.connections.iter().find(|(f, _)| f == formal).map(|(_, a)| a.clone());
+        let inputs = sub_model.inputs.iter()
+            .map(|f| actual(f).ok_or_else(|| format!("model {}: input {} of {} is not connected", name, f, subckt.model)))
+            .collect::<Result<Vec<_>, _>>()?;
+        // unconnected outputs get a name nobody uses
+        let outputs: Vec<String> = sub_model.outputs.iter()
+            .map(|f| actual(f).unwrap_or_else(|| format!("{} {} {}", subckt.model, i, f)))
+            .collect();
+        builder.add(FunctionBox::new_circuit(&subckt.model, [0., 0.], circuit), &inputs, &outputs)?;
+    }
+
+    let container = builder.finish(&model.outputs).map_err(|e| format!("model {}: {}", name, e))?;
+    stack.pop();
+    imported.insert(name.into(), container.clone());
+    Ok(container)
+}
+
+// the cover as a sum of products, an and gate per row and an or gate over the rows
+fn cover(builder: &mut NetBuilder, names: &Names) -> Result<(), String> {
+    // the rows give either the on set or the off set
+    let on = names.rows.first().is_none_or(|(_, value)| *value == '1');
+    if names.rows.iter().any(|(_, value)| (*value == '1') != on) {
+        return Err(format!("the cover of {} mixes rows for 0 and 1", names.output));
+    }
+    let cubes: Vec<Vec<(&String, bool)>> = names.rows.iter()
+        .map(|(pattern, _)| names.inputs.iter().zip(pattern.chars())
+            .filter(|&(_, c)| c != '-')
+            .map(|(input, c)| (input, c == '1'))
+            .collect())
+        .collect();
+
+    if cubes.is_empty() {
+        return builder.constant(false, &names.output);
+    }
+    if cubes.iter().any(|cube| cube.is_empty()) {
+        return builder.constant(on, &names.output);
+    }
+    if let [cube] = &cubes[..] {
+        return match cube[..] {
+            // a buffer is an and gate with one input
+            [(input, positive)] if positive == on => builder.gate("and", std::slice::from_ref(input), &names.output),
+            [(input, _)] => builder.gate("not", std::slice::from_ref(input), &names.output),
+            _ => {
+                let literals = literals(builder, cube)?;
+                builder.gate(if on { "and" } else { "nand" }, &literals, &names.output)
+            }
+        };
+    }
+    let mut products = Vec::new();
+    for cube in &cubes {
+        let literals = literals(builder, cube)?;
+        products.push(match &literals[..] {
+            [literal] => literal.clone(),
+            _ => builder.internal_gate("and", &literals)?,
+        });
+    }
+    builder.gate(if on { "or" } else { "nor" }, &products, &names.output)
+}
+
+fn literals(builder: &mut NetBuilder, cube: &[(&String, bool)]) -> Result<Vec<String>, String> {
+    cube.iter()
+        .map(|&(input, positive)| if positive { Ok(input.clone()) } else { builder.not(input) })
+        .collect()
+}
+
+// the rows for which a gate is 1
+fn gate_cover(gate: &str, inputs: usize) -> Result<Vec<String>, String> {
+    let all = |c: char| std::iter::repeat_n(c, inputs).collect::<String>();
+    let one_of = |c: char, rest: char| (0..inputs)
+        .map(|i| (0..inputs).map(|j| if i == j { c } else { rest }).collect())
+        .collect();
+    let parity = |odd: bool| (0..1usize << inputs)
+        .filter(|i| (i.count_ones() % 2 == 1) == odd)
+        .map(|i| (0..inputs).map(|j| if i >> (inputs - 1 - j) & 1 == 1 { '1' } else { '0' }).collect())
+        .collect();
+    Ok(match gate {
+        "and" => vec![all('1')],
+        "nand" => one_of('0', '-'),
+        "or" => one_of('1', '-'),
+        "nor" => vec![all('0')],
+        "xor" => parity(true),
+        "xnor" => parity(false),
+        "not" => vec!["0".into()],
+        _ => return Err(format!("no BLIF for the gate {:?}", gate)),
+    })
+}
+
+// constants are the nets $false and $true like yosys writes them
+fn signal(signal: &Signal) -> &str {
+    match signal {
+        Signal::Net(net) => net,
+        Signal::Constant(false) => "$false",
+        Signal::Constant(true) => "$true",
+    }
+}
+
+// one model per (sub-)circuit, the top model comes first
+pub fn to_blif(container: &Container, name: &str) -> Result<String, String> {
+    let design = Design::new(container, name)?;
+    let mut out = String::new();
+    for (i, module) in design.modules.iter().rev().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_model(&mut out, module)?;
+    }
+    Ok(out)
+}
+
+fn write_model(out: &mut String, module: &Netlist) -> Result<(), String> {
+    out.push_str(&format!(".model {}\n", module.name));
+    let inputs: Vec<&str> = module.inputs.iter().chain(&module.clocks).map(String::as_str).collect();
+    if !inputs.is_empty() {
+        out.push_str(&format!(".inputs {}\n", inputs.join(" ")));
+    }
+    let outputs: Vec<&str> = module.outputs.iter().map(|(port, _)| port.as_str()).collect();
+    if !outputs.is_empty() {
+        out.push_str(&format!(".outputs {}\n", outputs.join(" ")));
+    }
+
+    let used: Vec<&Signal> = module.cells.iter().flat_map(|c| c.inputs.iter().map(|(_, s)| s))
+        .chain(module.outputs.iter().map(|(_, s)| s))
+        .collect();
+    if used.contains(&&Signal::Constant(false)) {
+        out.push_str(".names $false\n");
+    }
+    if used.contains(&&Signal::Constant(true)) {
+        out.push_str(".names $true\n1\n");
+    }
+
+    for cell in &module.cells {
+        match &cell.kind {
+            CellKind::Gate(gate) => {
+                let inputs: Vec<&str> = cell.inputs.iter().map(|(_, s)| signal(s)).collect();
+                let (_, first) = &cell.outputs[0];
+                out.push_str(&format!(".names {} {}\n", inputs.join(" "), first));
+                for row in gate_cover(gate, inputs.len())? {
+                    out.push_str(&format!("{} 1\n", row));
+                }
+                for (_, net) in &cell.outputs[1..] {
+                    out.push_str(&format!(".names {} {}\n1 1\n", first, net));
+                }
+            }
+            CellKind::Module(model) => {
+                let connections: Vec<String> = cell.inputs.iter().map(|(port, s)| format!("{}={}", port, signal(s)))
+                    .chain(cell.outputs.iter().map(|(port, net)| format!("{}={}", port, net)))
+                    .collect();
+                out.push_str(&format!(".subckt {} {}\n", model, connections.join(" ")));
+            }
+        }
+    }
+    for (port, value) in &module.outputs {
+        out.push_str(&format!(".names {} {}\n1 1\n", signal(value), port));
+    }
+    out.push_str(".end\n");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::netlist::fixtures::evaluate;
+
+    fn assert_full_adder(container: &mut Container) {
+        for v in 0..8 {
+            let inputs: Vec<bool> = (0..3).map(|i| v >> i & 1 == 1).collect();
+            let count = inputs.iter().filter(|&&x| x).count();
+            assert_eq!(evaluate(container, &inputs), vec![count % 2 == 1, count >= 2], "{:?}", inputs);
+        }
+    }
+
+    #[test]
+    fn imports_full_adder_with_sub_models() {
+        let mut container = import_blif(include_str!("../tests/data/full_adder.blif")).unwrap();
+        assert_eq!(container.input_names(), vec!["a", "b", "cin"]);
+        assert_eq!(container.output_names(), vec!["sum", "cout"]);
+        assert_eq!(container.graph.raw_nodes().iter().filter(|n| n.weight.name == "circuit").count(), 2);
+        assert_full_adder(&mut container);
+    }
+
+    #[test]
+    fn exported_circuit_imports_again() {
+        let container = import_blif(include_str!("../tests/data/full_adder.blif")).unwrap();
+        let text = to_blif(&container, "full_adder").unwrap();
+        assert!(text.starts_with(".model full_adder\n"), "{}", text);
+        assert_full_adder(&mut import_blif(&text).unwrap());
+    }
+
+    #[test]
+    fn imports_off_set_covers_and_constants() {
+        let mut container = import_blif(".model m\n.inputs a b\n.outputs y one\n.names a b y\n11 0\n.names one\n1\n.end\n").unwrap();
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert_eq!(evaluate(&mut container, &[a, b]), vec![!(a && b), true]);
+        }
+        let error = import_blif(".model m\n.inputs d clk\n.outputs q\n.latch d q re clk 0\n.end\n").unwrap_err();
+        assert!(error.contains(".latch (line 4)"), "{}", error);
+    }
+}
@@ -1,12 +1,14 @@
-use crate::game::{Update, State, Collide, Draw, DrawCtx, PosF, Entity};
+use crate::game::{Update, State, Collide, Draw, DrawCtx, PosF};
 use crate::ui::{rgba, draw_text_centered};
 use graphics::Rectangle;
+use graphics::types::FontSize;
 use crate::util::rect_center;
 
 #[derive(Debug)]
 pub struct Button {
     rect: [f64; 4],
     text: String,
+    font_size: FontSize,
     pressed: bool,
     was_pressed: bool,
     highlighted: bool,
@@ -14,9 +16,14 @@ pub struct Button {
 
 impl Button {
     pub fn new(text: String, pos: PosF) -> Self {
+        Button::new_with_rect(text, [pos[0], pos[1], 70., 35.], 20)
+    }
+
+    pub fn new_with_rect(text: String, rect: [f64; 4], font_size: FontSize) -> Self {
         Button {
-            rect: [pos[0], pos[1], 70., 35.],
+            rect,
             text,
+            font_size,
             was_pressed: false,
             pressed: false,
             highlighted: false,
@@ -42,8 +49,6 @@ impl Collide for Button {
     }
 }
 
-impl Entity for Button {}
-
 impl Update for Button {
     fn update(&mut self, state: &State) {
         if state.mouse_button1_pressed {
@@ -69,6 +74,6 @@ impl Draw for Button {
         let mut rectangle = Rectangle::new_round_border(rgba(45, 52, 54, 1.0), 2., 2.);
         rectangle = rectangle.color(if self.highlighted { rgba(253, 203, 110, 1.0) } else { rgba(178, 190, 195, 1.0) });
         rectangle.draw_tri(self.rect, &Default::default(), ctx.c.transform, ctx.g);
-        draw_text_centered(&self.text, 20, rect_center(self.rect), rgba(45, 52, 54, 1.0), ctx);
+        draw_text_centered(&self.text, self.font_size, rect_center(self.rect), rgba(45, 52, 54, 1.0), ctx);
     }
 }
\ No newline at end of file
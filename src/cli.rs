@@ -0,0 +1,127 @@
+use std::path::{Path, PathBuf};
+
+use crate::equivalence::{check_equivalence, Equivalence};
+use crate::export::export;
+use crate::files::{load_container, load_file, save_container};
+use crate::statistics::statistics;
+use crate::test_vectors::TestVectors;
+use crate::synthesis::synthesize_text;
+
+pub const USAGE: &str = "usage: rustcpu [CIRCUIT]
+       rustcpu --export OUTPUT CIRCUIT   export to the format given by the extension of OUTPUT
+       rustcpu --synthesize OUTPUT EXPRESSION
+                                         build a circuit of nand gates, save it as .json or export it
+       rustcpu --equivalent CIRCUIT CIRCUIT
+                                         check that two circuits compute the same function
+       rustcpu --stats CIRCUIT           print the gate counts and the critical path
+       rustcpu test CIRCUIT VECTORS      simulate the test vectors, fails on the first mismatch
+
+OUTPUT can be Verilog (.v), VHDL (.vhd), Logisim (.circ), BLIF (.blif), ISCAS BENCH (.bench),
+Graphviz (.dot), an image of the schematic (.svg, .png) or the truth table (.csv, .md)
+
+CIRCUIT is a circuit file, a netlist written by `yosys write_json`, a Logisim .circ file
+or a BLIF or ISCAS BENCH netlist, a .csv or .md truth table is synthesized
+
+EXPRESSION is like `a & !b | c`, several outputs are written as `sum = a ^ b; carry = a & b`.
+A truth table with the inputs as the first columns can be given instead, - reads it from stdin
+
+VECTORS is a text file starting with the pins like `a b -> sum carry` followed by lines like
+`0 1 -> 1 0`. - keeps an input, x doesn't check an output. A line waits until the outputs
+settle, `@ 10` runs 10 simulation ticks instead and `@ 2 cycles` two cycles of the clock.
+A clock with the period p is 0 for p ticks and 1 for p ticks, so a cycle is 2p ticks";
+
+pub enum Command {
+    // opens the editor window, optionally with a circuit
+    Open(Option<PathBuf>),
+    Export { circuit: PathBuf, output: PathBuf },
+    Synthesize { expression: String, output: PathBuf },
+    Equivalent(PathBuf, PathBuf),
+    Statistics(PathBuf),
+    Test { circuit: PathBuf, vectors: PathBuf },
+}
+
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    match args {
+        [] => Ok(Command::Open(None)),
+        [flag, output, circuit] if flag == "--export" => Ok(Command::Export { circuit: circuit.into(), output: output.into() }),
+        [flag, output, expression] if flag == "--synthesize" => Ok(Command::Synthesize { expression: expression.clone(), output: output.into() }),
+        [flag, a, b] if flag == "--equivalent" => Ok(Command::Equivalent(a.into(), b.into())),
+        [flag, circuit] if flag == "--stats" => Ok(Command::Statistics(circuit.into())),
+        [command, circuit, vectors] if command == "test" => Ok(Command::Test { circuit: circuit.into(), vectors: vectors.into() }),
+        [circuit] if !circuit.starts_with("--") => Ok(Command::Open(Some(circuit.into()))),
+        _ => Err(format!("invalid arguments {:?}", args)),
+    }
+}
+
+// runs a command without a window, returns the exit code
+pub fn run(command: Command) -> i32 {
+    let result = match command {
+        Command::Open(_) => unreachable!("opening needs the window"),
+        Command::Export { circuit, output } => load_file(&circuit)
+            .and_then(|loaded| {
+                let mut warnings = loaded.warnings;
+                warnings.extend(export(&loaded.container, &output)?);
+                Ok(warnings)
+            })
+            .map(|warnings| {
+                for warning in warnings {
+                    eprintln!("warning: {}", warning);
+                }
+                println!("Exported {} to {}", circuit.display(), output.display())
+            }),
+        Command::Synthesize { expression, output } => synthesize(&expression, &output),
+        Command::Equivalent(a, b) => equivalent(&a, &b),
+        Command::Test { circuit, vectors } => run_test(&circuit, &vectors),
+        Command::Statistics(circuit) => load_container(&circuit).map(|container| {
+            statistics(&container).report(&container).iter().for_each(|line| println!("{}", line))
+        }),
+    };
+    match result {
+        Ok(()) => 0,
+        Err(e) => {
+            eprintln!("{}", e);
+            1
+        }
+    }
+}
+
+fn synthesize(expression: &str, output: &Path) -> Result<(), String> {
+    let text = if expression == "-" {
+        std::io::read_to_string(std::io::stdin()).map_err(|e| format!("Cannot read stdin: {}", e))?
+    } else {
+        expression.to_string()
+    };
+    let container = synthesize_text(&text)?;
+    if output.extension().is_some_and(|e| e == "json") {
+        save_container(output, &container, false)?;
+    } else {
+        for warning in export(&container, output)? {
+            eprintln!("warning: {}", warning);
+        }
+    }
+    println!("Synthesized {} nand gates to {}", container.graph.raw_nodes().iter().filter(|n| n.weight.name == "nand").count(), output.display());
+    Ok(())
+}
+
+// a difference is an error so scripts can check the exit code
+fn equivalent(a: &Path, b: &Path) -> Result<(), String> {
+    let equivalence = check_equivalence(&load_container(a)?, &load_container(b)?)?;
+    let report = equivalence.report(&a.display().to_string(), &b.display().to_string());
+    match equivalence {
+        Equivalence::Equal => {
+            println!("{}", report);
+            Ok(())
+        }
+        Equivalence::Different { .. } => Err(report),
+    }
+}
+
+fn run_test(circuit: &Path, vectors: &Path) -> Result<(), String> {
+    let container = load_container(circuit)?;
+    let text = std::fs::read_to_string(vectors).map_err(|e| format!("Cannot open {}: {}", vectors.display(), e))?;
+    let steps = TestVectors::parse(&text)
+        .and_then(|v| v.run(&container))
+        .map_err(|e| format!("{} failed {}: {}", circuit.display(), vectors.display(), e))?;
+    println!("{} passed {} test vectors of {}", circuit.display(), steps, vectors.display());
+    Ok(())
+}
@@ -0,0 +1,141 @@
+use std::path::PathBuf;
+
+use crate::connector::ConnectorDirection;
+use crate::files::load_container;
+use crate::function_box::FunctionBox;
+use crate::game::PosF;
+
+pub const CIRCUIT_LIBRARY_DIR: &str = "circuits";
+
+pub struct ComponentKind {
+    pub name: &'static str,
+    pub category: &'static str,
+    pub inputs: &'static [&'static str],
+    pub outputs: &'static [&'static str],
+    pub function: fn(&[bool]) -> bool,
+    // whether the number of inputs (outputs) can be changed in the inspector
+    pub resizable_inputs: bool,
+    pub resizable_outputs: bool,
+    // kind specific parameters with their default values
+    pub params: &'static [(&'static str, &'static str)],
+}
+
+const fn gate(name: &'static str, inputs: &'static [&'static str], outputs: &'static [&'static str], function: fn(&[bool]) -> bool) -> ComponentKind {
+    ComponentKind { name, category: "Gates", inputs, outputs, function, resizable_inputs: inputs.len() > 1, resizable_outputs: false, params: &[] }
+}
+
+pub const COMPONENT_KINDS: &[ComponentKind] = &[
+    ComponentKind { name: "input", category: "I/O", inputs: &[], outputs: &["1", "2", "3"], function: |_| false, resizable_inputs: false, resizable_outputs: true, params: &[] },
+    ComponentKind { name: "output", category: "I/O", inputs: &["1", "2", "3"], outputs: &[], function: |_| false, resizable_inputs: true, resizable_outputs: false, params: &[] },
+    ComponentKind { name: "0", category: "I/O", inputs: &[], outputs: &["0"], function: |_| false, resizable_inputs: false, resizable_outputs: false, params: &[] },
+    ComponentKind { name: "1", category: "I/O", inputs: &[], outputs: &["1"], function: |_| true, resizable_inputs: false, resizable_outputs: false, params: &[] },
+    ComponentKind { name: "clock", category: "I/O", inputs: &[], outputs: &["clk"], function: |_| false, resizable_inputs: false, resizable_outputs: false, params: &[("period", "10")] },
+    gate("nand", &["i1", "i2"], &["nand"], |i| !i.iter().all(|&x| x)),
+    gate("and", &["i1", "i2"], &["and"], |i| i.iter().all(|&x| x)),
+    gate("or", &["i1", "i2"], &["or"], |i| i.iter().any(|&x| x)),
+    gate("nor", &["i1", "i2"], &["nor"], |i| !i.iter().any(|&x| x)),
+    gate("xor", &["i1", "i2"], &["xor"], |i| i.iter().filter(|&&x| x).count() % 2 == 1),
+    gate("xnor", &["i1", "i2"], &["xnor"], |i| i.iter().filter(|&&x| x).count() % 2 == 0),
+    gate("not", &["i"], &["not"], |i| !i[0]),
+    // the number of inputs (outputs) is the address (data) width
+    ComponentKind { name: "rom", category: "Memory", inputs: &["a1", "a2", "a3", "a4"], outputs: &["d1", "d2", "d3", "d4", "d5", "d6", "d7", "d8"], function: |_| false, resizable_inputs: true, resizable_outputs: true, params: &[("file", "")] },
+];
+
+pub fn find_kind(name: &str) -> Option<&'static ComponentKind> {
+    COMPONENT_KINDS.iter().find(|k| k.name == name)
+}
+
+impl ComponentKind {
+    pub fn create(&self, position: PosF) -> FunctionBox {
+        let mut function_box = FunctionBox::new(self.name, position,
+                                                self.inputs.iter().map(|&x| x.into()).collect(),
+                                                self.outputs.iter().map(|&x| x.into()).collect());
+        function_box.params = self.params.iter().map(|&(k, v)| (k.into(), v.into())).collect();
+        function_box
+    }
+
+    // the inspector rows changing the number of inputs and outputs, a rom has bus widths
+    pub fn size_labels(&self) -> (&'static str, &'static str) {
+        match self.name {
+            "rom" => ("Address", "Data"),
+            _ => ("Inputs", "Outputs"),
+        }
+    }
+
+    // name for a connector added in the inspector, following the naming of the default connectors
+    pub fn pin_name(&self, direction: ConnectorDirection, idx: usize) -> String {
+        match (self.name, direction) {
+            ("input", _) | ("output", _) => format!("{}", idx + 1),
+            ("rom", ConnectorDirection::Input) => format!("a{}", idx + 1),
+            ("rom", ConnectorDirection::Output) => format!("d{}", idx + 1),
+            (_, ConnectorDirection::Input) => format!("i{}", idx + 1),
+            (_, ConnectorDirection::Output) => format!("o{}", idx + 1),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub enum ComponentSource {
+    Builtin(&'static ComponentKind),
+    SubCircuit(PathBuf),
+}
+
+#[derive(Clone)]
+pub struct ComponentEntry {
+    pub name: String,
+    pub category: String,
+    pub source: ComponentSource,
+}
+
+impl ComponentEntry {
+    pub fn create(&self, position: PosF) -> Result<FunctionBox, String> {
+        match &self.source {
+            ComponentSource::Builtin(kind) => Ok(kind.create(position)),
+            ComponentSource::SubCircuit(path) => {
+                let container = load_container(path)?;
+                Ok(FunctionBox::new_circuit(&self.name, position, container))
+            }
+        }
+    }
+}
+
+// the editor is NAND-first, the other gates are only there for imported netlists and their export
+fn placeable(kind: &ComponentKind) -> bool {
+    kind.category != "Gates" || kind.name == "nand"
+}
+
+// the builtin component kinds that can be placed followed by the circuits saved in the library directory
+pub fn available_components() -> Vec<ComponentEntry> {
+    let mut sub_circuits: Vec<PathBuf> = std::fs::read_dir(CIRCUIT_LIBRARY_DIR).into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|e| e == "json"))
+        .collect();
+    sub_circuits.sort();
+
+    COMPONENT_KINDS.iter()
+        .filter(|kind| placeable(kind))
+        .map(|kind| ComponentEntry {
+            name: kind.name.into(),
+            category: kind.category.into(),
+            source: ComponentSource::Builtin(kind),
+        })
+        .chain(sub_circuits.into_iter().map(|path| ComponentEntry {
+            name: path.file_stem().unwrap().to_string_lossy().into(),
+            category: "Circuits".into(),
+            source: ComponentSource::SubCircuit(path),
+        }))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_nand_gates_are_offered() {
+        let gates: Vec<String> = available_components().into_iter().filter(|e| e.category == "Gates").map(|e| e.name).collect();
+        assert_eq!(gates, vec!["nand"]);
+        assert!(find_kind("xor").is_some());
+    }
+}
@@ -1,18 +1,20 @@
 use std::fmt::{Display, Formatter};
 use std::fmt;
 
-#[derive(Debug, Clone, Copy, PartialEq, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ConnectorDirection {
     Input,
     Output,
 }
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+#[derive(Debug, Clone)]
 pub struct Connector {
     pub name: String,
     pub direction: ConnectorDirection,
     pub idx: usize,
     pub state: bool,
+    // its value changes are recorded into the waveform
+    pub probe: bool,
 }
 
 impl Connector {
@@ -22,6 +24,7 @@ impl Connector {
             direction,
             idx,
             state: false,
+            probe: false,
         }
     }
 }
@@ -1,42 +1,107 @@
 use std::ops::IndexMut;
 
 use petgraph::{Direction, Graph};
-use petgraph::graph::NodeIndex;
+use petgraph::graph::{EdgeIndex, NodeIndex};
+use petgraph::visit::EdgeRef;
 
-use crate::connector::ConnectorDirection;
+use crate::annotation::{Annotation, FRAME_TITLE_HEIGHT};
+use crate::component::find_kind;
+use crate::connector::{Connector, ConnectorDirection};
 use crate::function_box::FunctionBox;
-use crate::function_box_draw::output_input_pair;
-use serde::{Serialize, Deserialize};
+use crate::function_box_draw::{FunctionBoxDraw, output_input_pair};
+use crate::game::PosF;
+use vecmath::{vec2_add, vec2_sub};
 
 pub type FunctionBoxRef = NodeIndex<u32>;
 pub type ConnectorRef = usize;
-pub type FBGraph = Graph<FunctionBox, Vec<(ConnectorRef, ConnectorRef)>>;
+pub type FBGraph = Graph<FunctionBox, Vec<Wire>>;
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wire {
+    pub output: ConnectorRef,
+    pub input: ConnectorRef,
+    // user placed corners of the wire, from the output towards the input
+    pub bend_points: Vec<PosF>,
+}
+
+impl Wire {
+    pub fn new(output: ConnectorRef, input: ConnectorRef) -> Wire {
+        Wire {
+            output,
+            input,
+            bend_points: Vec::new(),
+        }
+    }
+}
+
+// (edge, index of the wire in the edge weight, index of the bend point)
+pub type BendPointRef = (EdgeIndex<u32>, usize, usize);
+
+#[derive(Debug, Clone, Default)]
 pub struct Container {
-    pub graph: FBGraph
+    pub graph: FBGraph,
+    pub annotations: Vec<Annotation>,
+}
+
+// record labels give braces, bars and angle brackets a meaning
+fn dot_escape(text: &str) -> String {
+    text.chars().flat_map(|c| match c {
+        '\\' | '"' | '{' | '}' | '|' | '<' | '>' => vec!['\\', c],
+        _ => vec![c],
+    }).collect()
+}
+
+fn rect_contains(outer: [f64; 4], inner: [f64; 4]) -> bool {
+    inner[0] >= outer[0] && inner[1] >= outer[1]
+        && inner[0] + inner[2] <= outer[0] + outer[2]
+        && inner[1] + inner[3] <= outer[1] + outer[3]
 }
 
 
 impl Container {
-    pub(crate) fn new() -> Container {
+    pub fn new() -> Container {
         Container {
-            graph: Graph::new()
+            graph: Graph::new(),
+            annotations: Vec::new(),
         }
     }
 
-    pub(crate) fn add(&mut self, function_box: FunctionBox) -> FunctionBoxRef {
+    // boxes without an id or with one already taken get a new id like "nand3"
+    pub fn add(&mut self, mut function_box: FunctionBox) -> FunctionBoxRef {
+        if function_box.id.is_empty() || self.find_id(&function_box.id).is_some() {
+            function_box.id = (1..)
+                .map(|i| format!("{}{}", function_box.name, i))
+                .find(|id| self.find_id(id).is_none())
+                .unwrap();
+        }
         self.graph.add_node(function_box)
     }
 
+    pub fn find_id(&self, id: &str) -> Option<FunctionBoxRef> {
+        self.graph.node_indices().find(|&n| self.graph[n].id == id)
+    }
+
+    // removes the box and all wires from and to it, the last box takes over its index
+    pub(crate) fn remove(&mut self, function_box: FunctionBoxRef) -> Option<FunctionBox> {
+        self.graph.remove_node(function_box)
+    }
+
+    // the output connector driving the given input connector
+    pub fn input_driver(&self, input: (FunctionBoxRef, ConnectorRef)) -> Option<(FunctionBoxRef, ConnectorRef)> {
+        self.graph.edges_directed(input.0, Direction::Incoming)
+            .find_map(|e| e.weight().iter()
+                .find(|w| w.input == input.1)
+                .map(|w| (e.source(), w.output)))
+    }
+
     pub fn can_connect(&self, c1: (FunctionBoxRef, ConnectorRef), c2: (FunctionBoxRef, ConnectorRef)) -> bool {
         if let Some(((output_ref, output_connector), (input_ref, input_connector))) = output_input_pair(&self.graph, c1, c2) {
             assert!(matches!(self.graph[output_ref].connectors[output_connector].direction, ConnectorDirection::Output), "wrong direction {}", output_connector);
             assert!(matches!(self.graph[input_ref].connectors[input_connector].direction, ConnectorDirection::Input), "wrong direction {}", input_connector);
-            self.graph.edges_directed(input_ref, Direction::Incoming).into_iter()
-                .find(|x| {
-                    x.weight().iter().find(|(outp, inp)| { *inp == input_connector }).is_some()
-                }).is_none()
+            !self.graph.edges_directed(input_ref, Direction::Incoming)
+                .any(|x| {
+                    x.weight().iter().any(|w| w.input == input_connector)
+                })
         } else {
             false
         }
@@ -53,9 +118,8 @@ impl Container {
             .unwrap_or_else(|| { self.graph.add_edge(output_ref, input_ref, Vec::new()) });
         let vec = self.graph.index_mut(edge_ref);
 
-        let new_edge = (output_connector, input_connector);
-        if !vec.contains(&new_edge) {
-            vec.push(new_edge)
+        if !vec.iter().any(|w| w.output == output_connector && w.input == input_connector) {
+            vec.push(Wire::new(output_connector, input_connector))
         }
     }
 
@@ -65,8 +129,8 @@ impl Container {
             let mut neighbors = self.graph.neighbors_directed(connector.0, Direction::Incoming)
                 .detach();
             while let Some(n) = neighbors.next_edge(&self.graph) {
-                self.graph.index_mut(n).retain(|&(out, inp)|{
-                    inp != connector.1
+                self.graph.index_mut(n).retain(|w| {
+                    w.input != connector.1
                 });
                 if self.graph[n].is_empty() {
                     self.graph.remove_edge(n);
@@ -74,4 +138,273 @@ impl Container {
             }
         }
     }
+
+    // changes the number of connectors of a box, keeping the names and wires of the remaining ones
+    pub(crate) fn resize_connectors(&mut self, fb: FunctionBoxRef, inputs_len: usize, outputs_len: usize) {
+        let kind = find_kind(&self.graph[fb].name);
+        let old_inputs_len = self.graph[fb].inputs_len;
+        let names = |connectors: Vec<String>, len: usize, direction: ConnectorDirection| -> Vec<String> {
+            (0..len).map(|i| connectors.get(i).cloned()
+                .or_else(|| kind.map(|k| k.pin_name(direction, i)))
+                .unwrap_or_else(|| format!("{}", i + 1)))
+                .collect()
+        };
+        let inputs = names(self.graph[fb].inputs_iter().map(|c| c.name.clone()).collect(), inputs_len, ConnectorDirection::Input);
+        let outputs = names(self.graph[fb].outputs_iter().map(|c| c.name.clone()).collect(), outputs_len, ConnectorDirection::Output);
+        let probed_inputs: Vec<usize> = self.graph[fb].inputs_iter().filter(|c| c.probe).map(|c| c.idx).collect();
+        let probed_outputs: Vec<usize> = self.graph[fb].outputs_iter().filter(|c| c.probe).map(|c| c.idx - old_inputs_len).collect();
+        self.graph[fb].set_connectors(inputs, outputs);
+        // the remaining pins stay probed
+        let probed = probed_inputs.into_iter().filter(|&i| i < inputs_len)
+            .chain(probed_outputs.into_iter().filter(|&o| o < outputs_len).map(|o| inputs_len + o));
+        for c in probed {
+            self.graph[fb].connectors[c].probe = true;
+        }
+
+        let mut incoming = self.graph.neighbors_directed(fb, Direction::Incoming).detach();
+        while let Some(e) = incoming.next_edge(&self.graph) {
+            self.graph[e].retain(|w| w.input < inputs_len);
+        }
+        let mut outgoing = self.graph.neighbors_directed(fb, Direction::Outgoing).detach();
+        while let Some(e) = outgoing.next_edge(&self.graph) {
+            self.graph[e].retain(|w| w.output - old_inputs_len < outputs_len);
+            self.graph[e].iter_mut().for_each(|w| w.output = w.output - old_inputs_len + inputs_len);
+        }
+        self.graph.retain_edges(|g, e| !g[e].is_empty());
+    }
+
+    fn boxes_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item=&'a FunctionBox> + 'a {
+        self.graph.raw_nodes().iter().map(|n| &n.weight).filter(move |fb| fb.name == name)
+    }
+
+    // the pins of the "input" boxes are the outputs driving the circuit
+    pub fn input_names(&self) -> Vec<String> {
+        self.boxes_named("input").flat_map(|fb| fb.outputs_iter().map(|c| c.name.clone())).collect()
+    }
+
+    // the pins of the "output" boxes are the inputs the circuit drives
+    pub fn output_names(&self) -> Vec<String> {
+        self.boxes_named("output").flat_map(|fb| fb.inputs_iter().map(|c| c.name.clone())).collect()
+    }
+
+    pub fn set_inputs(&mut self, values: &[bool]) {
+        let mut values = values.iter();
+        self.graph.node_weights_mut()
+            .filter(|fb| fb.name == "input")
+            .for_each(|fb| fb.outputs_iter_mut().for_each(|c| {
+                if let Some(&v) = values.next() {
+                    c.state = v;
+                }
+            }));
+    }
+
+    pub fn outputs(&self) -> Vec<bool> {
+        self.boxes_named("output").flat_map(|fb| fb.inputs_iter().map(|c| c.state)).collect()
+    }
+
+    // Graphviz source with a record node per box, its inputs on the left and outputs on the right
+    pub fn to_dot(&self) -> String {
+        let mut out = String::from("digraph circuit {\n    rankdir=LR;\n    node [shape=record];\n");
+        for n in self.graph.node_indices() {
+            let fb = &self.graph[n];
+            let ports = |connectors: Vec<&Connector>| connectors.iter()
+                .map(|c| format!("<c{}> {}", c.idx, dot_escape(&c.name)))
+                .collect::<Vec<_>>();
+            let (inputs, outputs) = (ports(fb.inputs_iter().collect()), ports(fb.outputs_iter().collect()));
+            let mut fields = Vec::new();
+            if !inputs.is_empty() {
+                fields.push(format!("{{{}}}", inputs.join("|")));
+            }
+            fields.push(format!("{}\\n{}", dot_escape(&fb.id), dot_escape(fb.display_name())));
+            if !outputs.is_empty() {
+                fields.push(format!("{{{}}}", outputs.join("|")));
+            }
+            out.push_str(&format!("    n{} [label=\"{{{}}}\"];\n", n.index(), fields.join("|")));
+        }
+        for e in self.graph.edge_references() {
+            for wire in e.weight() {
+                out.push_str(&format!("    n{}:c{}:e -> n{}:c{}:w;\n", e.source().index(), wire.output, e.target().index(), wire.input));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub(crate) fn add_annotation(&mut self, annotation: Annotation) -> usize {
+        self.annotations.push(annotation);
+        self.annotations.len() - 1
+    }
+
+    // moves an annotation, a frame takes the boxes, bend points and annotations inside it along
+    pub(crate) fn move_annotation(&mut self, i: usize, position: PosF) {
+        let old_position = self.annotations[i].position();
+        let delta = vec2_sub(position, old_position);
+
+        if let Annotation::Frame { rect, .. } = self.annotations[i] {
+            let inside = |r: [f64; 4]| rect_contains(rect, r);
+
+            let boxes: Vec<FunctionBoxRef> = self.graph.node_indices()
+                .filter(|&n| inside(FunctionBoxDraw::new(&self.graph[n], n).rect()))
+                .collect();
+            boxes.into_iter().for_each(|n| {
+                self.graph[n].position = vec2_add(self.graph[n].position, delta);
+            });
+            self.graph.edge_weights_mut()
+                .flat_map(|wires| wires.iter_mut())
+                .flat_map(|wire| wire.bend_points.iter_mut())
+                .filter(|p| inside([p[0], p[1], 0., 0.]))
+                .for_each(|p| *p = vec2_add(*p, delta));
+            self.annotations.iter_mut().enumerate()
+                .filter(|&(j, ref a)| j != i && inside(a.rect()))
+                .for_each(|(_, a)| a.set_position(vec2_add(a.position(), delta)));
+        }
+
+        self.annotations[i].set_position(position);
+    }
+
+    pub(crate) fn resize_frame(&mut self, i: usize, size: PosF) {
+        if let Annotation::Frame { rect, .. } = &mut self.annotations[i] {
+            rect[2] = size[0].max(40.);
+            rect[3] = size[1].max(FRAME_TITLE_HEIGHT + 10.);
+        }
+    }
+
+    pub(crate) fn insert_bend_point(&mut self, edge: EdgeIndex<u32>, wire: usize, at: usize, position: PosF) {
+        self.graph[edge][wire].bend_points.insert(at, position);
+    }
+
+    // false if the bend point is gone, e.g. its wire was removed while it was dragged
+    pub(crate) fn move_bend_point(&mut self, (edge, wire, bend): BendPointRef, position: PosF) -> bool {
+        match self.graph.edge_weight_mut(edge).and_then(|w| w.get_mut(wire)).and_then(|w| w.bend_points.get_mut(bend)) {
+            Some(point) => {
+                *point = position;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn remove_bend_point(&mut self, (edge, wire, bend): BendPointRef) {
+        if let Some(wire) = self.graph.edge_weight_mut(edge).and_then(|w| w.get_mut(wire)).filter(|w| bend < w.bend_points.len()) {
+            wire.bend_points.remove(bend);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wires(container: &Container) -> Vec<(FunctionBoxRef, ConnectorRef, FunctionBoxRef, ConnectorRef)> {
+        let mut wires: Vec<_> = container.graph.edge_references()
+            .flat_map(|e| e.weight().iter().map(move |w| (e.source(), w.output, e.target(), w.input)))
+            .collect();
+        wires.sort();
+        wires
+    }
+
+    #[test]
+    fn resizing_the_inputs_moves_the_wires_of_the_outputs() {
+        let mut container = Container::new();
+        let input = container.add(find_kind("input").unwrap().create([0., 0.]));
+        let and = container.add(find_kind("and").unwrap().create([0., 0.]));
+        let output = container.add(find_kind("output").unwrap().create([0., 0.]));
+        container.connect((input, 0), (and, 0));
+        container.connect((input, 1), (and, 1));
+        container.connect((and, 2), (output, 0));
+        container.graph[and].connectors[2].probe = true;
+
+        // the output of the and moves from connector 2 to 3
+        container.resize_connectors(and, 3, 1);
+        assert_eq!(container.graph[and].connectors.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), vec!["i1", "i2", "i3", "and"]);
+        assert!(container.graph[and].connectors[3].probe);
+        assert_eq!(wires(&container), vec![(input, 0, and, 0), (input, 1, and, 1), (and, 3, output, 0)]);
+
+        // the wire to the removed input is gone
+        container.resize_connectors(and, 1, 1);
+        assert_eq!(wires(&container), vec![(input, 0, and, 0), (and, 1, output, 0)]);
+        assert_eq!(container.graph[and].outputs_iter().map(|c| c.idx).collect::<Vec<_>>(), vec![1]);
+    }
+
+    #[test]
+    fn bend_points_of_removed_wires_are_ignored() {
+        let mut container = Container::new();
+        let input = container.add(find_kind("input").unwrap().create([0., 0.]));
+        let output = container.add(find_kind("output").unwrap().create([0., 0.]));
+        container.connect((input, 0), (output, 0));
+        let edge = container.graph.find_edge(input, output).unwrap();
+        container.insert_bend_point(edge, 0, 0, [10., 10.]);
+        assert!(container.move_bend_point((edge, 0, 0), [20., 20.]));
+
+        container.disconnect((output, 0));
+        assert!(!container.move_bend_point((edge, 0, 0), [30., 30.]));
+        container.remove_bend_point((edge, 0, 0));
+    }
+
+    #[test]
+    fn moving_a_frame_moves_what_is_inside() {
+        let mut container = Container::new();
+        let frame = container.add_annotation(Annotation::Frame { name: "alu".into(), rect: [0., 0., 200., 200.] });
+        let inner_frame = container.add_annotation(Annotation::Frame { name: "carry".into(), rect: [120., 120., 60., 60.] });
+        let inside_label = container.add_annotation(Annotation::Label { text: "hi".into(), position: [20., 170.] });
+        let outside_label = container.add_annotation(Annotation::Label { text: "hi".into(), position: [20., 250.] });
+        // a not is 48 wide and 80 high
+        let inside = container.add(find_kind("not").unwrap().create([10., 30.]));
+        let outside = container.add(find_kind("not").unwrap().create([300., 0.]));
+        let overlapping = container.add(find_kind("not").unwrap().create([180., 0.]));
+        container.connect((inside, 1), (outside, 0));
+        container.insert_bend_point(container.graph.find_edge(inside, outside).unwrap(), 0, 0, [50., 150.]);
+        container.insert_bend_point(container.graph.find_edge(inside, outside).unwrap(), 0, 1, [250., 50.]);
+
+        container.move_annotation(frame, [100., 50.]);
+
+        assert_eq!(container.annotations[frame].rect(), [100., 50., 200., 200.]);
+        assert_eq!(container.annotations[inner_frame].rect(), [220., 170., 60., 60.]);
+        assert_eq!(container.annotations[inside_label].position(), [120., 220.]);
+        assert_eq!(container.annotations[outside_label].position(), [20., 250.]);
+        assert_eq!(container.graph[inside].position, [110., 80.]);
+        assert_eq!(container.graph[outside].position, [300., 0.]);
+        assert_eq!(container.graph[overlapping].position, [180., 0.]);
+        let edge = container.graph.find_edge(inside, outside).unwrap();
+        assert_eq!(container.graph[edge][0].bend_points, vec![[150., 200.], [250., 50.]]);
+
+        // a label takes nothing along
+        container.move_annotation(outside_label, [0., 0.]);
+        assert_eq!(container.graph[inside].position, [110., 80.]);
+        assert_eq!(container.annotations[frame].rect(), [100., 50., 200., 200.]);
+    }
+
+    #[test]
+    fn rect_contains_only_whole_rects() {
+        assert!(rect_contains([0., 0., 10., 10.], [0., 0., 10., 10.]));
+        assert!(rect_contains([0., 0., 10., 10.], [2., 2., 0., 0.]));
+        assert!(!rect_contains([0., 0., 10., 10.], [5., 5., 6., 1.]));
+        assert!(!rect_contains([0., 0., 10., 10.], [-1., 2., 3., 3.]));
+    }
+
+    #[test]
+    fn writes_a_record_per_box_and_a_line_per_wire() {
+        let mut container = Container::new();
+        let input = container.add(FunctionBox::new("input", [0., 0.], vec![], vec!["a".into(), "b".into()]));
+        let and = container.add(find_kind("and").unwrap().create([0., 0.]));
+        container.graph[and].label = r#"{x|y} <z> "q""#.into();
+        container.graph[and].connectors[2].name = "a|b".into();
+        // both wires share one edge
+        container.connect((input, 0), (and, 0));
+        container.connect((input, 1), (and, 1));
+        let output = container.add(FunctionBox::new("output", [0., 0.], vec!["y".into()], vec![]));
+        container.connect((and, 2), (output, 0));
+
+        assert_eq!(container.to_dot(), r#"digraph circuit {
+    rankdir=LR;
+    node [shape=record];
+    n0 [label="{input1\ninput|{<c0> a|<c1> b}}"];
+    n1 [label="{{<c0> i1|<c1> i2}|and1\n\{x\|y\} \<z\> \"q\"|{<c2> a\|b}}"];
+    n2 [label="{{<c0> y}|output1\noutput}"];
+    n0:c0:e -> n1:c0:w;
+    n0:c1:e -> n1:c1:w;
+    n1:c2:e -> n2:c0:w;
+}
+"#);
+    }
 }
@@ -0,0 +1,297 @@
+use std::collections::HashMap;
+
+use petgraph::graph::NodeIndex;
+
+use crate::component::find_kind;
+use crate::container::{ConnectorRef, Container};
+use crate::expression::{input_index, output_pins};
+use crate::sat::{lit, Lit, Solver};
+
+// up to this many inputs every combination is simulated, wider circuits go to the SAT solver
+const EXHAUSTIVE_INPUTS: usize = 16;
+
+enum Op {
+    Input(usize),
+    Constant(bool),
+    And(Vec<usize>),
+    Or(Vec<usize>),
+    Xor(Vec<usize>),
+}
+
+struct Node {
+    op: Op,
+    negated: bool,
+}
+
+// the gates of both circuits flattened into one list, every node comes after the nodes it reads
+struct Network {
+    nodes: Vec<Node>,
+    memo: HashMap<(usize, NodeIndex, ConnectorRef), usize>,
+    // the instance of every sub-circuit box, the top level circuits are 0 and 1
+    instances: HashMap<(usize, NodeIndex), usize>,
+    visiting: Vec<(usize, NodeIndex)>,
+}
+
+impl Network {
+    fn add(&mut self, op: Op, negated: bool) -> usize {
+        self.nodes.push(Node { op, negated });
+        self.nodes.len() - 1
+    }
+
+    fn input(&mut self, instance: usize, container: &Container, input: (NodeIndex, ConnectorRef), bindings: &[usize]) -> Result<usize, String> {
+        match container.input_driver(input) {
+            Some(driver) => self.output(instance, container, driver, bindings),
+            None => Ok(self.add(Op::Constant(false), false)),
+        }
+    }
+
+    fn output(&mut self, instance: usize, container: &Container, (n, c): (NodeIndex, ConnectorRef), bindings: &[usize]) -> Result<usize, String> {
+        if let Some(&node) = self.memo.get(&(instance, n, c)) {
+            return Ok(node);
+        }
+        let fb = &container.graph[n];
+        if self.visiting.contains(&(instance, n)) {
+            return Err(format!("{} is part of a loop, only combinational circuits can be compared", fb.id));
+        }
+        if fb.name == "input" {
+            return Ok(bindings[input_index(container, (n, c))]);
+        }
+
+        self.visiting.push((instance, n));
+        let mut operands = Vec::new();
+        for i in 0..fb.inputs_len {
+            operands.push(self.input(instance, container, (n, i), bindings)?);
+        }
+        let node = match fb.name.as_str() {
+            "circuit" => {
+                let circuit = fb.circuit.as_ref().ok_or_else(|| format!("{} has no circuit", fb.id))?;
+                let next = self.instances.len() + 2;
+                let inner = *self.instances.entry((instance, n)).or_insert(next);
+                let pin = output_pins(circuit)[c - fb.inputs_len];
+                self.input(inner, circuit, pin, &operands).map_err(|e| format!("{}: {}", fb.id, e))?
+            }
+            "and" => self.add(Op::And(operands), false),
+            "nand" => self.add(Op::And(operands), true),
+            "or" => self.add(Op::Or(operands), false),
+            "nor" => self.add(Op::Or(operands), true),
+            "xor" => self.add(Op::Xor(operands), false),
+            "xnor" => self.add(Op::Xor(operands), true),
+            "not" => self.add(Op::And(operands), true),
+            kind => match find_kind(kind) {
+                Some(k) if k.inputs.is_empty() && kind != "clock" => self.add(Op::Constant((k.function)(&[])), false),
+                _ => return Err(format!("{} is a {}, only combinational circuits can be compared", fb.id, kind)),
+            },
+        };
+        self.visiting.pop();
+        self.memo.insert((instance, n, c), node);
+        Ok(node)
+    }
+
+    fn evaluate(&self, inputs: &[bool]) -> Vec<bool> {
+        let mut values: Vec<bool> = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let value = match &node.op {
+                Op::Input(i) => inputs[*i],
+                Op::Constant(v) => *v,
+                Op::And(xs) => xs.iter().all(|&x| values[x]),
+                Op::Or(xs) => xs.iter().any(|&x| values[x]),
+                Op::Xor(xs) => xs.iter().filter(|&&x| values[x]).count() % 2 == 1,
+            };
+            values.push(value != node.negated);
+        }
+        values
+    }
+}
+
+// y = a ^ b
+fn encode_xor(solver: &mut Solver, y: Lit, a: Lit, b: Lit) {
+    solver.add_clause(&[y ^ 1, a, b]);
+    solver.add_clause(&[y ^ 1, a ^ 1, b ^ 1]);
+    solver.add_clause(&[y, a ^ 1, b]);
+    solver.add_clause(&[y, a, b ^ 1]);
+}
+
+// one variable per node with the Tseitin clauses of its gate, then a miter asking for any output to differ
+fn find_difference(network: &Network, inputs: usize, outputs: &[(usize, usize)]) -> Option<Vec<bool>> {
+    let mut solver = Solver::new();
+    let mut literals: Vec<Lit> = Vec::with_capacity(network.nodes.len());
+    let mut input_vars = vec![0; inputs];
+    for node in &network.nodes {
+        let y = solver.new_var();
+        let out = lit(y, false);
+        match &node.op {
+            Op::Input(i) => input_vars[*i] = y,
+            Op::Constant(v) => solver.add_clause(&[lit(y, !v)]),
+            Op::And(xs) => {
+                let xs: Vec<Lit> = xs.iter().map(|&x| literals[x]).collect();
+                xs.iter().for_each(|&x| solver.add_clause(&[out ^ 1, x]));
+                solver.add_clause(&[vec![out], xs.iter().map(|&x| x ^ 1).collect()].concat());
+            }
+            Op::Or(xs) => {
+                let xs: Vec<Lit> = xs.iter().map(|&x| literals[x]).collect();
+                xs.iter().for_each(|&x| solver.add_clause(&[out, x ^ 1]));
+                solver.add_clause(&[vec![out ^ 1], xs].concat());
+            }
+            Op::Xor(xs) => match xs.split_first() {
+                None => solver.add_clause(&[out ^ 1]),
+                Some((&first, rest)) => {
+                    let mut parity = literals[first];
+                    for &x in rest {
+                        let next = lit(solver.new_var(), false);
+                        encode_xor(&mut solver, next, parity, literals[x]);
+                        parity = next;
+                    }
+                    solver.add_clause(&[out ^ 1, parity]);
+                    solver.add_clause(&[out, parity ^ 1]);
+                }
+            },
+        }
+        literals.push(out ^ node.negated as u32);
+    }
+
+    let differences: Vec<Lit> = outputs.iter()
+        .map(|&(a, b)| {
+            let d = lit(solver.new_var(), false);
+            encode_xor(&mut solver, d, literals[a], literals[b]);
+            d
+        })
+        .collect();
+    solver.add_clause(&differences);
+    solver.solve().map(|model| input_vars.iter().map(|&v| model[v]).collect())
+}
+
+pub enum Equivalence {
+    Equal,
+    // the inputs and for every output the values of both circuits
+    Different {
+        inputs: Vec<(String, bool)>,
+        outputs: Vec<(String, bool, bool)>,
+    },
+}
+
+impl Equivalence {
+    pub fn report(&self, a: &str, b: &str) -> String {
+        match self {
+            Equivalence::Equal => format!("{} and {} compute the same function", a, b),
+            Equivalence::Different { inputs, outputs } => {
+                let assignment: Vec<String> = inputs.iter().map(|(name, v)| format!("{}={}", name, *v as u8)).collect();
+                let differences: Vec<String> = outputs.iter()
+                    .filter(|(_, x, y)| x != y)
+                    .map(|(name, x, y)| format!("{} is {} in {} but {} in {}", name, *x as u8, a, *y as u8, b))
+                    .collect();
+                format!("{} and {} differ for {}: {}", a, b, assignment.join(" "), differences.join(", "))
+            }
+        }
+    }
+}
+
+fn same_names(kind: &str, a: &[String], b: &[String]) -> Result<(), String> {
+    for names in [a, b] {
+        if let Some(name) = names.iter().enumerate().find(|(i, n)| names[..*i].contains(n)).map(|(_, n)| n) {
+            return Err(format!("the {} name {} is used twice", kind, name));
+        }
+    }
+    let only = |x: &[String], y: &[String]| x.iter().filter(|n| !y.contains(n)).cloned().collect::<Vec<_>>().join(", ");
+    let (only_a, only_b) = (only(a, b), only(b, a));
+    if !only_a.is_empty() || !only_b.is_empty() {
+        return Err(format!("the {}s don't match, only in the first: {}, only in the second: {}",
+                           kind, if only_a.is_empty() { "-" } else { &only_a }, if only_b.is_empty() { "-" } else { &only_b }));
+    }
+    Ok(())
+}
+
+// the pins are matched by name, the order doesn't matter
+pub fn check_equivalence(a: &Container, b: &Container) -> Result<Equivalence, String> {
+    let inputs = a.input_names();
+    let outputs = a.output_names();
+    same_names("input", &inputs, &b.input_names())?;
+    same_names("output", &outputs, &b.output_names())?;
+
+    let mut network = Network { nodes: Vec::new(), memo: HashMap::new(), instances: HashMap::new(), visiting: Vec::new() };
+    let input_nodes: Vec<usize> = (0..inputs.len()).map(|i| network.add(Op::Input(i), false)).collect();
+    let b_inputs: Vec<usize> = b.input_names().iter().map(|name| input_nodes[inputs.iter().position(|n| n == name).unwrap()]).collect();
+    let mut output_nodes = |instance: usize, container: &Container, bindings: &[usize]| -> Result<Vec<usize>, String> {
+        output_pins(container).into_iter().map(|pin| network.input(instance, container, pin, bindings)).collect()
+    };
+    let a_outputs = output_nodes(0, a, &input_nodes).map_err(|e| format!("first circuit: {}", e))?;
+    let b_outputs = output_nodes(1, b, &b_inputs).map_err(|e| format!("second circuit: {}", e))?;
+    let b_names = b.output_names();
+    let pairs: Vec<(usize, usize)> = outputs.iter().zip(&a_outputs)
+        .map(|(name, &node)| (node, b_outputs[b_names.iter().position(|n| n == name).unwrap()]))
+        .collect();
+
+    let differs = |values: &[bool]| pairs.iter().any(|&(x, y)| values[x] != values[y]);
+    let counterexample = if inputs.len() <= EXHAUSTIVE_INPUTS {
+        (0..1usize << inputs.len())
+            .map(|value| (0..inputs.len()).map(|i| value >> (inputs.len() - 1 - i) & 1 == 1).collect::<Vec<bool>>())
+            .find(|vector| differs(&network.evaluate(vector)))
+    } else {
+        find_difference(&network, inputs.len(), &pairs)
+    };
+
+    Ok(match counterexample {
+        None => Equivalence::Equal,
+        Some(vector) => {
+            let values = network.evaluate(&vector);
+            Equivalence::Different {
+                inputs: inputs.into_iter().zip(vector).collect(),
+                outputs: outputs.into_iter().zip(&pairs).map(|(name, &(x, y))| (name, values[x], values[y])).collect(),
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function_box::FunctionBox;
+    use crate::synthesis::synthesize_text;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    // a gate of the given kind over all inputs
+    fn circuit(kind: &str, inputs: &[&str]) -> Container {
+        let mut container = Container::new();
+        let input = container.add(FunctionBox::new("input", [0., 0.], vec![], names(inputs)));
+        let mut gate = find_kind(kind).unwrap().create([0., 0.]);
+        gate.set_connectors((0..inputs.len()).map(|i| format!("i{}", i)).collect(), names(&["o"]));
+        let gate = container.add(gate);
+        let output = container.add(FunctionBox::new("output", [0., 0.], names(&["y"]), vec![]));
+        for i in 0..inputs.len() {
+            container.connect((input, i), (gate, i));
+        }
+        container.connect((gate, inputs.len()), (output, 0));
+        container
+    }
+
+    #[test]
+    fn finds_counterexamples_exhaustively() {
+        let nand = synthesize_text("y = a ^ b").unwrap();
+        assert!(matches!(check_equivalence(&circuit("xor", &["b", "a"]), &nand).unwrap(), Equivalence::Equal));
+
+        let report = check_equivalence(&circuit("or", &["a", "b"]), &nand).unwrap().report("or", "xor");
+        assert_eq!(report, "or and xor differ for a=1 b=1: y is 1 in or but 0 in xor");
+
+        let error = check_equivalence(&circuit("or", &["a", "c"]), &nand).err().unwrap();
+        assert_eq!(error, "the inputs don't match, only in the first: c, only in the second: b");
+    }
+
+    #[test]
+    fn uses_the_solver_for_wide_circuits() {
+        let inputs: Vec<String> = (0..20).map(|i| format!("x{}", i)).collect();
+        let refs: Vec<&str> = inputs.iter().map(|s| s.as_str()).collect();
+        let parity = circuit("xor", &refs);
+        let rebuilt = synthesize_text(&format!("y = {}", inputs.join(" ^ "))).unwrap();
+        assert!(matches!(check_equivalence(&parity, &rebuilt).unwrap(), Equivalence::Equal));
+
+        // differs whenever all but the last input are 1
+        let and = circuit("and", &refs);
+        let rebuilt = synthesize_text(&format!("y = {} & !x19", inputs[..19].join(" & "))).unwrap();
+        match check_equivalence(&and, &rebuilt).unwrap() {
+            Equivalence::Different { inputs, .. } => assert!(inputs[..19].iter().all(|(_, v)| *v)),
+            Equivalence::Equal => panic!("the circuits differ"),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+use std::path::Path;
+
+use crate::bench::to_bench;
+use crate::blif::to_blif;
+use crate::container::Container;
+use crate::logisim::to_logisim;
+use crate::netlist::sanitize;
+use crate::svg::{to_png, to_svg};
+use crate::truth_table::truth_table;
+use crate::verilog::to_verilog;
+use crate::vhdl::to_vhdl;
+
+// the extensions export can write, the format is chosen by the extension of the target
+pub const EXPORT_FORMATS: &[&str] = &["v", "vhd", "circ", "blif", "bench", "dot", "svg", "png", "csv", "md"];
+
+// returns what the format could not represent
+pub fn export(container: &Container, path: &Path) -> Result<Vec<String>, String> {
+    let name = sanitize(&path.file_stem().unwrap_or_default().to_string_lossy());
+    let extension = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    let (text, warnings): (String, Vec<String>) = match extension.as_str() {
+        "v" => (to_verilog(container, &name)?, Vec::new()),
+        "vhd" | "vhdl" => (to_vhdl(container, &name)?, Vec::new()),
+        "circ" => to_logisim(container),
+        "blif" => (to_blif(container, &name)?, Vec::new()),
+        "bench" => (to_bench(container, &name)?, Vec::new()),
+        "dot" => (container.to_dot(), Vec::new()),
+        // images use the straight wires the canvas starts with
+        "svg" => (to_svg(container, false), Vec::new()),
+        "png" => return write(path, &to_png(container, false)?).map(|_| Vec::new()),
+        "csv" => (truth_table(container)?.to_csv(), Vec::new()),
+        "md" => (truth_table(container)?.to_markdown(), Vec::new()),
+        _ => return Err(format!("cannot export to {:?}, known formats are {}", extension, EXPORT_FORMATS.join(", "))),
+    };
+    write(path, text.as_bytes())?;
+    Ok(warnings)
+}
+
+fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+    std::fs::write(path, data).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
@@ -0,0 +1,331 @@
+use std::fmt;
+
+use petgraph::Direction;
+use petgraph::graph::NodeIndex;
+
+use crate::component::find_kind;
+use crate::container::{ConnectorRef, Container};
+
+// expressions larger than this come from deeply reconvergent circuits and are not readable anyway
+const MAX_SIZE: usize = 100_000;
+
+// a boolean expression written like `a & !b | c`, ^ binds tighter than | and & tighter than ^
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Constant(bool),
+    Var(String),
+    Not(Box<Expr>),
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Xor(Vec<Expr>),
+}
+
+impl Expr {
+    pub(crate) fn not(expr: Expr) -> Expr {
+        Expr::Not(Box::new(expr))
+    }
+
+    pub fn evaluate(&self, value: &dyn Fn(&str) -> bool) -> bool {
+        match self {
+            Expr::Constant(v) => *v,
+            Expr::Var(name) => value(name),
+            Expr::Not(e) => !e.evaluate(value),
+            Expr::And(es) => es.iter().all(|e| e.evaluate(value)),
+            Expr::Or(es) => es.iter().any(|e| e.evaluate(value)),
+            Expr::Xor(es) => es.iter().filter(|e| e.evaluate(value)).count() % 2 == 1,
+        }
+    }
+
+    // the variables in the order they first appear
+    pub fn variables(&self) -> Vec<String> {
+        let mut variables = Vec::new();
+        self.collect_variables(&mut variables);
+        variables
+    }
+
+    fn collect_variables(&self, variables: &mut Vec<String>) {
+        match self {
+            Expr::Constant(_) => {}
+            Expr::Var(name) => if !variables.contains(name) {
+                variables.push(name.clone());
+            },
+            Expr::Not(e) => e.collect_variables(variables),
+            Expr::And(es) | Expr::Or(es) | Expr::Xor(es) => es.iter().for_each(|e| e.collect_variables(variables)),
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(es) | Expr::Xor(es) | Expr::And(es) if es.len() == 1 => es[0].precedence(),
+            Expr::Or(_) => 0,
+            Expr::Xor(_) => 1,
+            Expr::And(_) => 2,
+            _ => 3,
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let operand = |f: &mut fmt::Formatter<'_>, e: &Expr, min: u8| {
+            if e.precedence() < min { write!(f, "({})", e) } else { write!(f, "{}", e) }
+        };
+        let list = |f: &mut fmt::Formatter<'_>, es: &[Expr], operator: &str, precedence: u8, empty: &str| {
+            if es.is_empty() {
+                return write!(f, "{}", empty);
+            }
+            for (i, e) in es.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", operator)?;
+                }
+                // a ^ b ^ c is the same in any grouping, but a ^ (b | c) is not
+                operand(f, e, precedence + if es.len() > 1 { 1 } else { 0 })?;
+            }
+            Ok(())
+        };
+        match self {
+            Expr::Constant(v) => write!(f, "{}", *v as u8),
+            Expr::Var(name) => write!(f, "{}", name),
+            Expr::Not(e) => {
+                write!(f, "!")?;
+                operand(f, e, 3)
+            }
+            Expr::And(es) => list(f, es, "&", 2, "1"),
+            Expr::Or(es) => list(f, es, "|", 0, "0"),
+            Expr::Xor(es) => list(f, es, "^", 1, "0"),
+        }
+    }
+}
+
+// a recursive descent parser for the syntax of Display, ~ is accepted for ! as well
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_spaces(&mut self) {
+        let rest = &self.text[self.position..];
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        self.text[self.position..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.position += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error(&self, expected: &str) -> String {
+        match self.text[self.position..].chars().next() {
+            Some(c) => format!("expected {} at {:?} (column {})", expected, c, self.position + 1),
+            None => format!("expected {} at the end", expected),
+        }
+    }
+
+    fn list(&mut self, operator: char, operand: fn(&mut Self) -> Result<Expr, String>, wrap: fn(Vec<Expr>) -> Expr) -> Result<Expr, String> {
+        let mut operands = vec![operand(self)?];
+        while self.eat(operator) {
+            operands.push(operand(self)?);
+        }
+        Ok(if operands.len() == 1 { operands.pop().unwrap() } else { wrap(operands) })
+    }
+
+    fn or(&mut self) -> Result<Expr, String> {
+        self.list('|', Parser::xor, Expr::Or)
+    }
+
+    fn xor(&mut self) -> Result<Expr, String> {
+        self.list('^', Parser::and, Expr::Xor)
+    }
+
+    fn and(&mut self) -> Result<Expr, String> {
+        self.list('&', Parser::unary, Expr::And)
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.eat('!') || self.eat('~') {
+            return Ok(Expr::not(self.unary()?));
+        }
+        if self.eat('(') {
+            let expr = self.or()?;
+            return if self.eat(')') { Ok(expr) } else { Err(self.error("')'")) };
+        }
+        self.skip_spaces();
+        let rest = &self.text[self.position..];
+        let name: String = rest.chars().take_while(|&c| c.is_alphanumeric() || c == '_' || c == '.').collect();
+        self.position += name.len();
+        match name.as_str() {
+            "" => Err(self.error("a name")),
+            "0" => Ok(Expr::Constant(false)),
+            "1" => Ok(Expr::Constant(true)),
+            _ => Ok(Expr::Var(name)),
+        }
+    }
+}
+
+pub fn parse(text: &str) -> Result<Expr, String> {
+    let mut parser = Parser { text, position: 0 };
+    let expr = parser.or()?;
+    if parser.peek().is_some() {
+        return Err(parser.error("an operator"));
+    }
+    Ok(expr)
+}
+
+// equations like `sum = a ^ b; carry = a & b` separated by ; or new lines, a single expression without a name is y
+pub fn parse_equations(text: &str) -> Result<Vec<(String, Expr)>, String> {
+    let parts: Vec<&str> = text.split([';', '\n']).map(str::trim).filter(|p| !p.is_empty()).collect();
+    if parts.is_empty() {
+        return Err("the expression is empty".into());
+    }
+    let mut equations: Vec<(String, Expr)> = Vec::new();
+    for part in &parts {
+        let (name, expr) = match part.split_once('=') {
+            Some((name, expr)) => (name.trim().to_string(), expr),
+            None if parts.len() == 1 => ("y".to_string(), *part),
+            None => return Err(format!("{:?} needs a name like y = {}", part, part)),
+        };
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '.') {
+            return Err(format!("{:?} is not a valid output name", name));
+        }
+        if equations.iter().any(|(n, _)| *n == name) {
+            return Err(format!("{} is defined twice", name));
+        }
+        let expr = parse(expr).map_err(|e| format!("{}: {}", name, e))?;
+        equations.push((name, expr));
+    }
+    Ok(equations)
+}
+
+struct Extractor {
+    budget: usize,
+    // boxes on the current path, a box seen again is a loop
+    visiting: Vec<NodeIndex>,
+}
+
+// the expression of every output pin, the variables are the input pins
+pub fn output_expressions(container: &Container) -> Result<Vec<(String, Expr)>, String> {
+    let inputs: Vec<Expr> = container.input_names().into_iter().map(Expr::Var).collect();
+    let mut extractor = Extractor { budget: MAX_SIZE, visiting: Vec::new() };
+    let names = container.output_names();
+    output_pins(container).into_iter().zip(names)
+        .map(|(pin, name)| extractor.input(container, pin, &inputs)
+            .map(|expr| (name.clone(), expr))
+            .map_err(|e| format!("{}: {}", name, e)))
+        .collect()
+}
+
+// the input connectors of the "output" boxes in the order of Container::output_names
+pub(crate) fn output_pins(container: &Container) -> Vec<(NodeIndex, ConnectorRef)> {
+    let graph = &container.graph;
+    graph.node_indices()
+        .filter(|&n| graph[n].name == "output")
+        .flat_map(|n| graph[n].inputs_iter().map(move |c| (n, c.idx)))
+        .collect()
+}
+
+// the position of an input box pin in Container::input_names
+pub(crate) fn input_index(container: &Container, (n, c): (NodeIndex, ConnectorRef)) -> usize {
+    let graph = &container.graph;
+    graph.node_indices()
+        .take_while(|&m| m != n)
+        .filter(|&m| graph[m].name == "input")
+        .map(|m| graph[m].outputs_len)
+        .sum::<usize>() + (c - graph[n].inputs_len)
+}
+
+impl Extractor {
+    // the value arriving at an input connector, unconnected inputs are 0 like in the simulation
+    fn input(&mut self, container: &Container, input: (NodeIndex, ConnectorRef), bindings: &[Expr]) -> Result<Expr, String> {
+        match container.input_driver(input) {
+            Some(driver) => self.output(container, driver, bindings),
+            None => Ok(Expr::Constant(false)),
+        }
+    }
+
+    fn output(&mut self, container: &Container, (n, c): (NodeIndex, ConnectorRef), bindings: &[Expr]) -> Result<Expr, String> {
+        let fb = &container.graph[n];
+        if self.visiting.contains(&n) {
+            return Err(format!("{} is part of a loop, only combinational circuits have an expression", fb.id));
+        }
+        if self.budget == 0 {
+            return Err("the expression is too large".into());
+        }
+        self.budget -= 1;
+
+        if fb.name == "input" {
+            return Ok(bindings[input_index(container, (n, c))].clone());
+        }
+        self.visiting.push(n);
+        let mut operands = Vec::new();
+        for i in 0..fb.inputs_len {
+            operands.push(self.input(container, (n, i), bindings)?);
+        }
+        let expr = match fb.name.as_str() {
+            "clock" => return Err(format!("{} is a clock, only combinational circuits have an expression", fb.id)),
+            "circuit" => {
+                let circuit = fb.circuit.as_ref().ok_or_else(|| format!("{} has no circuit", fb.id))?;
+                let pin = output_pins(circuit)[c - fb.inputs_len];
+                let mut inner = Extractor { budget: self.budget, visiting: Vec::new() };
+                let expr = inner.input(circuit, pin, &operands).map_err(|e| format!("{}: {}", fb.id, e))?;
+                self.budget = inner.budget;
+                expr
+            }
+            kind => gate_expression(kind, operands)?,
+        };
+        self.visiting.pop();
+        Ok(expr)
+    }
+}
+
+fn gate_expression(kind: &str, mut operands: Vec<Expr>) -> Result<Expr, String> {
+    let single = |es: Vec<Expr>, wrap: fn(Vec<Expr>) -> Expr| if es.len() == 1 { es.into_iter().next().unwrap() } else { wrap(es) };
+    Ok(match kind {
+        "and" => single(operands, Expr::And),
+        "or" => single(operands, Expr::Or),
+        "xor" => single(operands, Expr::Xor),
+        "nand" => Expr::not(single(operands, Expr::And)),
+        "nor" => Expr::not(single(operands, Expr::Or)),
+        "xnor" => Expr::not(single(operands, Expr::Xor)),
+        "not" => Expr::not(operands.remove(0)),
+        kind => match find_kind(kind) {
+            Some(k) if k.inputs.is_empty() => Expr::Constant((k.function)(&[])),
+            _ => return Err(format!("no expression for {:?}", kind)),
+        },
+    })
+}
+
+// the gates an output depends on, with every gate inside the sub-circuits it passes through
+pub fn cone_gate_count(container: &Container, output: usize) -> usize {
+    let graph = &container.graph;
+    let mut seen = Vec::new();
+    let mut stack: Vec<NodeIndex> = output_pins(container).get(output)
+        .and_then(|&pin| container.input_driver(pin))
+        .map(|(n, _)| n)
+        .into_iter()
+        .collect();
+    while let Some(n) = stack.pop() {
+        if seen.contains(&n) {
+            continue;
+        }
+        seen.push(n);
+        stack.extend(graph.neighbors_directed(n, Direction::Incoming));
+    }
+    seen.iter().map(|&n| gate_count(container, n)).sum()
+}
+
+fn gate_count(container: &Container, n: NodeIndex) -> usize {
+    let fb = &container.graph[n];
+    match &fb.circuit {
+        Some(circuit) => circuit.graph.node_indices().map(|m| gate_count(circuit, m)).sum(),
+        None => find_kind(&fb.name).map_or(0, |k| (k.category == "Gates") as usize),
+    }
+}
@@ -0,0 +1,223 @@
+use std::path::{Path, PathBuf};
+
+use graphics::Rectangle;
+
+use crate::button::Button;
+use crate::files::{RecentFiles, OPEN_FORMATS};
+use crate::game::{Draw, DrawCtx, PosF, State, Update};
+use crate::text_field::TextField;
+use crate::ui::{draw_text, rgba};
+
+const WIDTH: f64 = 440.;
+const HEIGHT: f64 = 400.;
+const ROW_HEIGHT: f64 = 22.;
+const MAX_ENTRIES: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DialogMode {
+    Open,
+    SaveAs,
+    // another circuit to check the current one against
+    Compare,
+}
+
+// path prompt with a listing of the directory and the recently used files
+pub struct FileDialog {
+    position: PosF,
+    mode: Option<DialogMode>,
+    directory: PathBuf,
+    path: TextField,
+    entries: Vec<(PathBuf, Button)>,
+    recent: Vec<(PathBuf, Button)>,
+    up_button: Button,
+    ok_button: Button,
+    cancel_button: Button,
+    // save as only, whether the simulation state is saved along with the design
+    state_button: Button,
+    include_state: bool,
+    // save as only, the file the dialog was opened for and an existing other file the next OK overwrites
+    current: Option<PathBuf>,
+    overwrite: Option<PathBuf>,
+    chosen: Option<(DialogMode, PathBuf)>,
+}
+
+impl FileDialog {
+    pub fn new(position: PosF) -> Self {
+        let [x, y] = position;
+        FileDialog {
+            position,
+            mode: None,
+            directory: PathBuf::from("."),
+            path: TextField::new(String::new(), [x + 10., y + 30., WIDTH - 20., ROW_HEIGHT]),
+            entries: Vec::new(),
+            recent: Vec::new(),
+            up_button: Button::new_with_rect("..".into(), [x + 10., y + 60., 200., ROW_HEIGHT - 2.], 14),
+            ok_button: Button::new_with_rect("OK".into(), [x + WIDTH - 160., y + HEIGHT - 35., 70., 25.], 16),
+            cancel_button: Button::new_with_rect("Cancel".into(), [x + WIDTH - 80., y + HEIGHT - 35., 70., 25.], 16),
+            state_button: Button::new_with_rect("State".into(), [x + 10., y + HEIGHT - 35., 70., 25.], 16),
+            include_state: false,
+            current: None,
+            overwrite: None,
+            chosen: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.mode.is_some()
+    }
+
+    pub fn open(&mut self, mode: DialogMode, current: Option<&Path>, include_state: bool) {
+        self.include_state = include_state;
+        self.current = current.map(Path::to_path_buf);
+        self.overwrite = None;
+        let current = current.map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from("circuit.json"));
+        self.mode = Some(mode);
+        self.path.text = current.to_string_lossy().into();
+        self.path.focus();
+        self.list_directory(current.parent().filter(|p| p.is_dir()).unwrap_or_else(|| Path::new(".")).to_path_buf());
+
+        let [x, y] = self.position;
+        self.recent = RecentFiles::load().paths.into_iter()
+            .take(MAX_ENTRIES)
+            .enumerate()
+            .map(|(i, path)| {
+                let name = path.file_name().map_or_else(|| path.to_string_lossy(), |n| n.to_string_lossy()).into_owned();
+                (path, Button::new_with_rect(name, [x + 230., y + 82. + i as f64 * ROW_HEIGHT, 200., ROW_HEIGHT - 2.], 14))
+            })
+            .collect();
+    }
+
+    pub fn include_state(&self) -> bool {
+        self.include_state
+    }
+
+    pub fn close(&mut self) {
+        self.mode = None;
+    }
+
+    pub fn chosen(&mut self) -> Option<(DialogMode, PathBuf)> {
+        self.chosen.take()
+    }
+
+    // lists the subdirectories and the files we can open
+    fn list_directory(&mut self, directory: PathBuf) {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(&directory).into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_dir() || path.extension().is_some_and(|e| OPEN_FORMATS.iter().any(|f| e.eq_ignore_ascii_case(f))))
+            .collect();
+        paths.sort_by_key(|path| (!path.is_dir(), path.file_name().map(|n| n.to_os_string())));
+
+        let [x, y] = self.position;
+        self.entries = paths.into_iter()
+            .take(MAX_ENTRIES)
+            .enumerate()
+            .map(|(i, path)| {
+                let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                let name = if path.is_dir() { format!("{}/", name) } else { name };
+                (path, Button::new_with_rect(name, [x + 10., y + 82. + i as f64 * ROW_HEIGHT, 200., ROW_HEIGHT - 2.], 14))
+            })
+            .collect();
+        self.directory = directory;
+    }
+
+    fn choose(&mut self, path: PathBuf) {
+        if let Some(mode) = self.mode {
+            self.chosen = Some((mode, path));
+            self.close();
+        }
+    }
+}
+
+fn pressed_path(buttons: &mut [(PathBuf, Button)]) -> Option<PathBuf> {
+    for (path, button) in buttons {
+        if button.pressed() {
+            return Some(path.clone());
+        }
+    }
+    None
+}
+
+impl Update for FileDialog {
+    fn update(&mut self, state: &State) {
+        if !self.is_open() {
+            return;
+        }
+
+        self.path.update(state);
+        self.up_button.update(state);
+        self.ok_button.update(state);
+        self.cancel_button.update(state);
+        if self.mode == Some(DialogMode::SaveAs) {
+            self.state_button.update(state);
+        }
+        self.entries.iter_mut().for_each(|(_, b)| b.update(state));
+        self.recent.iter_mut().for_each(|(_, b)| b.update(state));
+
+        if self.path.submitted() || self.ok_button.pressed() {
+            let path = PathBuf::from(self.path.text.trim());
+            if path.is_dir() {
+                self.list_directory(path);
+            } else if self.mode == Some(DialogMode::SaveAs) && path.exists()
+                && self.current.as_ref() != Some(&path) && self.overwrite.as_ref() != Some(&path) {
+                // saving over another file needs a second OK
+                self.overwrite = Some(path);
+            } else if !path.as_os_str().is_empty() {
+                self.choose(path);
+            }
+        } else if self.cancel_button.pressed() {
+            self.close();
+        } else if self.state_button.pressed() {
+            self.include_state = !self.include_state;
+        } else if self.up_button.pressed() {
+            let parent = self.directory.canonicalize().ok()
+                .and_then(|d| d.parent().map(Path::to_path_buf));
+            if let Some(parent) = parent {
+                self.list_directory(parent);
+            }
+        } else if let Some(path) = pressed_path(&mut self.entries) {
+            if path.is_dir() {
+                self.list_directory(path);
+            } else {
+                self.path.text = path.to_string_lossy().into();
+            }
+        } else if let Some(path) = pressed_path(&mut self.recent) {
+            self.path.text = path.to_string_lossy().into();
+        }
+    }
+}
+
+impl Draw for FileDialog {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        let mode = match self.mode {
+            Some(mode) => mode,
+            None => return,
+        };
+        let [x, y] = self.position;
+        Rectangle::new_round_border(rgba(45, 52, 54, 1.0), 3., 1.)
+            .color(rgba(178, 190, 195, 1.0))
+            .draw_tri([x, y, WIDTH, HEIGHT], &Default::default(), ctx.c.transform, ctx.g);
+        let title = match mode {
+            DialogMode::Open => "Open circuit",
+            DialogMode::SaveAs => "Save circuit as",
+            DialogMode::Compare => "Compare with circuit",
+        };
+        draw_text(title, 16, [x + 10., y + 15.], rgba(45, 52, 54, 1.0), ctx);
+        draw_text("Recent files", 14, [x + 230., y + 70.], rgba(45, 52, 54, 1.0), ctx);
+
+        self.path.draw(ctx);
+        self.up_button.draw(ctx);
+        self.entries.iter().for_each(|(_, b)| b.draw(ctx));
+        self.recent.iter().for_each(|(_, b)| b.draw(ctx));
+        self.ok_button.draw(ctx);
+        self.cancel_button.draw(ctx);
+        if mode == DialogMode::SaveAs {
+            self.state_button.draw(ctx);
+            let text = if self.include_state { "with simulation state" } else { "design only" };
+            draw_text(text, 14, [x + 90., y + HEIGHT - 22.], rgba(45, 52, 54, 1.0), ctx);
+            if let Some(path) = self.overwrite.as_ref().filter(|p| p.as_os_str() == self.path.text.trim()) {
+                draw_text(&format!("{} exists, OK again overwrites it", path.display()), 14, [x + 10., y + HEIGHT - 47.], rgba(214, 48, 49, 1.0), ctx);
+            }
+        }
+    }
+}
@@ -0,0 +1,459 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+
+use crate::annotation::Annotation;
+use crate::container::Container;
+use crate::function_box::{default_delay, FunctionBox};
+use crate::game::PosF;
+use crate::validation::validate;
+
+// the schema is described in FILE_FORMAT.md, every change of it needs a new version and a migration
+pub const CURRENT_VERSION: u64 = 2;
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+// MIGRATIONS[v] turns a file of version v into one of version v + 1
+const MIGRATIONS: &[Migration] = &[
+    migrate_v0,
+    migrate_v1,
+];
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CircuitFile {
+    pub version: u64,
+    #[serde(flatten)]
+    pub circuit: CircuitDesc,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct CircuitDesc {
+    #[serde(default)]
+    pub components: Vec<ComponentDesc>,
+    #[serde(default)]
+    pub wires: Vec<WireDesc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub annotations: Vec<Annotation>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ComponentDesc {
+    pub id: String,
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub label: String,
+    pub position: PosF,
+    #[serde(default)]
+    pub inputs: Vec<String>,
+    #[serde(default)]
+    pub outputs: Vec<String>,
+    #[serde(default = "default_delay", skip_serializing_if = "is_default_delay")]
+    pub delay: usize,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub params: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit: Option<CircuitDesc>,
+    // simulation state of the inputs followed by the outputs, only saved on request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<Vec<bool>>,
+    // the probed pins, numbered with the inputs first like the state
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub probes: Vec<usize>,
+}
+
+// a pin is addressed by the component id and its position among the outputs (from) or inputs (to)
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PinDesc {
+    pub component: String,
+    pub pin: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct WireDesc {
+    pub from: PinDesc,
+    pub to: PinDesc,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bend_points: Vec<PosF>,
+}
+
+fn is_default_delay(delay: &usize) -> bool {
+    *delay == default_delay()
+}
+
+pub fn to_file(container: &Container, include_state: bool) -> CircuitFile {
+    CircuitFile {
+        version: CURRENT_VERSION,
+        circuit: describe(container, include_state),
+    }
+}
+
+fn describe(container: &Container, include_state: bool) -> CircuitDesc {
+    let graph = &container.graph;
+    let components = graph.node_indices().map(|n| {
+        let fb = &graph[n];
+        ComponentDesc {
+            id: fb.id.clone(),
+            kind: fb.name.clone(),
+            label: fb.label.clone(),
+            position: fb.position,
+            inputs: fb.inputs_iter().map(|c| c.name.clone()).collect(),
+            outputs: fb.outputs_iter().map(|c| c.name.clone()).collect(),
+            delay: fb.delay,
+            params: fb.params.clone(),
+            circuit: fb.circuit.as_ref().map(|c| describe(c, include_state)),
+            state: if include_state { Some(fb.connectors.iter().map(|c| c.state).collect()) } else { None },
+            probes: fb.connectors.iter().filter(|c| c.probe).map(|c| c.idx).collect(),
+        }
+    }).collect();
+
+    let wires = graph.edge_indices().flat_map(|e| {
+        let (source, target) = graph.edge_endpoints(e).unwrap();
+        graph[e].iter().map(move |wire| WireDesc {
+            from: PinDesc { component: graph[source].id.clone(), pin: wire.output - graph[source].inputs_len },
+            to: PinDesc { component: graph[target].id.clone(), pin: wire.input },
+            bend_points: wire.bend_points.clone(),
+        })
+    }).collect();
+
+    CircuitDesc {
+        components,
+        wires,
+        annotations: container.annotations.clone(),
+    }
+}
+
+fn problem_list(problems: &[String]) -> String {
+    match problems {
+        [problem] => problem.clone(),
+        _ => format!("{} problems\n{}", problems.len(), problems.join("\n")),
+    }
+}
+
+pub fn from_file(file: CircuitFile) -> Result<Container, String> {
+    let problems = validate(&file.circuit);
+    if !problems.is_empty() {
+        return Err(problem_list(&problems));
+    }
+    build(file.circuit)
+}
+
+fn build(desc: CircuitDesc) -> Result<Container, String> {
+    let mut container = Container::new();
+    container.annotations = desc.annotations;
+
+    for component in desc.components {
+        if component.id.is_empty() || container.find_id(&component.id).is_some() {
+            return Err(format!("component id {:?} is empty or used twice", component.id));
+        }
+        let mut fb = FunctionBox::new(&component.kind, component.position, component.inputs, component.outputs);
+        fb.id = component.id;
+        fb.label = component.label;
+        fb.delay = component.delay;
+        fb.params = component.params;
+        fb.circuit = component.circuit.map(build).transpose()?.map(Box::new);
+        if let Some(state) = component.state.filter(|s| s.len() == fb.connectors.len()) {
+            fb.connectors.iter_mut().zip(state).for_each(|(c, s)| c.state = s);
+        }
+        for i in component.probes {
+            if let Some(c) = fb.connectors.get_mut(i) {
+                c.probe = true;
+            }
+        }
+        container.add(fb);
+    }
+
+    for wire in desc.wires {
+        let pin = |p: &PinDesc, output: bool| {
+            let n = container.find_id(&p.component)
+                .ok_or_else(|| format!("wire refers to unknown component {:?}", p.component))?;
+            let fb = &container.graph[n];
+            let (len, offset) = if output { (fb.outputs_len, fb.inputs_len) } else { (fb.inputs_len, 0) };
+            if p.pin < len {
+                Ok((n, p.pin + offset))
+            } else {
+                Err(format!("{} has no {} pin {}", p.component, if output { "output" } else { "input" }, p.pin))
+            }
+        };
+        let output = pin(&wire.from, true)?;
+        let input = pin(&wire.to, false)?;
+        if !container.can_connect(output, input) {
+            return Err(format!("input pin {} of {} is driven twice", wire.to.pin, wire.to.component));
+        }
+        container.connect(output, input);
+        let edge = container.graph.find_edge(output.0, input.0).unwrap();
+        let last = container.graph[edge].len() - 1;
+        container.graph[edge][last].bend_points = wire.bend_points;
+    }
+
+    Ok(container)
+}
+
+// brings a parsed file of any known version up to the current one, with notes on what changed for the load warnings
+pub fn migrate(mut value: Value) -> Result<(Value, Vec<String>), String> {
+    let version = match value.get("version") {
+        Some(v) => v.as_u64().ok_or_else(|| format!("invalid version {}", v))?,
+        None => 0,
+    };
+    if version > CURRENT_VERSION {
+        return Err(format!("file version {} is newer than the supported version {}", version, CURRENT_VERSION));
+    }
+    for migration in &MIGRATIONS[version as usize..] {
+        value = migration(value)?;
+    }
+    let mut notes = Vec::new();
+    if version < CURRENT_VERSION {
+        notes.push(format!("migrated from file version {} to {}, saving writes version {}", version, CURRENT_VERSION, CURRENT_VERSION));
+    }
+    if version == 0 {
+        notes.push("the output boxes of version 0 drove the circuit, they are input boxes now and the input boxes are output boxes".into());
+    }
+    Ok((value, notes))
+}
+
+// version 0 is the serde dump of the petgraph graph, wires refer to node and connector indices
+fn migrate_v0(value: Value) -> Result<Value, String> {
+    let mut problems = Vec::new();
+    let mut circuit = migrate_v0_container(&value, "", &mut problems)?;
+    if !problems.is_empty() {
+        return Err(problem_list(&problems));
+    }
+    circuit.insert("version".into(), json!(1));
+    Ok(Value::Object(circuit))
+}
+
+// version 2 added the optional probes of a component
+fn migrate_v1(mut value: Value) -> Result<Value, String> {
+    value["version"] = json!(2);
+    Ok(value)
+}
+
+fn migrate_v0_container(value: &Value, prefix: &str, problems: &mut Vec<String>) -> Result<Map<String, Value>, String> {
+    let graph = value.get("graph").ok_or("missing graph")?;
+    let nodes = graph.get("nodes").and_then(Value::as_array).ok_or("missing graph nodes")?;
+
+    let mut ids = Vec::new();
+    // (name, is input) of the connectors of every box, indexed like in the file
+    let mut box_connectors: Vec<Vec<(String, bool)>> = Vec::new();
+    let mut counters: HashMap<String, usize> = HashMap::new();
+    let mut components = Vec::new();
+    for node in nodes {
+        let connectors: Vec<(String, bool)> = node.get("connectors").and_then(Value::as_array).ok_or("missing connectors")?
+            .iter()
+            .map(|c| (c.get("name").and_then(Value::as_str).unwrap_or("").to_string(),
+                      c.get("direction").and_then(Value::as_str) == Some("Input")))
+            .collect();
+        let inputs: Vec<&str> = connectors.iter().filter(|c| c.1).map(|c| c.0.as_str()).collect();
+        let outputs: Vec<&str> = connectors.iter().filter(|c| !c.1).map(|c| c.0.as_str()).collect();
+
+        // before the input box got output pins "input" and "output" were named after the pin direction
+        let mut kind = node.get("name").and_then(Value::as_str).ok_or("missing box name")?.to_string();
+        if (kind == "input" && outputs.is_empty() && !inputs.is_empty()) || (kind == "output" && inputs.is_empty() && !outputs.is_empty()) {
+            kind = if kind == "input" { "output".into() } else { "input".into() };
+        }
+
+        let counter = counters.entry(kind.clone()).or_insert(0);
+        *counter += 1;
+        let id = format!("{}{}", kind, counter);
+        let name = format!("{}{}", prefix, id);
+
+        // wires address connectors by index, which only maps to pins if the inputs come first
+        let field = |key: &str| node.get(key).and_then(Value::as_u64).map(|x| x as usize);
+        if field("output_start_idx") != Some(inputs.len()) || field("inputs_len") != Some(inputs.len()) || field("outputs_len") != Some(outputs.len()) {
+            problems.push(format!("box {}: output_start_idx {:?}, inputs_len {:?} and outputs_len {:?} do not match its {} input and {} output connectors",
+                                  name, field("output_start_idx"), field("inputs_len"), field("outputs_len"), inputs.len(), outputs.len()));
+        }
+        if let Some(i) = connectors.iter().position(|c| !c.1).filter(|&i| connectors[i..].iter().any(|c| c.1)) {
+            problems.push(format!("box {}: input connectors follow the output connector {}", name, connectors[i].0));
+        }
+        if let Some(c) = node["connectors"].as_array().unwrap().iter().enumerate()
+            .find(|(i, c)| c.get("idx").and_then(Value::as_u64) != Some(*i as u64)) {
+            problems.push(format!("box {}: connector {} has the index {} instead of {}", name, c.1.get("name").unwrap_or(&Value::Null), c.1.get("idx").unwrap_or(&Value::Null), c.0));
+        }
+
+        let mut component = Map::new();
+        component.insert("id".into(), json!(id));
+        component.insert("kind".into(), json!(kind));
+        for key in &["label", "position", "delay", "params"] {
+            if let Some(v) = node.get(*key) {
+                component.insert((*key).into(), v.clone());
+            }
+        }
+        component.insert("inputs".into(), json!(inputs));
+        component.insert("outputs".into(), json!(outputs));
+        if let Some(circuit) = node.get("circuit").filter(|c| !c.is_null()) {
+            let circuit = migrate_v0_container(circuit, &format!("{}/", name), problems)?;
+            component.insert("circuit".into(), Value::Object(circuit));
+        }
+
+        ids.push(id);
+        box_connectors.push(connectors);
+        components.push(Value::Object(component));
+    }
+
+    let connector_name = |node: usize, idx: usize| match box_connectors[node].get(idx) {
+        Some((name, _)) => format!("{}{}.{}", prefix, ids[node], name),
+        None => format!("{}{}.#{}", prefix, ids[node], idx),
+    };
+
+    let mut wires = Vec::new();
+    let mut drivers: HashMap<(usize, usize), String> = HashMap::new();
+    let edges = graph.get("edges").and_then(Value::as_array).cloned().unwrap_or_default();
+    for edge in edges.iter().filter(|e| !e.is_null()) {
+        let (source, target, weight) = match edge.as_array().map(Vec::as_slice) {
+            Some([s, t, w]) => (s.as_u64(), t.as_u64(), w),
+            _ => {
+                problems.push(format!("invalid edge {}", edge));
+                continue;
+            }
+        };
+        let (source, target) = match (source, target) {
+            (Some(s), Some(t)) if (s as usize) < ids.len() && (t as usize) < ids.len() => (s as usize, t as usize),
+            _ => {
+                problems.push(format!("edge {} refers to a missing box", edge));
+                continue;
+            }
+        };
+        // wires were (output, input) pairs before bend points were added
+        for wire in weight.as_array().cloned().unwrap_or_default() {
+            let (output, input, bend_points) = match &wire {
+                Value::Array(pair) if pair.len() == 2 => (pair[0].as_u64(), pair[1].as_u64(), json!([])),
+                Value::Object(w) => (w.get("output").and_then(Value::as_u64), w.get("input").and_then(Value::as_u64),
+                                     w.get("bend_points").cloned().unwrap_or_else(|| json!([]))),
+                _ => (None, None, Value::Null),
+            };
+            let (output, input) = match (output, input) {
+                (Some(o), Some(i)) => (o as usize, i as usize),
+                _ => {
+                    problems.push(format!("invalid wire {}", wire));
+                    continue;
+                }
+            };
+
+            let description = format!("wire {} -> {}", connector_name(source, output), connector_name(target, input));
+            let mut valid = true;
+            for (node, idx, expect_input) in [(source, output, false), (target, input, true)] {
+                match box_connectors[node].get(idx) {
+                    None => {
+                        problems.push(format!("{}: {}{} has no connector {}", description, prefix, ids[node], idx));
+                        valid = false;
+                    }
+                    Some((_, is_input)) if *is_input != expect_input => {
+                        problems.push(format!("{}: {} is an {}", description, connector_name(node, idx), if *is_input { "input" } else { "output" }));
+                        valid = false;
+                    }
+                    _ => {}
+                }
+            }
+            if !valid {
+                continue;
+            }
+            if let Some(other) = drivers.insert((target, input), connector_name(source, output)) {
+                problems.push(format!("input {} is driven by both {} and {}", connector_name(target, input), other, connector_name(source, output)));
+            }
+
+            let output_pin = box_connectors[source][..output].iter().filter(|c| !c.1).count();
+            let input_pin = box_connectors[target][..input].iter().filter(|c| c.1).count();
+            wires.push(json!({
+                "from": { "component": ids[source], "pin": output_pin },
+                "to": { "component": ids[target], "pin": input_pin },
+                "bend_points": bend_points,
+            }));
+        }
+    }
+
+    let mut circuit = Map::new();
+    circuit.insert("components".into(), Value::Array(components));
+    circuit.insert("wires".into(), Value::Array(wires));
+    circuit.insert("annotations".into(), value.get("annotations").cloned().unwrap_or_else(|| json!([])));
+    Ok(circuit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthesis::synthesize_text;
+
+    fn reload(value: Value) -> Result<Container, String> {
+        migrate(value).and_then(|(v, _)| serde_json::from_value(v).map_err(|e| e.to_string())).and_then(from_file)
+    }
+
+    #[test]
+    fn keeps_probes_and_migrates_version_1() {
+        let mut container = synthesize_text("y = !a").unwrap();
+        let output = container.find_id("output1").unwrap();
+        container.graph[output].connectors[0].probe = true;
+        let value = serde_json::to_value(to_file(&container, false)).unwrap();
+        assert_eq!(value["version"], json!(2));
+
+        let loaded = reload(value.clone()).unwrap();
+        let probed: Vec<(&str, &str)> = loaded.graph.raw_nodes().iter()
+            .flat_map(|n| n.weight.connectors.iter().filter(|c| c.probe).map(move |c| (n.weight.id.as_str(), c.name.as_str())))
+            .collect();
+        assert_eq!(probed, vec![("output1", "y")]);
+
+        let mut version_1 = value.clone();
+        version_1["version"] = json!(1);
+        assert!(reload(version_1).is_ok());
+
+        let mut wrong = value;
+        let components = wrong["components"].as_array_mut().unwrap();
+        let output = components.iter_mut().find(|c| c["id"] == "output1").unwrap();
+        output["probes"] = json!([1]);
+        assert_eq!(reload(wrong).err(), Some("component output1 probes the pin 1 but has only 1".into()));
+    }
+
+    #[test]
+    fn migrates_version_0_with_swapped_boxes_and_a_sub_circuit() {
+        let value: Value = serde_json::from_str(include_str!("../tests/data/version0.json")).unwrap();
+        let (migrated, notes) = migrate(value.clone()).unwrap();
+        assert_eq!(migrated["version"], json!(CURRENT_VERSION));
+        assert_eq!(notes.len(), 2);
+
+        let components = |circuit: &Value| circuit["components"].as_array().unwrap().iter()
+            .map(|c| (c["id"].as_str().unwrap().to_string(), c["inputs"].clone(), c["outputs"].clone()))
+            .collect::<Vec<_>>();
+        let wires = |circuit: &Value| circuit["wires"].as_array().unwrap().iter()
+            .map(|w| format!("{}.{} -> {}.{}", w["from"]["component"].as_str().unwrap(), w["from"]["pin"], w["to"]["component"].as_str().unwrap(), w["to"]["pin"]))
+            .collect::<Vec<_>>();
+        // the old output box drove the circuit, so it is the input box now
+        assert_eq!(components(&migrated), vec![
+            ("input1".to_string(), json!([]), json!(["a", "b"])),
+            ("output1".into(), json!(["sum", "carry"]), json!([])),
+            ("circuit1".into(), json!(["x", "y"]), json!(["z"])),
+            ("xor1".into(), json!(["i1", "i2"]), json!(["xor"])),
+        ]);
+        assert_eq!(wires(&migrated), vec![
+            "input1.0 -> xor1.0", "input1.1 -> xor1.1", "input1.0 -> circuit1.0", "input1.1 -> circuit1.1",
+            "xor1.0 -> output1.0", "circuit1.0 -> output1.1",
+        ]);
+        assert_eq!(migrated["wires"][2]["bend_points"], json!([[300.0, 350.0]]));
+        assert_eq!(migrated["components"][2]["label"], json!("carry"));
+
+        let inner = &migrated["components"][2]["circuit"];
+        assert_eq!(components(inner), vec![
+            ("input1".to_string(), json!([]), json!(["x", "y"])),
+            ("and1".into(), json!(["i1", "i2"]), json!(["and"])),
+            ("output1".into(), json!(["z"]), json!([])),
+        ]);
+        assert_eq!(wires(inner), vec!["input1.0 -> and1.0", "input1.1 -> and1.1", "and1.0 -> output1.0"]);
+
+        // the migrated file is a working half adder
+        let mut container = reload(value).unwrap();
+        container.set_inputs(&[true, true]);
+        assert!(crate::simulation::settle(&mut container));
+        assert_eq!(container.outputs(), vec![false, true]);
+    }
+
+    #[test]
+    fn reports_every_invalid_edge_and_wire_of_version_0() {
+        let mut value: Value = serde_json::from_str(include_str!("../tests/data/version0.json")).unwrap();
+        let edges = value["graph"]["edges"].as_array_mut().unwrap();
+        edges.push(json!([0, 3]));
+        edges.push(json!([0, 1, [[0], [1, 1]]]));
+        assert_eq!(migrate(value).err(), Some("3 problems\n\
+            invalid edge [0,3]\n\
+            invalid wire [0]\n\
+            input output1.carry is driven by both circuit1.z and input1.b".into()));
+    }
+}
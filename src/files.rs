@@ -0,0 +1,202 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::bench::import_bench;
+use crate::blif::import_blif;
+use crate::container::Container;
+use crate::file_format::{from_file, migrate, to_file};
+use crate::logisim::{import_logisim, is_logisim};
+use crate::rom;
+use crate::synthesis::synthesize_table;
+use crate::truth_table::TruthTable;
+use crate::yosys::{import_yosys, is_yosys_netlist};
+
+const RECENT_FILES_LIMIT: usize = 10;
+
+// our circuit files and the yosys netlists are json, the others are imported, truth tables are synthesized
+pub const OPEN_FORMATS: &[&str] = &["json", "circ", "blif", "bench", "csv", "md"];
+
+pub fn config_dir() -> Option<PathBuf> {
+    std::env::var_os("XDG_CONFIG_HOME").map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .map(|dir| dir.join("rustcpu"))
+}
+
+pub struct LoadedFile {
+    pub container: Container,
+    // true if the file was in another format, saving must not overwrite it with ours
+    pub imported: bool,
+    // parts of an imported file that could not be imported, or how an old circuit file was migrated
+    pub warnings: Vec<String>,
+}
+
+pub fn load_container(path: &Path) -> Result<Container, String> {
+    load_file(path).map(|loaded| loaded.container)
+}
+
+// reads our circuit files and the formats we can import
+pub fn load_file(path: &Path) -> Result<LoadedFile, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("Cannot open {}: {}", path.display(), e))?;
+    // the text netlist formats are only recognized by their extension
+    let extension = path.extension().unwrap_or_default().to_string_lossy().to_lowercase();
+    let imported = |container| LoadedFile { container, imported: true, warnings: Vec::new() };
+    let mut loaded = match extension.as_str() {
+        "blif" => import_blif(&text).map(imported),
+        "bench" => import_bench(&text).map(imported),
+        "csv" | "md" => TruthTable::parse(&text).and_then(|table| synthesize_table(&table)).map(imported),
+        _ => parse_file(&text),
+    }.map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    // rom files are found next to the circuit file
+    let errors = rom::load_all(&mut loaded.container, path.parent());
+    loaded.warnings.extend(errors);
+    Ok(loaded)
+}
+
+fn parse_file(text: &str) -> Result<LoadedFile, String> {
+    if is_logisim(text) {
+        return import_logisim(text).map(|(container, warnings)| LoadedFile { container, imported: true, warnings });
+    }
+    let value: Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+    if is_yosys_netlist(&value) {
+        return import_yosys(&value).map(|container| LoadedFile { container, imported: true, warnings: Vec::new() });
+    }
+    let (value, warnings) = migrate(value)?;
+    serde_json::from_value(value).map_err(|e| e.to_string())
+        .and_then(from_file)
+        .map(|container| LoadedFile { container, imported: false, warnings })
+}
+
+pub fn save_container(path: &Path, container: &Container, include_state: bool) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(&to_file(container, include_state)).map_err(|e| e.to_string())?;
+    File::create(path).and_then(|mut file| file.write_all(json.as_bytes()))
+        .map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+}
+
+// the most recently opened or saved files, newest first
+pub struct RecentFiles {
+    pub paths: Vec<PathBuf>,
+    // where the list is kept, none without a config directory
+    file: Option<PathBuf>,
+}
+
+impl RecentFiles {
+    pub fn load() -> RecentFiles {
+        RecentFiles::load_from(config_dir().map(|dir| dir.join("recent.json")))
+    }
+
+    pub(crate) fn load_from(file: Option<PathBuf>) -> RecentFiles {
+        let paths = file.as_ref()
+            .and_then(|path| File::open(path).ok())
+            .and_then(|file| serde_json::from_reader(file).ok())
+            .unwrap_or_default();
+        RecentFiles { paths, file }
+    }
+
+    pub fn add(&mut self, path: &Path) {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        self.paths.retain(|p| *p != path);
+        self.paths.insert(0, path);
+        self.paths.truncate(RECENT_FILES_LIMIT);
+
+        if let Some(recent_path) = &self.file {
+            let result = recent_path.parent().map_or(Ok(()), std::fs::create_dir_all)
+                .and_then(|_| File::create(recent_path))
+                .map_err(|e| e.to_string())
+                .and_then(|file| serde_json::to_writer_pretty(file, &self.paths).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                println!("Error saving recent files: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synthesis::synthesize_text;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("rustcpu-files-{}-{}", std::process::id(), name))
+    }
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data").join(name)
+    }
+
+    #[test]
+    fn recent_files_are_newest_first_without_duplicates() {
+        let file = temp_path("recent.json");
+        let mut recent = RecentFiles::load_from(Some(file.clone()));
+        assert!(recent.paths.is_empty());
+
+        for i in 0..12 {
+            recent.add(Path::new(&format!("/nonexistent/{}.json", i)));
+        }
+        recent.add(Path::new("/nonexistent/5.json"));
+        let expected: Vec<PathBuf> = [5, 11, 10, 9, 8, 7, 6, 4, 3, 2].iter()
+            .map(|i| PathBuf::from(format!("/nonexistent/{}.json", i)))
+            .collect();
+        assert_eq!(recent.paths, expected);
+
+        // the list is kept for the next start
+        assert_eq!(RecentFiles::load_from(Some(file.clone())).paths, expected);
+        std::fs::remove_file(&file).unwrap();
+        assert!(RecentFiles::load_from(None).paths.is_empty());
+    }
+
+    #[test]
+    fn recent_files_are_stored_canonical() {
+        let mut recent = RecentFiles::load_from(None);
+        recent.add(&fixture("c17.bench"));
+        recent.add(&fixture("../data/c17.bench"));
+        assert_eq!(recent.paths, vec![fixture("c17.bench").canonicalize().unwrap()]);
+    }
+
+    #[test]
+    fn load_file_chooses_the_format() {
+        let imported = |name: &str| {
+            let loaded = load_file(&fixture(name)).unwrap();
+            assert!(loaded.imported && loaded.warnings.is_empty(), "{}", name);
+            (loaded.container.input_names().len(), loaded.container.output_names().len())
+        };
+        assert_eq!(imported("c17.bench"), (5, 2));
+        assert_eq!(imported("full_adder.blif"), (3, 2));
+        assert_eq!(imported("half_adder.circ"), (2, 2));
+        assert_eq!(imported("half_adder.yosys.json"), (2, 2));
+
+        let table = temp_path("table.csv");
+        std::fs::write(&table, "a,b,y\n0,0,0\n0,1,1\n1,0,1\n1,1,0\n").unwrap();
+        let loaded = load_file(&table).unwrap();
+        assert!(loaded.imported);
+        assert_eq!(loaded.container.output_names(), vec!["y".to_string()]);
+        std::fs::remove_file(&table).unwrap();
+
+        // our own files can be saved over
+        let circuit = temp_path("circuit.json");
+        save_container(&circuit, &synthesize_text("y = a & b").unwrap(), false).unwrap();
+        let loaded = load_file(&circuit).unwrap();
+        assert!(!loaded.imported && loaded.warnings.is_empty());
+        assert_eq!(loaded.container.input_names(), vec!["a".to_string(), "b".into()]);
+        std::fs::remove_file(&circuit).unwrap();
+
+        // old versions tell how they were migrated
+        let loaded = load_file(&fixture("version0.json")).unwrap();
+        assert!(!loaded.imported);
+        assert!(loaded.warnings[0].starts_with("migrated from file version 0 to "), "{:?}", loaded.warnings);
+    }
+
+    #[test]
+    fn load_file_names_the_file_in_errors() {
+        let path = temp_path("broken.bench");
+        std::fs::write(&path, "INPUT(a)\ny = FLY(a)\n").unwrap();
+        let error = load_file(&path).err().unwrap();
+        assert!(error.starts_with(&format!("Cannot read {}: ", path.display())), "{}", error);
+        std::fs::remove_file(&path).unwrap();
+
+        let error = load_file(&temp_path("missing.json")).err().unwrap();
+        assert!(error.starts_with("Cannot open "), "{}", error);
+    }
+}
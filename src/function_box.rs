@@ -1,9 +1,17 @@
+use std::collections::{BTreeMap, VecDeque};
+
 use crate::connector::{Connector, ConnectorDirection};
+use crate::container::Container;
 use crate::game::PosF;
 
-#[derive(Debug, serde::Deserialize, serde::Serialize)]
+pub fn default_delay() -> usize { 1 }
+
+#[derive(Debug, Clone)]
 pub struct FunctionBox {
+    // stable identifier within the container, used by the file format instead of node indices
+    pub id: String,
     pub name: String,
+    pub label: String,
     pub connectors: Vec<Connector>,
     output_start_idx: usize,
     pub outputs_len: usize,
@@ -11,16 +19,38 @@ pub struct FunctionBox {
 
     pub position: PosF,
     pub generation: usize, // increased in every tick to avoid infinite recursion in circles
+
+    // number of ticks until a change of the inputs shows up at the outputs
+    pub delay: usize,
+    // kind specific parameters like the period of a clock
+    pub params: BTreeMap<String, String>,
+    // output states computed but not yet visible because of the delay
+    pub pending_outputs: VecDeque<Vec<bool>>,
+
+    // the embedded circuit of a "circuit" box, its input and output boxes become the connectors
+    pub circuit: Option<Box<Container>>,
+    // the words of a "rom" box, read when its file param is set or the circuit is loaded
+    pub(crate) rom: Vec<u64>,
 }
 
 impl FunctionBox {
+    #[allow(dead_code)]
     pub fn get_input_connector(&self, name: &str) -> &Connector {
         self.inputs_iter().find(|x| { x.name == name }).unwrap()
     }
+    #[allow(dead_code)]
     pub fn get_output_connector(&self, name: &str) -> &Connector {
         self.outputs_iter().find(|x| { x.name == name }).unwrap()
     }
 
+    pub fn display_name(&self) -> &str {
+        if self.label.is_empty() { &self.name } else { &self.label }
+    }
+
+    pub fn param<T: std::str::FromStr>(&self, name: &str) -> Option<T> {
+        self.params.get(name).and_then(|x| x.trim().parse().ok())
+    }
+
     pub fn inputs_iter(&self) -> impl Iterator<Item=&Connector> {
         self.connectors.iter().take(self.output_start_idx)
     }
@@ -29,6 +59,7 @@ impl FunctionBox {
         self.connectors.iter().skip(self.output_start_idx)
     }
 
+    #[allow(dead_code)]
     pub fn inputs_iter_mut(&mut self) -> impl Iterator<Item=&mut Connector> {
         self.connectors.iter_mut().take(self.output_start_idx)
     }
@@ -37,21 +68,46 @@ impl FunctionBox {
         self.connectors.iter_mut().skip(self.output_start_idx)
     }
 
-    pub(crate) fn new(name: &str, position: PosF, inputs: Vec<String>, outputs: Vec<String>) -> FunctionBox {
-        let output_start_idx = inputs.len();
-
-        FunctionBox {
+    pub fn new(name: &str, position: PosF, inputs: Vec<String>, outputs: Vec<String>) -> FunctionBox {
+        let mut function_box = FunctionBox {
+            id: String::new(),
             name: name.into(),
-            output_start_idx,
-            outputs_len: outputs.len(),
-            inputs_len: inputs.len(),
-            connectors:
-            inputs.into_iter().enumerate()
-                .chain(outputs.into_iter().enumerate()
-                    .map(|(i, x)| { (i + output_start_idx, x) }))
-                .map(|(i, n)| Connector::new(n, if i < output_start_idx { ConnectorDirection::Input } else { ConnectorDirection::Output }, i)).collect(),
+            label: String::new(),
+            output_start_idx: 0,
+            outputs_len: 0,
+            inputs_len: 0,
+            connectors: Vec::new(),
             position,
             generation: 0,
-        }
+            delay: default_delay(),
+            params: BTreeMap::new(),
+            pending_outputs: VecDeque::new(),
+            circuit: None,
+            rom: Vec::new(),
+        };
+        function_box.set_connectors(inputs, outputs);
+        function_box
+    }
+
+    // replaces the connectors, wires to them have to be fixed by the container
+    pub(crate) fn set_connectors(&mut self, inputs: Vec<String>, outputs: Vec<String>) {
+        let output_start_idx = inputs.len();
+
+        self.output_start_idx = output_start_idx;
+        self.outputs_len = outputs.len();
+        self.inputs_len = inputs.len();
+        self.connectors = inputs.into_iter().enumerate()
+            .chain(outputs.into_iter().enumerate()
+                .map(|(i, x)| { (i + output_start_idx, x) }))
+            .map(|(i, n)| Connector::new(n, if i < output_start_idx { ConnectorDirection::Input } else { ConnectorDirection::Output }, i)).collect();
+        self.pending_outputs.clear();
+    }
+
+    pub(crate) fn new_circuit(label: &str, position: PosF, circuit: Container) -> FunctionBox {
+        let mut function_box = FunctionBox::new("circuit", position, circuit.input_names(), circuit.output_names());
+        function_box.label = label.into();
+        function_box.circuit = Some(Box::new(circuit));
+        function_box
     }
 }
+
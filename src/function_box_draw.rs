@@ -1,9 +1,10 @@
 use std::cmp::max;
+use std::f64::consts::PI;
 
-use graphics::{line_from_to, Rectangle};
-use opengl_graphics::OpenGL;
+use graphics::{CircleArc, line_from_to, Rectangle};
+use graphics::types::Color;
 use petgraph::Direction;
-use vecmath::{vec2_add, vec2_sub};
+use vecmath::vec2_add;
 
 use crate::connector::{Connector, ConnectorDirection};
 use crate::container::{ConnectorRef, FBGraph, FunctionBoxRef};
@@ -13,17 +14,15 @@ use crate::ui::{draw_arc_centered, draw_text_centered, rgba};
 use crate::util::rect_center;
 
 pub struct ConnectorDraw<'a> {
-    idx: usize,
     connector: &'a Connector,
     highlighted: bool,
     connected: bool,
 }
 
 impl<'a> ConnectorDraw<'a> {
-    pub fn new(connector: &'a Connector, idx: usize) -> ConnectorDraw {
+    pub fn new(connector: &'a Connector) -> ConnectorDraw<'a> {
         ConnectorDraw {
             connector,
-            idx,
             highlighted: false,
             connected: false,
         }
@@ -59,11 +58,15 @@ impl<'a> FunctionBoxDraw<'a> {
             connector_radius,
             connector_margin,
             highlighted: false,
-            connector_draws: function_box.connectors.iter().enumerate()
-                .map(|(i, c)| ConnectorDraw::new(c, i)).collect(),
+            connector_draws: function_box.connectors.iter()
+                .map(ConnectorDraw::new).collect(),
         }
     }
 
+    pub fn rect(&self) -> [f64; 4] {
+        self.rect
+    }
+
     pub fn connector_position(&self, connector: &Connector) -> PosF {
         let i = if matches!(connector.direction, ConnectorDirection::Input) { connector.idx } else { connector.idx - self.function_box.inputs_len } as f64;
 
@@ -73,13 +76,68 @@ impl<'a> FunctionBoxDraw<'a> {
         ]
     }
 
-    pub fn draw_connection_line(&self, connector: &Connector, target: PosF, ctx: &mut DrawCtx) {
+    // marks the box or one of its connectors, the outline stays outside of what the box draws
+    pub fn draw_outline(&self, connector: Option<ConnectorRef>, color: Color, ctx: &mut DrawCtx) {
+        match connector {
+            Some(c) => {
+                let [x, y] = self.connector_position(&self.function_box.connectors[c]);
+                let r = self.connector_radius + 4.;
+                CircleArc::new(color, 1., 0., 2. * PI).draw_tri([x - r, y - r, 2. * r, 2. * r], &Default::default(), ctx.c.transform, ctx.g);
+            }
+            None => {
+                let [x, y, w, h] = self.rect;
+                Rectangle::new_round_border(color, 5., 1.5).draw_tri([x - 6., y - 6., w + 12., h + 12.], &Default::default(), ctx.c.transform, ctx.g);
+            }
+        }
+    }
+
+    pub fn draw_connection_line(&self, connector: &Connector, bend_points: &[PosF], target: PosF, orthogonal: bool, ctx: &mut DrawCtx) {
         let bg = if connector.state { rgba(214, 48, 49, 1.0) } else { rgba(99, 110, 114, 1.0) };
 
-        line_from_to(bg, 1., self.connector_position(connector), target, ctx.c.transform, ctx.g);
+        wire_path(self.connector_position(connector), bend_points, target, orthogonal)
+            .windows(2)
+            .for_each(|s| line_from_to(bg, 1., s[0], s[1], ctx.c.transform, ctx.g));
+        bend_points.iter()
+            .for_each(|&p| draw_arc_centered(p, 2.5, bg, ctx));
+    }
+}
+
+// Outputs sit on top of a box and inputs on the bottom, so orthogonal wires leave and enter
+// the connectors vertically and run horizontally in between.
+pub fn wire_leg(from: PosF, to: PosF, first: bool, last: bool, orthogonal: bool) -> Vec<PosF> {
+    if !orthogonal {
+        vec![from, to]
+    } else if first && last {
+        let mid = (from[1] + to[1]) / 2.;
+        vec![from, [from[0], mid], [to[0], mid], to]
+    } else if last {
+        vec![from, [to[0], from[1]], to]
+    } else {
+        vec![from, [from[0], to[1]], to]
     }
 }
 
+pub fn wire_legs(start: PosF, bend_points: &[PosF], end: PosF, orthogonal: bool) -> Vec<Vec<PosF>> {
+    let points: Vec<PosF> = Some(start).into_iter()
+        .chain(bend_points.iter().copied())
+        .chain(Some(end))
+        .collect();
+
+    points.windows(2).enumerate()
+        .map(|(i, p)| wire_leg(p[0], p[1], i == 0, i == points.len() - 2, orthogonal))
+        .collect()
+}
+
+pub fn wire_path(start: PosF, bend_points: &[PosF], end: PosF, orthogonal: bool) -> Vec<PosF> {
+    let mut path: Vec<PosF> = Vec::new();
+    wire_legs(start, bend_points, end, orthogonal).into_iter()
+        .for_each(|leg| {
+            let skip = if path.is_empty() { 0 } else { 1 };
+            path.extend(leg.into_iter().skip(skip));
+        });
+    path
+}
+
 pub enum FunctionBoxCollideDesc {
     FunctionBox,
     Connector(ConnectorRef),
@@ -93,7 +151,7 @@ impl Collide for FunctionBoxDraw<'_> {
 
         if let Some((i, _conn)) =
         self.function_box.connectors.iter().enumerate()
-            .find(|(i, x)| {
+            .find(|(_i, x)| {
                 let [x2, y2] = self.connector_position(x);
 
                 f64::sqrt((x1 - x2) * (x1 - x2) + (y1 - y2) * (y1 - y2)) <= self.connector_radius*self.connector_radius
@@ -114,7 +172,7 @@ impl Draw for FunctionBoxDraw<'_> {
         rectangle = rectangle.color(bg_color);
         rectangle.draw_tri(self.rect, &Default::default(), ctx.c.transform, ctx.g);
 
-        draw_text_centered(&self.function_box.name, 16,
+        draw_text_centered(self.function_box.display_name(), 16,
                            rect_center(self.rect), rgba(223, 230, 233, 1.0), ctx);
 
         self.connector_draws.iter()
@@ -141,12 +199,12 @@ impl Draw for FunctionBoxDraw<'_> {
 impl<'a> Update for FunctionBoxDraw<'a> {
     fn update(&mut self, state: &State) {
         let i = self.idx;
-        if let Some((fb, hpos)) = &state.dragged_function_box {
+        if let Some((fb, _hpos)) = &state.dragged_function_box {
             if i == *fb {
                 self.highlighted = true;
             }
         }
-        if let Some((fb, conn, hpos)) = &state.dragged_connector {
+        if let Some((fb, conn, _hpos)) = &state.dragged_connector {
             if i == *fb {
                 self.connector_draws[*conn].highlighted = true;
             }
@@ -161,8 +219,8 @@ impl<'a> Update for FunctionBoxDraw<'a> {
             }
         }
         state.container.graph.edges_directed(self.idx, Direction::Outgoing)
-            .flat_map(|x| { x.weight().iter().map(|y| &y.0) })
-            .chain(state.container.graph.edges_directed(self.idx, Direction::Incoming).flat_map(|x| { x.weight().iter().map(|y| &y.1) }))
+            .flat_map(|x| { x.weight().iter().map(|y| &y.output) })
+            .chain(state.container.graph.edges_directed(self.idx, Direction::Incoming).flat_map(|x| { x.weight().iter().map(|y| &y.input) }))
             .for_each(|x| {
                 self.connector_draws[*x].connected = true;
             })
@@ -179,4 +237,33 @@ pub fn output_input_pair(graph: &FBGraph, c1: (FunctionBoxRef, ConnectorRef), c2
     } else {
         None
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn straight_wires_connect_the_points() {
+        let legs = wire_legs([0., 0.], &[[10., 20.]], [30., 40.], false);
+        assert_eq!(legs, vec![vec![[0., 0.], [10., 20.]], vec![[10., 20.], [30., 40.]]]);
+        assert_eq!(wire_path([0., 0.], &[[10., 20.]], [30., 40.], false), vec![[0., 0.], [10., 20.], [30., 40.]]);
+    }
+
+    #[test]
+    fn orthogonal_wires_leave_and_enter_vertically() {
+        // without bend points the wire turns halfway between the connectors
+        assert_eq!(wire_path([0., 0.], &[], [20., 40.], true), vec![[0., 0.], [0., 20.], [20., 20.], [20., 40.]]);
+
+        let legs = wire_legs([0., 0.], &[[10., 20.], [30., 30.]], [40., 50.], true);
+        assert_eq!(legs, vec![
+            vec![[0., 0.], [0., 20.], [10., 20.]],
+            vec![[10., 20.], [10., 30.], [30., 30.]],
+            vec![[30., 30.], [40., 30.], [40., 50.]],
+        ]);
+        // the shared points of the legs appear once
+        assert_eq!(wire_path([0., 0.], &[[10., 20.], [30., 30.]], [40., 50.], true), vec![
+            [0., 0.], [0., 20.], [10., 20.], [10., 30.], [30., 30.], [40., 30.], [40., 50.],
+        ]);
+    }
+}
@@ -1,64 +1,168 @@
-use core::fmt;
-use std::borrow::{Borrow, BorrowMut};
-use std::fmt::{Display, Formatter};
-use std::iter::{Take, once};
-use std::ops::{Index, IndexMut};
-use std::slice::Iter;
-
-use graphics::{color, Context, line_from_to};
+use std::collections::{HashMap, HashSet};
+use std::ops::IndexMut;
+
+use graphics::{Context, line_from_to};
 use graphics::math::Vec2d;
 use opengl_graphics::{GlGraphics, GlyphCache};
-use petgraph::{Direction, Graph};
-use petgraph::algo::connected_components;
-use petgraph::graph::{DefaultIx, NodeIndex};
+use petgraph::Direction;
 use petgraph::prelude::EdgeRef;
-use piston::{Size, Window};
+use piston::{Key, Size, Window};
+use piston::keyboard::ModifierKey;
 use vecmath::vec2_sub;
 
+use crate::annotation::{Annotation, AnnotationCollideDesc, AnnotationDraw};
 use crate::button::Button;
-use crate::connector::{Connector, ConnectorDirection};
-use crate::connector::ConnectorDirection::{Input, Output};
-use crate::container::{ConnectorRef, Container, FBGraph, FunctionBoxRef};
-use crate::function_box::FunctionBox;
-use crate::function_box_draw::{FunctionBoxCollideDesc, FunctionBoxDraw, output_input_pair};
-use std::fs::File;
-use std::io::Write;
-use std::error::Error;
+use crate::component::{ComponentEntry, find_kind};
+use crate::connector::ConnectorDirection;
+use crate::equivalence::{check_equivalence, Equivalence};
+use crate::file_dialog::{DialogMode, FileDialog};
+use crate::files::{load_container, load_file, RecentFiles, save_container};
+use crate::container::{BendPointRef, ConnectorRef, Container, FunctionBoxRef};
+use crate::function_box_draw::{FunctionBoxCollideDesc, FunctionBoxDraw, output_input_pair, wire_legs};
+use crate::inspector::Inspector;
+use crate::keymap::{Action, KeyMap};
+use crate::lint::{lint, Rule};
+use crate::minimize::describe_outputs;
+use crate::palette::Palette;
+use crate::quick_add::QuickAdd;
+use crate::recovery::RecoveryPrompt;
+use crate::simulation;
+use crate::statistics::{critical_path, statistics};
+use crate::synthesis::synthesize_text;
+use crate::synthesis_panel::SynthesisPanel;
+use crate::truth_table::{truth_table, TruthTable};
+use crate::truth_table_panel::{TableFormat, TruthTablePanel};
+use crate::ui::{draw_text, rgba};
+use crate::util::{distance, distance_to_segment, GRID_SIZE, snap_to_grid};
+use crate::waveform::Waveform;
+use std::path::{Path, PathBuf};
 
 pub type PosF = Vec2d;
-type OurGraphics = GlGraphics;
+// (rule, connector) of the design rule findings by box
+pub type Findings = HashMap<FunctionBoxRef, Vec<(Rule, Option<ConnectorRef>)>>;
+
+const UNDO_LIMIT: usize = 100;
 
 
 #[derive(Debug)]
 pub enum EntityKind {
     FunctionBox,
     Connector,
+    BendPoint,
+    Annotation,
 }
 
 #[derive(Debug)]
+pub enum Message {
+    Info(String),
+    Error(String),
+}
+
 pub struct State {
     pub mouse_position: PosF,
     pub mouse_button1_pressed: bool,
+    pub mouse_button2_clicked: bool,
     pub mouse_delta: PosF,
     pub window_size: Size,
+    pub keys_pressed: Vec<Key>,
+    pub modifiers: ModifierKey,
+    pub text_typed: String,
+
+    pub grid_snap: bool,
+    pub orthogonal_wires: bool,
+    // the findings of the design rule checks are outlined on the canvas
+    pub design_checks: bool,
+    // checked again after the container changed
+    pub findings: Option<Findings>,
+    // the critical path is outlined on the canvas
+    pub statistics: bool,
+    // the boxes on the critical path, found again after the container changed
+    pub critical_path: Option<HashSet<FunctionBoxRef>>,
+    // the probed connectors are recorded on every tick until it is saved
+    pub waveform: Option<Waveform>,
+    pub simulation_running: bool,
+    pub step_requested: bool,
 
     pub container: Container,
+    pub file_path: Option<PathBuf>,
+    pub recent_files: RecentFiles,
+    // saved files contain only the design unless asked for in the save as dialog
+    pub save_simulation_state: bool,
+    // shown below the buttons, e.g. when saving failed
+    pub message: Option<Message>,
+    // a dialog has the input, the canvas ignores the mouse
+    pub modal_open: bool,
+    pub undo_stack: Vec<Container>,
+    // whether the current drag already changed the container and pushed an undo checkpoint
+    pub drag_modified: bool,
     pub dragged_entity_kind: Option<EntityKind>,
     pub dragged_function_box: Option<(FunctionBoxRef, PosF)>,
     pub dragged_connector: Option<(FunctionBoxRef, ConnectorRef, PosF)>,
     pub dragged_connector_target: Option<(FunctionBoxRef, ConnectorRef, PosF)>,
+    pub dragged_bend_point: Option<BendPointRef>,
+    pub dragged_annotation: Option<(usize, PosF, AnnotationCollideDesc)>,
+    pub selected_function_box: Option<FunctionBoxRef>,
+    pub selected_annotation: Option<usize>,
+}
 
-    pub output_fb: NodeIndex,
-    pub input_fb: NodeIndex,
+impl State {
+    pub fn snap(&self, pos: PosF) -> PosF {
+        if self.grid_snap { snap_to_grid(pos) } else { pos }
+    }
 
-}
+    // remembers the container before a change so it can be undone
+    pub fn checkpoint(&mut self) {
+        self.findings = None;
+        self.critical_path = None;
+        self.undo_stack.push(self.container.clone());
+        if self.undo_stack.len() > UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+    }
+
+    pub fn undo(&mut self) {
+        if let Some(container) = self.undo_stack.pop() {
+            self.container = container;
+            self.findings = None;
+            self.critical_path = None;
+            self.reset_interaction();
+        }
+    }
 
-pub trait Entity: Update+Draw{}
+    // forgets everything referring to boxes of the container, needed when boxes are removed or replaced
+    pub fn reset_interaction(&mut self) {
+        self.dragged_entity_kind = None;
+        self.dragged_function_box = None;
+        self.dragged_connector = None;
+        self.dragged_connector_target = None;
+        self.dragged_bend_point = None;
+        self.dragged_annotation = None;
+        self.selected_function_box = None;
+        self.selected_annotation = None;
+    }
+}
 
 pub struct Entities {
     pub add_fb_button: Button,
     pub save_button: Button,
+    pub save_as_button: Button,
     pub load_button: Button,
+    pub grid_button: Button,
+    pub route_button: Button,
+    pub label_button: Button,
+    pub frame_button: Button,
+    pub palette: Palette,
+    pub quick_add: QuickAdd,
+    pub inspector: Inspector,
+    pub key_map: KeyMap,
+    pub file_dialog: FileDialog,
+    pub recovery_prompt: RecoveryPrompt,
+    pub table_button: Button,
+    pub truth_table_panel: TruthTablePanel,
+    pub expression_button: Button,
+    pub synthesis_button: Button,
+    pub synthesis_panel: SynthesisPanel,
+    pub compare_button: Button,
 
     //entities: Vec<&'a dyn Entity>
 }
@@ -85,6 +189,7 @@ pub trait Draw {
 pub struct DrawCtx<'a, 'b> {
     pub g: &'a mut GlGraphics,
     pub c: &'a Context,
+    #[allow(dead_code)]
     pub window: &'a dyn Window,
     pub font_normal: &'a mut GlyphCache<'b>,
 }
@@ -93,24 +198,311 @@ pub(crate) fn update_entities(
     entities: &mut Entities,
     state: &mut State,
 ) {
+    // while a dialog is open it gets all the input
+    if entities.recovery_prompt.is_open() {
+        entities.recovery_prompt.update(state);
+        if let Some(path) = entities.recovery_prompt.restore() {
+            restore(state, &path);
+        }
+        state.modal_open = entities.recovery_prompt.is_open();
+        return;
+    }
+    if entities.file_dialog.is_open() {
+        entities.file_dialog.update(state);
+        match entities.file_dialog.chosen() {
+            Some((DialogMode::Open, path)) => load(state, &path),
+            Some((DialogMode::Compare, path)) => compare(state, &path),
+            Some((DialogMode::SaveAs, path)) => {
+                state.save_simulation_state = entities.file_dialog.include_state();
+                save(state, &path);
+                entities.palette.refresh();
+            }
+            None => {}
+        }
+        state.modal_open = entities.file_dialog.is_open();
+        return;
+    }
+    if entities.synthesis_panel.is_open() {
+        entities.synthesis_panel.update(state);
+        if let Some(text) = entities.synthesis_panel.requested() {
+            match synthesize_text(&text) {
+                Ok(container) => {
+                    state.checkpoint();
+                    state.container = container;
+                    state.reset_interaction();
+                    state.file_path = None;
+                    state.message = Some(Message::Info("Synthesized the circuit, save it to keep it".into()));
+                    entities.synthesis_panel.close();
+                }
+                Err(e) => state.message = Some(Message::Error(e)),
+            }
+        }
+        state.modal_open = entities.synthesis_panel.is_open();
+        return;
+    }
+    if entities.truth_table_panel.is_open() {
+        entities.truth_table_panel.update(state);
+        if let Some(format) = entities.truth_table_panel.export() {
+            if let Some(table) = entities.truth_table_panel.table() {
+                save_truth_table(state, table, format);
+            }
+        }
+        state.modal_open = entities.truth_table_panel.is_open();
+        return;
+    }
+
     entities.add_fb_button.update(state);
     entities.save_button.update(state);
+    entities.save_as_button.update(state);
     entities.load_button.update(state);
+    entities.grid_button.update(state);
+    entities.route_button.update(state);
+    entities.label_button.update(state);
+    entities.frame_button.update(state);
+    entities.table_button.update(state);
+    entities.expression_button.update(state);
+    entities.synthesis_button.update(state);
+    entities.compare_button.update(state);
+    entities.palette.update(state);
+    entities.quick_add.update(state);
+    entities.inspector.update(state);
+    entities.inspector.apply(state);
+
+    let actions = if entities.quick_add.is_open() || entities.inspector.has_focus() {
+        Vec::new()
+    } else {
+        entities.key_map.actions(&state.keys_pressed, state.modifiers)
+    };
 
     if entities.add_fb_button.pressed() {
-        println!("Pressed");
-        state.container.add(FunctionBox::new("nand", [100., 50.], vec!["i1".into(), "i2".into()], vec!["nand".into()]));
-    }
-    if entities.save_button.pressed() {
-        let json = serde_json::to_string_pretty(&state.container).unwrap();
-        println!("JSON: {}", json);
-        File::create("save.json").unwrap().write(json.as_bytes()).unwrap();
-    }
-    if entities.load_button.pressed() {
-        match File::open("save.json").map_err(|e| e.to_string())
-            .and_then(|file| serde_json::from_reader(file).map_err(|e| e.to_string())) {
-            Ok(graph) => state.container = graph,
-            Err(e) => println!("Error loading state: {}", e)
+        entities.quick_add.open([100., 50.]);
+    }
+    if actions.contains(&Action::QuickAdd) {
+        entities.quick_add.open(state.mouse_position);
+    }
+    if let Some((entry, position)) = entities.quick_add.chosen() {
+        let position = state.snap(position);
+        place_component(state, &entry, position);
+    }
+    if let Some(entry) = entities.palette.chosen() {
+        // the new box sticks to the cursor until the mouse button is released
+        let offset = [20., 20.];
+        if let Some(i) = place_component(state, &entry, vec2_sub(state.mouse_position, offset)) {
+            state.dragged_entity_kind = Some(EntityKind::FunctionBox);
+            state.dragged_function_box = Some((i, offset));
+            state.drag_modified = true;
+        }
+    }
+    if actions.contains(&Action::AddGate) {
+        let position = state.snap(state.mouse_position);
+        if let Some(kind) = find_kind("nand") {
+            state.checkpoint();
+            state.container.add(kind.create(position));
+        }
+    }
+    if entities.save_button.pressed() || actions.contains(&Action::Save) {
+        match state.file_path.clone() {
+            Some(path) => save(state, &path),
+            None => entities.file_dialog.open(DialogMode::SaveAs, None, state.save_simulation_state),
+        }
+    }
+    if entities.save_as_button.pressed() || actions.contains(&Action::SaveAs) {
+        entities.file_dialog.open(DialogMode::SaveAs, state.file_path.as_deref(), state.save_simulation_state);
+    }
+    if entities.load_button.pressed() || actions.contains(&Action::Load) {
+        entities.file_dialog.open(DialogMode::Open, state.file_path.as_deref(), state.save_simulation_state);
+    }
+    if entities.table_button.pressed() {
+        match truth_table(&state.container) {
+            Ok(table) => entities.truth_table_panel.open(table),
+            Err(e) => state.message = Some(Message::Error(e)),
+        }
+    }
+    if entities.expression_button.pressed() {
+        state.message = Some(match describe_outputs(&state.container) {
+            Ok(lines) if lines.is_empty() => Message::Info("The circuit has no outputs".into()),
+            Ok(lines) => Message::Info(lines.join("\n")),
+            Err(e) => Message::Error(e),
+        });
+    }
+    if entities.compare_button.pressed() {
+        entities.file_dialog.open(DialogMode::Compare, state.file_path.as_deref(), state.save_simulation_state);
+    }
+    if entities.synthesis_button.pressed() {
+        entities.synthesis_panel.open();
+    }
+    state.modal_open = entities.file_dialog.is_open() || entities.truth_table_panel.is_open() || entities.synthesis_panel.is_open();
+    if actions.contains(&Action::Undo) {
+        state.undo();
+    }
+    if actions.contains(&Action::Delete) {
+        if let Some(fb) = state.selected_function_box {
+            state.checkpoint();
+            state.container.remove(fb);
+            state.reset_interaction();
+        } else if let Some(i) = state.selected_annotation {
+            state.checkpoint();
+            state.container.annotations.remove(i);
+            state.reset_interaction();
+        }
+    }
+    if entities.label_button.pressed() {
+        add_annotation(state, Annotation::Label { text: "Text".into(), position: [300., 300.] });
+    }
+    if entities.frame_button.pressed() {
+        add_annotation(state, Annotation::Frame { name: "Frame".into(), rect: [300., 300., 200., 150.] });
+    }
+    if actions.contains(&Action::ToggleSimulation) {
+        state.simulation_running = !state.simulation_running;
+    }
+    if actions.contains(&Action::Step) {
+        state.step_requested = true;
+    }
+    if actions.contains(&Action::DesignChecks) {
+        state.design_checks = !state.design_checks;
+        state.message = if state.design_checks { Some(lint_message(&state.container)) } else { None };
+    }
+    if actions.contains(&Action::Statistics) {
+        state.statistics = !state.statistics;
+        state.message = if state.statistics {
+            Some(Message::Info(statistics(&state.container).report(&state.container).join("\n")))
+        } else {
+            None
+        };
+    }
+    if actions.contains(&Action::RecordWaveform) {
+        record_waveform(state);
+    }
+    if entities.grid_button.pressed() {
+        state.grid_snap = !state.grid_snap;
+    }
+    if entities.route_button.pressed() {
+        state.orthogonal_wires = !state.orthogonal_wires;
+    }
+}
+
+fn lint_message(container: &Container) -> Message {
+    const SHOWN: usize = 8;
+    let findings = lint(container);
+    if findings.is_empty() {
+        return Message::Info("The design rule checks found nothing".into());
+    }
+    let mut lines: Vec<String> = findings.iter().take(SHOWN).map(|f| f.message.clone()).collect();
+    if findings.len() > SHOWN {
+        lines.push(format!("and {} more", findings.len() - SHOWN));
+    }
+    Message::Error(format!("The design rule checks found {} problems\n{}", findings.len(), lines.join("\n")))
+}
+
+// starts recording the probes, the second time the recording is saved next to the circuit file
+fn record_waveform(state: &mut State) {
+    let waveform = match state.waveform.take() {
+        Some(waveform) => waveform,
+        None => {
+            let waveform = Waveform::new(&state.container);
+            state.message = Some(if waveform.signal_count() == 0 {
+                Message::Error("Nothing is probed, mark connectors as probes in the inspector".into())
+            } else {
+                let message = format!("Recording {} probes, record again to save the waveform", waveform.signal_count());
+                state.waveform = Some(waveform);
+                Message::Info(message)
+            });
+            return;
+        }
+    };
+    let path = state.file_path.as_deref().unwrap_or_else(|| Path::new("waveform")).with_extension("vcd");
+    state.message = Some(match std::fs::write(&path, waveform.to_vcd()) {
+        Ok(()) => Message::Info(format!("Saved {} ticks of {} probes to {}", waveform.ticks(), waveform.signal_count(), path.display())),
+        Err(e) => Message::Error(format!("Cannot write {}: {}", path.display(), e)),
+    });
+}
+
+// the table is saved next to the circuit file
+fn save_truth_table(state: &mut State, table: &TruthTable, format: TableFormat) {
+    let path = state.file_path.as_deref().unwrap_or_else(|| Path::new("truth_table")).with_extension(format.extension());
+    let text = match format {
+        TableFormat::Csv => table.to_csv(),
+        TableFormat::Markdown => table.to_markdown(),
+    };
+    state.message = Some(match std::fs::write(&path, text) {
+        Ok(()) => Message::Info(format!("Saved the truth table to {}", path.display())),
+        Err(e) => Message::Error(format!("Cannot write {}: {}", path.display(), e)),
+    });
+}
+
+fn add_annotation(state: &mut State, annotation: Annotation) {
+    state.checkpoint();
+    let i = state.container.add_annotation(annotation);
+    state.reset_interaction();
+    state.selected_annotation = Some(i);
+}
+
+pub(crate) fn save(state: &mut State, path: &Path) {
+    match save_container(path, &state.container, state.save_simulation_state) {
+        Ok(()) => {
+            state.file_path = Some(path.to_path_buf());
+            state.recent_files.add(path);
+            state.message = Some(Message::Info(format!("Saved {}", path.display())));
+        }
+        Err(e) => state.message = Some(Message::Error(e)),
+    }
+}
+
+pub(crate) fn load(state: &mut State, path: &Path) {
+    match load_file(path) {
+        Ok(loaded) => {
+            state.checkpoint();
+            state.container = loaded.container;
+            state.reset_interaction();
+            state.recent_files.add(path);
+            // saving must not overwrite the imported file with our format
+            if loaded.imported && !loaded.warnings.is_empty() {
+                state.file_path = None;
+                state.message = Some(Message::Error(format!("Imported {} with {} problems\n{}", path.display(), loaded.warnings.len(), loaded.warnings.join("\n"))));
+            } else if loaded.imported {
+                state.file_path = None;
+                state.message = Some(Message::Info(format!("Imported {}, save it as a circuit to keep changes", path.display())));
+            } else {
+                state.file_path = Some(path.to_path_buf());
+                // the notes of a migrated file
+                let lines: Vec<String> = std::iter::once(format!("Opened {}", path.display())).chain(loaded.warnings).collect();
+                state.message = Some(Message::Info(lines.join("\n")));
+            }
+        }
+        Err(e) => state.message = Some(Message::Error(e)),
+    }
+}
+
+fn compare(state: &mut State, path: &Path) {
+    let result = load_container(path).and_then(|other| check_equivalence(&state.container, &other));
+    state.message = Some(match result {
+        Ok(equivalence @ Equivalence::Equal) => Message::Info(equivalence.report("The circuit", &path.display().to_string())),
+        Ok(equivalence) => Message::Error(equivalence.report("The circuit", &path.display().to_string())),
+        Err(e) => Message::Error(e),
+    });
+}
+
+fn restore(state: &mut State, path: &Path) {
+    match load_container(path) {
+        Ok(container) => {
+            state.checkpoint();
+            state.container = container;
+            state.reset_interaction();
+            state.message = Some(Message::Info("Restored the autosaved circuit, save it to keep it".into()));
+        }
+        Err(e) => state.message = Some(Message::Error(e)),
+    }
+}
+
+fn place_component(state: &mut State, entry: &ComponentEntry, position: PosF) -> Option<FunctionBoxRef> {
+    match entry.create(position) {
+        Ok(function_box) => {
+            state.checkpoint();
+            Some(state.container.add(function_box))
+        }
+        Err(e) => {
+            state.message = Some(Message::Error(format!("Cannot add {}: {}", entry.name, e)));
+            None
         }
     }
 }
@@ -120,25 +512,79 @@ pub(crate) fn draw_entities(
     state: &State,
     ctx: &mut DrawCtx,
 ) {
+    if !state.simulation_running {
+        draw_text("Simulation stopped", 14, [50., 550.], rgba(45, 52, 54, 1.0), ctx);
+    }
+    if let Some(path) = &state.file_path {
+        draw_text(&path.to_string_lossy(), 14, [250., 550.], rgba(45, 52, 54, 1.0), ctx);
+    }
+    let message = match &state.message {
+        Some(Message::Info(text)) => Some((text, rgba(45, 52, 54, 1.0))),
+        Some(Message::Error(text)) => Some((text, rgba(214, 48, 49, 1.0))),
+        None => None,
+    };
+    if let Some((text, color)) = message {
+        for (i, line) in text.lines().enumerate() {
+            draw_text(line, 14, [50., 575. + i as f64 * 18.], color, ctx);
+        }
+    }
     entities.add_fb_button.draw(ctx);
     entities.save_button.draw(ctx);
+    entities.save_as_button.draw(ctx);
     entities.load_button.draw(ctx);
+    entities.grid_button.draw(ctx);
+    entities.route_button.draw(ctx);
+    entities.label_button.draw(ctx);
+    entities.frame_button.draw(ctx);
+    entities.table_button.draw(ctx);
+    entities.expression_button.draw(ctx);
+    entities.synthesis_button.draw(ctx);
+    entities.compare_button.draw(ctx);
+    entities.palette.draw(ctx);
+    entities.inspector.draw(ctx);
+    entities.quick_add.draw(ctx);
+    entities.file_dialog.draw(ctx);
+    entities.truth_table_panel.draw(ctx);
+    entities.synthesis_panel.draw(ctx);
+    entities.recovery_prompt.draw(ctx);
 }
 
 pub(crate) fn update(
     state: &mut State,
 ) {
     update_general_states(state);
-    update_fb_states(state);
+    if state.simulation_running || state.step_requested {
+        update_fb_states(state);
+        state.step_requested = false;
+    }
+    // checkpoints come before the edits, so this runs after them
+    if state.design_checks && state.findings.is_none() {
+        let mut findings = Findings::new();
+        for f in lint(&state.container) {
+            for &n in &f.boxes {
+                findings.entry(n).or_default().push((f.rule, f.connector));
+            }
+        }
+        state.findings = Some(findings);
+    }
+    if state.statistics && state.critical_path.is_none() {
+        let path = critical_path(&state.container).map(|(_, path)| path).unwrap_or_default();
+        state.critical_path = Some(path.into_iter().collect());
+    }
 }
 
 pub(crate) fn update_general_states(
     state: &mut State,
 ) {
+    if state.modal_open {
+        return;
+    }
+
     if !state.mouse_button1_pressed {
         if let (Some((fb1, c1, _)), Some((fb2, c2, _))) = (state.dragged_connector, state.dragged_connector_target) {
             let (output, input) = output_input_pair(&state.container.graph, (fb1, c1), (fb2, c2)).unwrap();
             println!("Connect {:?} to {:?}", output, input);
+            state.checkpoint();
             state.container.connect(output, input);
             println!("New graph {:?}", state.container.graph);
         }
@@ -146,20 +592,66 @@ pub(crate) fn update_general_states(
         state.dragged_function_box = None;
         state.dragged_connector = None;
         state.dragged_connector_target = None;
+        state.dragged_bend_point = None;
+        state.dragged_annotation = None;
         state.dragged_entity_kind = None;
+        state.drag_modified = false;
     }
 
-    state.container.graph.node_indices().for_each(|i| {
-        if state.mouse_button1_pressed {
-            if let Some((fb, hpos)) = state.dragged_function_box {
-                if i == fb {
-                    let pos = &mut state.container.graph.index_mut(i).position;
-                    *pos = vec2_sub(state.mouse_position, hpos);
-                    println!("Position {:?}", pos);
+    if state.mouse_button2_clicked {
+        update_bend_points(state);
+    }
+
+    if state.mouse_button1_pressed {
+        if let Some(bend) = state.dragged_bend_point {
+            let pos = state.snap(state.mouse_position);
+            if !state.drag_modified {
+                state.checkpoint();
+                state.drag_modified = true;
+            }
+            if !state.container.move_bend_point(bend, pos) {
+                state.dragged_bend_point = None;
+            }
+        } else if state.dragged_entity_kind.is_none() {
+            if let Some(bend) = find_bend_point(state, state.mouse_position) {
+                state.dragged_entity_kind = Some(EntityKind::BendPoint);
+                state.dragged_bend_point = Some(bend);
+            }
+        }
+    }
+
+    if state.mouse_button1_pressed {
+        if let Some((i, hpos, mode)) = state.dragged_annotation {
+            let new_pos = state.snap(vec2_sub(state.mouse_position, hpos));
+            let old_rect = state.container.annotations[i].rect();
+            let changed = match mode {
+                AnnotationCollideDesc::Move => new_pos != [old_rect[0], old_rect[1]],
+                AnnotationCollideDesc::Resize => new_pos != [old_rect[0] + old_rect[2], old_rect[1] + old_rect[3]],
+            };
+            if changed {
+                if !state.drag_modified {
+                    state.checkpoint();
+                    state.drag_modified = true;
+                }
+                match mode {
+                    AnnotationCollideDesc::Move => state.container.move_annotation(i, new_pos),
+                    AnnotationCollideDesc::Resize => state.container.resize_frame(i, vec2_sub(new_pos, [old_rect[0], old_rect[1]])),
                 }
             }
         }
-    });
+        if let Some((fb, hpos)) = state.dragged_function_box {
+            let new_pos = state.snap(vec2_sub(state.mouse_position, hpos));
+            if new_pos != state.container.graph[fb].position {
+                if !state.drag_modified {
+                    state.checkpoint();
+                    state.drag_modified = true;
+                }
+                let pos = &mut state.container.graph.index_mut(fb).position;
+                *pos = new_pos;
+                println!("Position {:?}", pos);
+            }
+        }
+    }
 
     state.dragged_connector_target = None;
     state.container.graph.node_indices().for_each(|i| {
@@ -169,16 +661,21 @@ pub(crate) fn update_general_states(
             let origin = vec2_sub(state.mouse_position, state.container.graph[i].position);
 
             match (&state.dragged_entity_kind, draw.collide(state.mouse_position)) {
-                (None, Some(FunctionBoxCollideDesc::FunctionBox)) => {
-                    if let None = state.dragged_function_box {
-                        state.dragged_entity_kind = Some(EntityKind::FunctionBox);
-                        state.dragged_function_box = Some((i, origin));
-                    }
+                (None, Some(FunctionBoxCollideDesc::FunctionBox)) if state.dragged_function_box.is_none() => {
+                    state.dragged_entity_kind = Some(EntityKind::FunctionBox);
+                    state.dragged_function_box = Some((i, origin));
+                    state.selected_function_box = Some(i);
+                    state.selected_annotation = None;
                 }
                 (None, Some(FunctionBoxCollideDesc::Connector(connector))) => {
                     let connector1 = &state.container.graph[i].connectors[connector];
                     if let ConnectorDirection::Input = connector1.direction {
+                        if state.container.input_driver((i, connector)).is_some() {
+                            state.checkpoint();
+                        }
                         state.container.disconnect((i, connector));
+                        // removing an edge moves the last one to its index
+                        state.dragged_bend_point = None;
                     }
 
                     state.dragged_entity_kind = Some(EntityKind::Connector);
@@ -193,6 +690,62 @@ pub(crate) fn update_general_states(
                 _ => {}
             }
         }
+    });
+
+    if state.mouse_button1_pressed && state.dragged_entity_kind.is_none() {
+        let hit = state.container.annotations.iter().enumerate().rev()
+            .find_map(|(i, a)| a.collide(state.mouse_position).map(|mode| (i, a.rect(), mode)));
+        if let Some((i, rect, mode)) = hit {
+            // the offset keeps the grabbed point (corner for resizing) under the cursor
+            let origin = match mode {
+                AnnotationCollideDesc::Move => [rect[0], rect[1]],
+                AnnotationCollideDesc::Resize => [rect[0] + rect[2], rect[1] + rect[3]],
+            };
+            state.dragged_entity_kind = Some(EntityKind::Annotation);
+            state.dragged_annotation = Some((i, vec2_sub(state.mouse_position, origin), mode));
+            state.selected_annotation = Some(i);
+            state.selected_function_box = None;
+        }
+    }
+}
+
+// right click on a bend point removes it, right click on a wire adds a new one
+fn update_bend_points(state: &mut State) {
+    if let Some(bend) = find_bend_point(state, state.mouse_position) {
+        state.checkpoint();
+        state.container.remove_bend_point(bend);
+    } else if let Some((edge, wire, leg)) = find_wire_leg(state, state.mouse_position) {
+        let pos = state.snap(state.mouse_position);
+        state.checkpoint();
+        state.container.insert_bend_point(edge, wire, leg, pos);
+    }
+}
+
+fn find_bend_point(state: &State, point: PosF) -> Option<BendPointRef> {
+    state.container.graph.edge_references()
+        .flat_map(|e| e.weight().iter().enumerate()
+            .flat_map(move |(w, wire)| wire.bend_points.iter().enumerate()
+                .map(move |(b, &p)| ((e.id(), w, b), p))))
+        .find(|(_, p)| distance(*p, point) <= 4.)
+        .map(|(bend, _)| bend)
+}
+
+// returns the wire and the index of the leg (the part between two bend points) under the point
+fn find_wire_leg(state: &State, point: PosF) -> Option<BendPointRef> {
+    let graph = &state.container.graph;
+
+    graph.edge_references().find_map(|e| {
+        let source = FunctionBoxDraw::new(&graph[e.source()], e.source());
+        let target = FunctionBoxDraw::new(&graph[e.target()], e.target());
+
+        e.weight().iter().enumerate().find_map(|(w, wire)| {
+            let start = source.connector_position(&graph[e.source()].connectors[wire.output]);
+            let end = target.connector_position(&graph[e.target()].connectors[wire.input]);
+
+            wire_legs(start, &wire.bend_points, end, state.orthogonal_wires).iter()
+                .position(|leg| leg.windows(2).any(|s| distance_to_segment(point, s[0], s[1]) <= 3.))
+                .map(|leg| (e.id(), w, leg))
+        })
     })
 }
 
@@ -200,18 +753,10 @@ pub(crate) fn update_general_states(
 pub(crate) fn update_fb_states(
     state: &mut State,
 ) {
-    let generation = 1 + state.container.graph.raw_nodes().first().map_or(0, |x| x.weight.generation);
-
-    state.container.graph.node_indices().for_each(|x| {
-        if state.container.graph[x].generation < generation {
-            calculate_and_set_state(&mut state.container.graph, x);
-            state.container.graph.index_mut(x).generation = generation;
-        }
-    });
-
-    state.container.graph.node_indices().for_each(|i| {
-        assert_eq!(state.container.graph[i].generation, generation)
-    });
+    simulation::tick(&mut state.container);
+    if let Some(waveform) = &mut state.waveform {
+        waveform.record(&state.container);
+    }
 }
 
 
@@ -219,6 +764,19 @@ pub(crate) fn draw(
     state: &State,
     ctx: &mut DrawCtx,
 ) {
+    if state.grid_snap {
+        draw_grid(ctx);
+    }
+
+    state.container.annotations.iter().enumerate().for_each(|(i, a)| {
+        let mut draw = AnnotationDraw::new(a);
+        draw.highlighted = state.selected_annotation == Some(i);
+        draw.draw(ctx);
+    });
+
+    let findings = state.findings.as_ref().filter(|_| state.design_checks);
+    let path = state.critical_path.as_ref().filter(|_| state.statistics);
+
     state.container.graph.node_indices().for_each(|i| {
         let mut draw = FunctionBoxDraw::new(&state.container.graph[i], i);
 
@@ -227,52 +785,41 @@ pub(crate) fn draw(
 
         state.container.graph.edges_directed(i, Direction::Outgoing)
             .for_each(|e| {
-                e.weight().iter().for_each(|&(c1, c2)| {
+                e.weight().iter().for_each(|wire| {
                     let d2 = FunctionBoxDraw::new(&state.container.graph[e.target()], e.target());
 
-                    draw.draw_connection_line(&state.container.graph[e.source()].connectors[c1], d2.connector_position(&state.container.graph[e.target()].connectors[c2]), ctx)
+                    draw.draw_connection_line(&state.container.graph[e.source()].connectors[wire.output], &wire.bend_points,
+                                              d2.connector_position(&state.container.graph[e.target()].connectors[wire.input]), state.orthogonal_wires, ctx)
                 })
             });
 
-        if let Some((i2, c, o)) = &state.dragged_connector {
+        if let Some((i2, c, _o)) = &state.dragged_connector {
             if i == *i2 {
-                draw.draw_connection_line(&state.container.graph[i].connectors[*c], state.mouse_position, ctx);
+                draw.draw_connection_line(&state.container.graph[i].connectors[*c], &[], state.mouse_position, false, ctx);
             }
         }
-    });
-}
 
-fn calculate_and_set_state(graph: &mut FBGraph, x: NodeIndex) {
-    let result_state: bool;
-    {
-        match graph.index(x).name.as_str() {
-            "nand" => {
-                result_state = !graph.index(x).inputs_iter().fold(true, |x, y| { x && y.state });
-            }
-            "1" => {
-                result_state = true;
-            }
-            "0" => {
-                result_state = false;
-            }
-            "output" | "input" => {
-                result_state = false;
-            }
-            _ => panic!("Unknown function {:?}", graph.index(x).name)
+        state.container.graph[i].connectors.iter().filter(|c| c.probe)
+            .for_each(|c| draw.draw_outline(Some(c.idx), rgba(0, 184, 148, 1.0), ctx));
+        if path.is_some_and(|p| p.contains(&i)) {
+            draw.draw_outline(None, rgba(108, 92, 231, 1.0), ctx);
         }
-    }
-
-    // TODO: for now only one output supported
-    graph.index_mut(x).outputs_iter_mut()
-        .for_each(|x| { x.state = result_state });
-
-    let mut neighbors = graph.neighbors_directed(x, Direction::Outgoing)
-        .detach();
-    while let Some((edge, node)) = neighbors.next(graph) {
-        (0..graph[edge].len()).for_each(|i| {
-            let connector_idx = graph[edge][i].1;
-            graph.node_weight_mut(node).unwrap().connectors[connector_idx].state = result_state;
+        findings.and_then(|f| f.get(&i)).into_iter().flatten().for_each(|&(rule, connector)| {
+            let color = match rule {
+                Rule::CombinationalLoop => rgba(225, 112, 85, 1.0),
+                _ => rgba(214, 48, 49, 1.0),
+            };
+            draw.draw_outline(connector, color, ctx);
         });
-    }
+    });
 }
 
+fn draw_grid(ctx: &mut DrawCtx) {
+    let color = rgba(164, 176, 182, 1.0);
+    let [width, height] = ctx.c.get_view_size();
+
+    (0..=(width / GRID_SIZE) as usize).map(|x| x as f64 * GRID_SIZE)
+        .for_each(|x| line_from_to(color, 0.5, [x, 0.], [x, height], ctx.c.transform, ctx.g));
+    (0..=(height / GRID_SIZE) as usize).map(|y| y as f64 * GRID_SIZE)
+        .for_each(|y| line_from_to(color, 0.5, [0., y], [width, y], ctx.c.transform, ctx.g));
+}
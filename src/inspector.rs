@@ -0,0 +1,304 @@
+use graphics::Rectangle;
+
+use crate::annotation::Annotation;
+use crate::button::Button;
+use crate::component::find_kind;
+use crate::connector::ConnectorDirection;
+use crate::container::{ConnectorRef, Container, FunctionBoxRef};
+use crate::game::{Draw, DrawCtx, Message, PosF, State, Update};
+use crate::rom;
+use crate::text_field::TextField;
+use crate::ui::{draw_text, rgba};
+
+const ROW_HEIGHT: f64 = 22.;
+const WIDTH: f64 = 160.;
+const FIELD_X: f64 = 55.;
+
+#[derive(Debug, Clone, Copy)]
+enum Property {
+    Inputs,
+    Outputs,
+    Delay,
+}
+
+enum Edit {
+    Label(String),
+    Param(String, String),
+    ConnectorName(ConnectorRef, String),
+    Toggle(ConnectorRef),
+    Probe(ConnectorRef),
+    Step(Property, usize),
+}
+
+struct Stepper {
+    property: Property,
+    value: usize,
+    decrease: Button,
+    increase: Button,
+}
+
+// panel to view and edit the selected function box
+pub struct Inspector {
+    position: PosF,
+    selected: Option<FunctionBoxRef>,
+    selected_annotation: Option<usize>,
+    rows: Vec<(String, PosF)>,
+    label: Option<TextField>,
+    steppers: Vec<Stepper>,
+    params: Vec<(String, TextField)>,
+    connectors: Vec<(ConnectorRef, TextField)>,
+    toggles: Vec<(ConnectorRef, Button)>,
+    probes: Vec<(ConnectorRef, Button)>,
+    height: f64,
+    // the shown values are outdated, rebuilt once the mouse button is released
+    dirty: bool,
+}
+
+impl Inspector {
+    pub fn new(position: PosF) -> Self {
+        Inspector {
+            position,
+            selected: None,
+            selected_annotation: None,
+            rows: Vec::new(),
+            label: None,
+            steppers: Vec::new(),
+            params: Vec::new(),
+            connectors: Vec::new(),
+            toggles: Vec::new(),
+            probes: Vec::new(),
+            height: 0.,
+            dirty: false,
+        }
+    }
+
+    pub fn has_focus(&self) -> bool {
+        self.label.iter().chain(self.params.iter().map(|(_, f)| f)).chain(self.connectors.iter().map(|(_, f)| f))
+            .any(|f| f.focused())
+    }
+
+    fn rebuild(&mut self, container: &Container, selected: Option<FunctionBoxRef>, selected_annotation: Option<usize>) {
+        self.selected = selected.filter(|&fb| container.graph.node_weight(fb).is_some());
+        self.selected_annotation = selected_annotation.filter(|&i| i < container.annotations.len());
+        self.rows.clear();
+        self.label = None;
+        self.steppers.clear();
+        self.params.clear();
+        self.connectors.clear();
+        self.toggles.clear();
+        self.probes.clear();
+        self.height = 0.;
+        self.dirty = false;
+
+        if let Some(i) = self.selected_annotation {
+            self.rebuild_annotation(container, i);
+            return;
+        }
+        let fb = match self.selected {
+            Some(fb) => fb,
+            None => return,
+        };
+        let function_box = &container.graph[fb];
+        let kind = find_kind(&function_box.name);
+        let [x, mut y] = self.position;
+        let field_rect = |y: f64| [x + FIELD_X, y + 2., WIDTH - FIELD_X - 5., ROW_HEIGHT - 4.];
+
+        self.rows.push((format!("Kind: {}", function_box.name), [x + 5., y + ROW_HEIGHT / 2.]));
+        y += ROW_HEIGHT;
+
+        self.rows.push(("Label".into(), [x + 5., y + ROW_HEIGHT / 2.]));
+        self.label = Some(TextField::new(function_box.label.clone(), field_rect(y)));
+        y += ROW_HEIGHT;
+
+        let mut steppers = vec![(Property::Delay, "Delay", function_box.delay)];
+        if let Some(kind) = kind {
+            let (inputs, outputs) = kind.size_labels();
+            if kind.resizable_inputs {
+                steppers.insert(0, (Property::Inputs, inputs, function_box.inputs_len));
+            }
+            if kind.resizable_outputs {
+                steppers.insert(0, (Property::Outputs, outputs, function_box.outputs_len));
+            }
+        }
+        for (property, name, value) in steppers {
+            self.rows.push((name.into(), [x + 5., y + ROW_HEIGHT / 2.]));
+            self.rows.push((format!("{}", value), [x + FIELD_X + 30., y + ROW_HEIGHT / 2.]));
+            self.steppers.push(Stepper {
+                property,
+                value,
+                decrease: Button::new_with_rect("-".into(), [x + FIELD_X, y + 2., 20., ROW_HEIGHT - 4.], 14),
+                increase: Button::new_with_rect("+".into(), [x + FIELD_X + 60., y + 2., 20., ROW_HEIGHT - 4.], 14),
+            });
+            y += ROW_HEIGHT;
+        }
+
+        for (name, value) in &function_box.params {
+            self.rows.push((name.clone(), [x + 5., y + ROW_HEIGHT / 2.]));
+            self.params.push((name.clone(), TextField::new(value.clone(), field_rect(y))));
+            y += ROW_HEIGHT;
+        }
+
+        self.rows.push(("Connectors".into(), [x + 5., y + ROW_HEIGHT / 2.]));
+        y += ROW_HEIGHT;
+        for (i, connector) in function_box.connectors.iter().enumerate() {
+            let direction = if matches!(connector.direction, ConnectorDirection::Input) { "in" } else { "out" };
+            self.rows.push((direction.into(), [x + 5., y + ROW_HEIGHT / 2.]));
+            if function_box.name == "input" {
+                self.connectors.push((i, TextField::new(connector.name.clone(), [x + 30., y + 2., WIDTH - 95., ROW_HEIGHT - 4.])));
+                self.toggles.push((i, Button::new_with_rect(if connector.state { "1" } else { "0" }.into(), [x + WIDTH - 60., y + 2., 30., ROW_HEIGHT - 4.], 14)));
+            } else {
+                self.connectors.push((i, TextField::new(connector.name.clone(), [x + 30., y + 2., WIDTH - 60., ROW_HEIGHT - 4.])));
+            }
+            // P marks a connector recorded into the waveform
+            self.probes.push((i, Button::new_with_rect(if connector.probe { "P" } else { "-" }.into(), [x + WIDTH - 25., y + 2., 20., ROW_HEIGHT - 4.], 14)));
+            y += ROW_HEIGHT;
+        }
+
+        self.height = y - self.position[1] + 3.;
+    }
+
+    fn rebuild_annotation(&mut self, container: &Container, i: usize) {
+        let annotation = &container.annotations[i];
+        let [x, y] = self.position;
+        let kind = match annotation {
+            Annotation::Label { .. } => "Kind: text",
+            Annotation::Frame { .. } => "Kind: frame",
+        };
+        self.rows.push((kind.into(), [x + 5., y + ROW_HEIGHT / 2.]));
+        self.rows.push(("Text".into(), [x + 5., y + ROW_HEIGHT * 1.5]));
+        self.label = Some(TextField::new(annotation.text().into(), [x + FIELD_X, y + ROW_HEIGHT + 2., WIDTH - FIELD_X - 5., ROW_HEIGHT - 4.]));
+        self.height = 2. * ROW_HEIGHT + 3.;
+    }
+
+    fn collect_edits(&mut self) -> Vec<Edit> {
+        let mut edits = Vec::new();
+
+        if let Some(label) = self.label.as_mut() {
+            if label.changed() {
+                edits.push(Edit::Label(label.text.trim().into()));
+            }
+        }
+        for (name, field) in &mut self.params {
+            if field.changed() {
+                edits.push(Edit::Param(name.clone(), field.text.trim().into()));
+            }
+        }
+        for (i, field) in &mut self.connectors {
+            if field.changed() && !field.text.trim().is_empty() {
+                edits.push(Edit::ConnectorName(*i, field.text.trim().into()));
+            }
+        }
+        for (i, button) in &mut self.toggles {
+            if button.pressed() {
+                edits.push(Edit::Toggle(*i));
+            }
+        }
+        for (i, button) in &mut self.probes {
+            if button.pressed() {
+                edits.push(Edit::Probe(*i));
+            }
+        }
+        for stepper in &mut self.steppers {
+            if stepper.decrease.pressed() {
+                edits.push(Edit::Step(stepper.property, stepper.value.saturating_sub(1).max(1)));
+            } else if stepper.increase.pressed() {
+                edits.push(Edit::Step(stepper.property, stepper.value + 1));
+            }
+        }
+
+        edits
+    }
+
+    // writes the edits back to the container
+    pub fn apply(&mut self, state: &mut State) {
+        if let Some(i) = self.selected_annotation.filter(|&i| !self.dirty && i < state.container.annotations.len()) {
+            if let Some(Edit::Label(text)) = self.collect_edits().pop() {
+                state.checkpoint();
+                state.container.annotations[i].set_text(text);
+                self.dirty = true;
+            }
+            return;
+        }
+        let fb = match self.selected {
+            Some(fb) if !self.dirty && state.container.graph.node_weight(fb).is_some() => fb,
+            _ => return,
+        };
+        let edits = self.collect_edits();
+        if edits.is_empty() {
+            return;
+        }
+
+        state.checkpoint();
+        let container = &mut state.container;
+        for edit in edits {
+            let (inputs_len, outputs_len) = (container.graph[fb].inputs_len, container.graph[fb].outputs_len);
+            match edit {
+                Edit::Label(label) => container.graph[fb].label = label,
+                Edit::Param(name, value) => {
+                    let is_rom_file = container.graph[fb].name == "rom" && name == "file";
+                    container.graph[fb].params.insert(name, value);
+                    // the simulation reads a broken rom file as all 0, so tell why
+                    if is_rom_file {
+                        let circuit_dir = state.file_path.as_ref().and_then(|p| p.parent());
+                        if let Err(e) = rom::load(&mut container.graph[fb], circuit_dir) {
+                            state.message = Some(Message::Error(e));
+                        }
+                    }
+                }
+                Edit::ConnectorName(i, name) => container.graph[fb].connectors[i].name = name,
+                Edit::Toggle(i) => {
+                    let connector = &mut container.graph[fb].connectors[i];
+                    connector.state = !connector.state;
+                }
+                Edit::Probe(i) => {
+                    let connector = &mut container.graph[fb].connectors[i];
+                    connector.probe = !connector.probe;
+                }
+                Edit::Step(Property::Inputs, value) => container.resize_connectors(fb, value, outputs_len),
+                Edit::Step(Property::Outputs, value) => container.resize_connectors(fb, inputs_len, value),
+                Edit::Step(Property::Delay, value) => container.graph[fb].delay = value,
+            }
+        }
+        self.dirty = true;
+    }
+}
+
+impl Update for Inspector {
+    fn update(&mut self, state: &State) {
+        if self.selected != state.selected_function_box || self.selected_annotation != state.selected_annotation
+            || (self.dirty && !state.mouse_button1_pressed) {
+            self.rebuild(&state.container, state.selected_function_box, state.selected_annotation);
+        }
+
+        self.label.iter_mut().for_each(|f| f.update(state));
+        self.params.iter_mut().for_each(|(_, f)| f.update(state));
+        self.connectors.iter_mut().for_each(|(_, f)| f.update(state));
+        self.toggles.iter_mut().for_each(|(_, b)| b.update(state));
+        self.probes.iter_mut().for_each(|(_, b)| b.update(state));
+        self.steppers.iter_mut().for_each(|s| {
+            s.decrease.update(state);
+            s.increase.update(state);
+        });
+    }
+}
+
+impl Draw for Inspector {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        if self.selected.is_none() && self.selected_annotation.is_none() {
+            return;
+        }
+        Rectangle::new(rgba(99, 110, 114, 0.5))
+            .draw_tri([self.position[0], self.position[1], WIDTH, self.height], &Default::default(), ctx.c.transform, ctx.g);
+        self.rows.iter()
+            .for_each(|(text, pos)| draw_text(text, 14, *pos, rgba(45, 52, 54, 1.0), ctx));
+        self.label.iter().for_each(|f| f.draw(ctx));
+        self.params.iter().for_each(|(_, f)| f.draw(ctx));
+        self.connectors.iter().for_each(|(_, f)| f.draw(ctx));
+        self.toggles.iter().for_each(|(_, b)| b.draw(ctx));
+        self.probes.iter().for_each(|(_, b)| b.draw(ctx));
+        self.steppers.iter().for_each(|s| {
+            s.decrease.draw(ctx);
+            s.increase.draw(ctx);
+        });
+    }
+}
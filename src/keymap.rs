@@ -0,0 +1,189 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::PathBuf;
+
+use piston::input::Key;
+use piston::input::keyboard::ModifierKey;
+use serde::{Deserialize, Serialize};
+
+use crate::files::config_dir;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Save,
+    SaveAs,
+    Load,
+    Undo,
+    Delete,
+    AddGate,
+    QuickAdd,
+    ToggleSimulation,
+    Step,
+    DesignChecks,
+    Statistics,
+    RecordWaveform,
+}
+
+const DEFAULT_BINDINGS: &[(Action, &str)] = &[
+    (Action::Save, "Ctrl+S"),
+    (Action::SaveAs, "Ctrl+Shift+S"),
+    (Action::Load, "Ctrl+O"),
+    (Action::Undo, "Ctrl+Z"),
+    (Action::Delete, "Delete"),
+    (Action::AddGate, "N"),
+    (Action::QuickAdd, "Space"),
+    (Action::ToggleSimulation, "F5"),
+    (Action::Step, "F6"),
+    (Action::DesignChecks, "F7"),
+    (Action::Statistics, "F8"),
+    (Action::RecordWaveform, "F9"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyBinding {
+    key: Key,
+    modifiers: ModifierKey,
+}
+
+impl KeyBinding {
+    // parses bindings like "Ctrl+Shift+S", key names are the ones of piston's Key enum
+    pub fn parse(text: &str) -> Result<KeyBinding, String> {
+        let mut parts: Vec<&str> = text.split('+').map(|x| x.trim()).collect();
+        let key_name = parts.pop().filter(|x| !x.is_empty()).ok_or_else(|| format!("no key in {:?}", text))?;
+        let key: Key = serde_json::from_value(serde_json::Value::String(key_name.into()))
+            .map_err(|_| format!("unknown key {:?} in {:?}", key_name, text))?;
+
+        let mut modifiers = ModifierKey::NO_MODIFIER;
+        for part in parts {
+            modifiers.insert(match part.to_lowercase().as_str() {
+                "ctrl" => ModifierKey::CTRL,
+                "shift" => ModifierKey::SHIFT,
+                "alt" => ModifierKey::ALT,
+                "gui" | "super" | "cmd" => ModifierKey::GUI,
+                _ => return Err(format!("unknown modifier {:?} in {:?}", part, text)),
+            });
+        }
+
+        Ok(KeyBinding { key, modifiers })
+    }
+
+    fn matches(&self, key: Key, modifiers: ModifierKey) -> bool {
+        self.key == key && self.modifiers == modifiers
+    }
+}
+
+#[derive(Debug)]
+pub struct KeyMap {
+    bindings: Vec<(KeyBinding, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap {
+            bindings: DEFAULT_BINDINGS.iter()
+                .map(|&(action, text)| (KeyBinding::parse(text).unwrap(), action))
+                .collect(),
+        }
+    }
+}
+
+impl KeyMap {
+    pub fn config_path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("keymap.json"))
+    }
+
+    // the defaults, overridden by the actions bound in the user's keymap.json
+    pub fn load() -> Result<KeyMap, String> {
+        let mut key_map = KeyMap::default();
+
+        if let Some(path) = KeyMap::config_path().filter(|p| p.exists()) {
+            File::open(&path).map_err(|e| e.to_string())
+                .and_then(|file| serde_json::from_reader(file).map_err(|e| e.to_string()))
+                .and_then(|config| key_map.apply_config(&config))
+                .map_err(|e| format!("Error loading key map {}: {}", path.display(), e))?;
+        }
+
+        Ok(key_map)
+    }
+
+    // config maps each action to one binding or a list of bindings, e.g. {"undo": ["Ctrl+Z", "Backspace"]}
+    pub fn apply_config(&mut self, config: &HashMap<Action, OneOrMany>) -> Result<(), String> {
+        for (&action, bindings) in config {
+            let bindings = bindings.as_slice().iter()
+                .map(|text| KeyBinding::parse(text))
+                .collect::<Result<Vec<_>, _>>()?;
+            self.bindings.retain(|&(_, a)| a != action);
+            self.bindings.extend(bindings.into_iter().map(|b| (b, action)));
+        }
+        Ok(())
+    }
+
+    pub fn actions(&self, keys_pressed: &[Key], modifiers: ModifierKey) -> Vec<Action> {
+        keys_pressed.iter()
+            .flat_map(|&key| self.bindings.iter()
+                .filter(move |(binding, _)| binding.matches(key, modifiers))
+                .map(|&(_, action)| action))
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    fn as_slice(&self) -> &[String] {
+        match self {
+            OneOrMany::One(x) => std::slice::from_ref(x),
+            OneOrMany::Many(x) => x,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(json: &str) -> HashMap<Action, OneOrMany> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn parses_keys_with_modifiers() {
+        let binding = KeyBinding::parse("ctrl + Shift+S").unwrap();
+        assert_eq!(binding, KeyBinding { key: Key::S, modifiers: ModifierKey::CTRL | ModifierKey::SHIFT });
+        assert_eq!(KeyBinding::parse("Cmd+Alt+F5").unwrap().modifiers, ModifierKey::GUI | ModifierKey::ALT);
+        assert_eq!(KeyBinding::parse("Delete").unwrap(), KeyBinding { key: Key::Delete, modifiers: ModifierKey::NO_MODIFIER });
+
+        assert_eq!(KeyBinding::parse("Ctrl+Enter"), Err("unknown key \"Enter\" in \"Ctrl+Enter\"".into()));
+        assert_eq!(KeyBinding::parse("Meta+S"), Err("unknown modifier \"Meta\" in \"Meta+S\"".into()));
+        assert_eq!(KeyBinding::parse("Ctrl+"), Err("no key in \"Ctrl+\"".into()));
+    }
+
+    #[test]
+    fn config_replaces_the_default_bindings_of_an_action() {
+        let mut key_map = KeyMap::default();
+        key_map.apply_config(&config(r#"{"undo": ["Ctrl+Y", "Backspace"], "save": "F2"}"#)).unwrap();
+
+        assert_eq!(key_map.actions(&[Key::Z], ModifierKey::CTRL), vec![]);
+        assert_eq!(key_map.actions(&[Key::Y], ModifierKey::CTRL), vec![Action::Undo]);
+        assert_eq!(key_map.actions(&[Key::Backspace], ModifierKey::NO_MODIFIER), vec![Action::Undo]);
+        assert_eq!(key_map.actions(&[Key::F2], ModifierKey::NO_MODIFIER), vec![Action::Save]);
+        // the others keep their defaults
+        assert_eq!(key_map.actions(&[Key::S], ModifierKey::CTRL | ModifierKey::SHIFT), vec![Action::SaveAs]);
+        assert_eq!(key_map.actions(&[Key::S], ModifierKey::CTRL), vec![]);
+    }
+
+    #[test]
+    fn invalid_bindings_are_reported() {
+        let mut key_map = KeyMap::default();
+        assert_eq!(key_map.apply_config(&config(r#"{"undo": "Ctrl+Nope"}"#)), Err("unknown key \"Nope\" in \"Ctrl+Nope\"".into()));
+        assert!(serde_json::from_str::<HashMap<Action, OneOrMany>>(r#"{"fly": "F1"}"#).is_err());
+        // the default stays bound
+        assert_eq!(key_map.actions(&[Key::Z], ModifierKey::CTRL), vec![Action::Undo]);
+    }
+}
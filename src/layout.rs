@@ -0,0 +1,69 @@
+use std::collections::VecDeque;
+
+use petgraph::Direction;
+use petgraph::graph::NodeIndex;
+
+use crate::container::Container;
+use crate::function_box_draw::FunctionBoxDraw;
+
+const MARGIN: f64 = 50.;
+const ROW_HEIGHT: f64 = 130.;
+const COLUMN_GAP: f64 = 30.;
+
+// places imported boxes in rows by their distance from the inputs, signals flow upwards like on the canvas
+pub fn auto_place(container: &mut Container) {
+    let graph = &container.graph;
+    let count = graph.node_count();
+    let mut level = vec![0; count];
+    let mut remaining: Vec<usize> = graph.node_indices()
+        .map(|n| graph.neighbors_directed(n, Direction::Incoming).count())
+        .collect();
+    let mut done = vec![false; count];
+    let mut queue: VecDeque<usize> = (0..count).filter(|&i| remaining[i] == 0).collect();
+
+    // longest path from the sources, a box in a loop is taken as soon as nothing else is left
+    loop {
+        let i = match queue.pop_front() {
+            Some(i) => i,
+            None => match (0..count).find(|&i| !done[i]) {
+                Some(i) => i,
+                None => break,
+            },
+        };
+        if done[i] {
+            continue;
+        }
+        done[i] = true;
+        for t in graph.neighbors_directed(NodeIndex::new(i), Direction::Outgoing) {
+            let t = t.index();
+            if done[t] {
+                continue;
+            }
+            level[t] = level[t].max(level[i] + 1);
+            remaining[t] = remaining[t].saturating_sub(1);
+            if remaining[t] == 0 {
+                queue.push_back(t);
+            }
+        }
+    }
+
+    // inputs at the bottom and outputs at the top
+    let mut max_level = level.iter().copied().max().unwrap_or(0);
+    for n in graph.node_indices() {
+        match graph[n].name.as_str() {
+            "input" => level[n.index()] = 0,
+            "output" => level[n.index()] = max_level + 1,
+            _ => {}
+        }
+    }
+    max_level += 1;
+
+    let mut row_x = vec![MARGIN; max_level + 1];
+    let nodes: Vec<_> = container.graph.node_indices().collect();
+    for n in nodes {
+        let l = level[n.index()];
+        let width = FunctionBoxDraw::new(&container.graph[n], n).rect()[2];
+        container.graph[n].position = [row_x[l], MARGIN + (max_level - l) as f64 * ROW_HEIGHT];
+        row_x[l] += width + COLUMN_GAP;
+    }
+}
@@ -0,0 +1,48 @@
+pub mod ui;
+pub mod game;
+pub mod function_box_draw;
+pub mod button;
+pub mod util;
+pub mod connector;
+pub mod function_box;
+pub mod container;
+pub mod component;
+pub mod simulation;
+pub mod rom;
+pub mod palette;
+pub mod quick_add;
+pub mod text_field;
+pub mod inspector;
+pub mod keymap;
+pub mod annotation;
+pub mod files;
+pub mod file_format;
+pub mod validation;
+pub mod recovery;
+pub mod netlist;
+pub mod verilog;
+pub mod vhdl;
+pub mod export;
+pub mod layout;
+pub mod yosys;
+pub mod logisim;
+pub mod net_builder;
+pub mod bench;
+pub mod blif;
+pub mod svg;
+pub mod truth_table;
+pub mod truth_table_panel;
+pub mod expression;
+pub mod minimize;
+pub mod synthesis;
+pub mod synthesis_panel;
+mod sat;
+pub mod equivalence;
+pub mod lint;
+pub mod statistics;
+pub mod test_vectors;
+pub mod waveform;
+pub mod cli;
+pub mod file_dialog;
+#[allow(dead_code)]
+pub mod toggle_button_array;
@@ -0,0 +1,121 @@
+use std::collections::HashSet;
+
+use petgraph::algo::tarjan_scc;
+use petgraph::graph::NodeIndex;
+use petgraph::unionfind::UnionFind;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use crate::container::{ConnectorRef, Container};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rule {
+    // reads 0 in the simulation
+    UnconnectedInput,
+    DanglingOutput,
+    // there are no storage elements, every cycle is combinational, latches built from gates are flagged too
+    CombinationalLoop,
+    // not connected to any output box in any way
+    Unreachable,
+}
+
+pub struct Finding {
+    pub rule: Rule,
+    pub boxes: Vec<NodeIndex>,
+    pub connector: Option<ConnectorRef>,
+    pub message: String,
+}
+
+fn connected(container: &Container, n: NodeIndex, c: ConnectorRef) -> bool {
+    let graph = &container.graph;
+    graph.edges_directed(n, Direction::Outgoing).any(|e| e.weight().iter().any(|w| w.output == c))
+        || graph.edges_directed(n, Direction::Incoming).any(|e| e.weight().iter().any(|w| w.input == c))
+}
+
+pub fn lint(container: &Container) -> Vec<Finding> {
+    let graph = &container.graph;
+    let mut findings = Vec::new();
+
+    let mut components = UnionFind::new(graph.node_count());
+    for e in graph.edge_references() {
+        components.union(e.source().index(), e.target().index());
+    }
+    let observed: HashSet<usize> = graph.node_indices()
+        .filter(|&n| graph[n].name == "output")
+        .map(|n| components.find(n.index()))
+        .collect();
+    let unreachable: HashSet<NodeIndex> = graph.node_indices()
+        .filter(|&n| !observed.contains(&components.find(n.index())))
+        .collect();
+
+    for n in graph.node_indices().filter(|n| unreachable.contains(n)) {
+        findings.push(Finding {
+            rule: Rule::Unreachable,
+            boxes: vec![n],
+            connector: None,
+            message: format!("{} doesn't lead to any output", graph[n].id),
+        });
+    }
+
+    // the pins of unreachable boxes would only repeat the finding above
+    for n in graph.node_indices().filter(|n| !unreachable.contains(n)) {
+        let fb = &graph[n];
+        for c in fb.connectors.iter().filter(|c| !connected(container, n, c.idx)) {
+            let (rule, message) = if c.idx < fb.inputs_len {
+                (Rule::UnconnectedInput, format!("{} of {} is not connected and reads 0", c.name, fb.id))
+            } else {
+                (Rule::DanglingOutput, format!("{} of {} drives nothing", c.name, fb.id))
+            };
+            findings.push(Finding { rule, boxes: vec![n], connector: Some(c.idx), message });
+        }
+    }
+
+    for mut cycle in tarjan_scc(graph) {
+        if cycle.len() == 1 && graph.find_edge(cycle[0], cycle[0]).is_none() {
+            continue;
+        }
+        cycle.sort();
+        let names: Vec<&str> = cycle.iter().map(|&n| graph[n].id.as_str()).collect();
+        findings.push(Finding {
+            rule: Rule::CombinationalLoop,
+            message: format!("combinational loop through {}", names.join(", ")),
+            boxes: cycle,
+            connector: None,
+        });
+    }
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::find_kind;
+    use crate::function_box::FunctionBox;
+
+    #[test]
+    fn flags_every_rule() {
+        let mut container = Container::new();
+        let input = container.add(FunctionBox::new("input", [0., 0.], vec![], vec!["s".into(), "r".into(), "x".into()]));
+        let top = container.add(find_kind("nand").unwrap().create([0., 0.]));
+        let bottom = container.add(find_kind("nand").unwrap().create([0., 0.]));
+        let output = container.add(FunctionBox::new("output", [0., 0.], vec!["q".into(), "y".into()], vec![]));
+        let lonely = container.add(find_kind("not").unwrap().create([0., 0.]));
+        // an SR latch
+        container.connect((input, 0), (top, 0));
+        container.connect((bottom, 2), (top, 1));
+        container.connect((input, 1), (bottom, 1));
+        container.connect((top, 2), (bottom, 0));
+        container.connect((top, 2), (output, 0));
+
+        let findings = lint(&container);
+        let messages: Vec<&str> = findings.iter().map(|f| f.message.as_str()).collect();
+        assert_eq!(messages, vec![
+            "not1 doesn't lead to any output",
+            "x of input1 drives nothing",
+            "y of output1 is not connected and reads 0",
+            "combinational loop through nand1, nand2",
+        ]);
+        assert_eq!(findings[0].boxes, vec![lonely]);
+        assert_eq!((findings[2].rule, findings[2].connector), (Rule::UnconnectedInput, Some(1)));
+    }
+}
@@ -0,0 +1,429 @@
+use std::collections::{BTreeSet, HashMap};
+
+use petgraph::graph::NodeIndex;
+
+use crate::annotation::Annotation;
+use crate::component::find_kind;
+use crate::container::Container;
+use crate::function_box::FunctionBox;
+use crate::netlist::sanitize;
+
+// Logisim components are smaller than our boxes, canvas positions are Logisim positions times this
+const SCALE: f64 = 2.;
+const GATE_SIZE: i64 = 50;
+const NOT_SIZE: i64 = 30;
+
+type Point = (i64, i64);
+
+const GATES: &[(&str, &str)] = &[
+    ("AND Gate", "and"),
+    ("OR Gate", "or"),
+    ("NAND Gate", "nand"),
+    ("NOR Gate", "nor"),
+    ("XOR Gate", "xor"),
+    ("XNOR Gate", "xnor"),
+    ("NOT Gate", "not"),
+];
+
+fn parse_point(text: &str) -> Result<Point, String> {
+    let coordinates: Vec<i64> = text.trim().trim_start_matches('(').trim_end_matches(')')
+        .split(',')
+        .map(|x| x.trim().parse::<i64>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("invalid location {:?}", text))?;
+    match coordinates[..] {
+        [x, y] => Ok((x, y)),
+        _ => Err(format!("invalid location {:?}", text)),
+    }
+}
+
+// the input pins of a gate relative to its output at loc, like Logisim's AbstractGate.getInputOffset
+fn gate_inputs(kind: &str, loc: Point, facing: &str, size: i64, inputs: usize) -> Vec<Point> {
+    // xor gates have an extra curve, negated gates the bubble at the output
+    let bonus_width = if kind == "xor" || kind == "xnor" { 10 } else { 0 };
+    let bubble = if kind.starts_with('n') || kind == "xnor" { 10 } else { 0 };
+    let n = inputs as i64;
+    let (skip_start, skip_dist, skip_lower_even) = if n <= 3 {
+        if size < 40 { (-5, 10, 10) } else if size < 60 || n <= 2 { (-10, 20, 20) } else { (-15, 30, 30) }
+    } else if n == 4 && size >= 60 {
+        (-5, 20, 0)
+    } else {
+        (-5, 10, 10)
+    };
+    (0..n).map(|i| {
+        let dy = if n % 2 == 1 {
+            skip_start * (n - 1) + skip_dist * i
+        } else {
+            skip_start * n + skip_dist * i + if i >= n / 2 { skip_lower_even } else { 0 }
+        };
+        offset(loc, facing, size + bonus_width + bubble, dy)
+    }).collect()
+}
+
+// a point dx behind and dy beside loc for a component facing the given direction
+fn offset((x, y): Point, facing: &str, dx: i64, dy: i64) -> Point {
+    match facing {
+        "north" => (x + dy, y + dx),
+        "south" => (x + dy, y - dx),
+        "west" => (x + dx, y + dy),
+        _ => (x - dx, y + dy),
+    }
+}
+
+// connection points are joined by wires touching them and tunnels with the same label
+#[derive(Default)]
+struct Nets {
+    parent: HashMap<Point, Point>,
+}
+
+impl Nets {
+    fn find(&mut self, p: Point) -> Point {
+        let parent = *self.parent.entry(p).or_insert(p);
+        if parent == p {
+            return p;
+        }
+        let root = self.find(parent);
+        self.parent.insert(p, root);
+        root
+    }
+
+    fn union(&mut self, a: Point, b: Point) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent.insert(a, b);
+        }
+    }
+}
+
+// a component of the circuit with its connection points, inputs first
+struct Part {
+    function_box: FunctionBox,
+    inputs: Vec<Point>,
+    outputs: Vec<Point>,
+    description: String,
+    // a Logisim xor with more than two inputs is true for exactly one input unless set to odd parity
+    exactly_one: bool,
+}
+
+pub fn is_logisim(text: &str) -> bool {
+    text.trim_start().starts_with("<?xml") || text.trim_start().starts_with("<project")
+}
+
+// imports the main circuit, returns the circuit and everything that could not be imported
+pub fn import_logisim(text: &str) -> Result<(Container, Vec<String>), String> {
+    let document = roxmltree::Document::parse(text).map_err(|e| e.to_string())?;
+    let project = document.root_element();
+    if project.tag_name().name() != "project" {
+        return Err("not a Logisim project".into());
+    }
+
+    let libraries: HashMap<&str, &str> = project.children()
+        .filter(|n| n.has_tag_name("lib"))
+        .filter_map(|n| Some((n.attribute("name")?, n.attribute("desc")?)))
+        .collect();
+    let main = project.children().find(|n| n.has_tag_name("main")).and_then(|n| n.attribute("name"));
+    let circuits: Vec<_> = project.children().filter(|n| n.has_tag_name("circuit")).collect();
+    let circuit = circuits.iter().find(|c| main.is_some() && c.attribute("name") == main)
+        .or_else(|| circuits.first())
+        .ok_or("the project has no circuit")?;
+
+    let mut warnings = Vec::new();
+    if circuits.len() > 1 {
+        warnings.push(format!("only the main circuit {} is imported, {} other circuits are skipped",
+                              circuit.attribute("name").unwrap_or(""), circuits.len() - 1));
+    }
+
+    let mut nets = Nets::default();
+    let mut tunnels: HashMap<String, Point> = HashMap::new();
+    let mut parts = Vec::new();
+    let mut annotations = Vec::new();
+
+    for node in circuit.children().filter(|n| n.is_element()) {
+        if node.has_tag_name("wire") {
+            let from = parse_point(node.attribute("from").unwrap_or(""))?;
+            let to = parse_point(node.attribute("to").unwrap_or(""))?;
+            nets.union(from, to);
+            continue;
+        }
+        if !node.has_tag_name("comp") {
+            continue;
+        }
+
+        let name = node.attribute("name").unwrap_or("");
+        let library = node.attribute("lib").and_then(|l| libraries.get(l)).copied().unwrap_or("");
+        let loc = parse_point(node.attribute("loc").unwrap_or(""))?;
+        let attributes: HashMap<&str, &str> = node.children()
+            .filter(|n| n.has_tag_name("a"))
+            .filter_map(|n| Some((n.attribute("name")?, n.attribute("val").unwrap_or_else(|| n.text().unwrap_or("")))))
+            .collect();
+        let attribute = |key: &str, default: &'static str| attributes.get(key).copied().unwrap_or(default);
+        let description = format!("{} at {:?}", name, loc);
+        let position = [loc.0 as f64 * SCALE, loc.1 as f64 * SCALE];
+
+        if attribute("width", "1") != "1" {
+            warnings.push(format!("{} has {} bits, only single bits are supported", description, attribute("width", "1")));
+            continue;
+        }
+
+        match (library, name) {
+            ("#Wiring", "Pin") => {
+                let label = attribute("label", "");
+                let pin_name = vec![if label.is_empty() { "1".to_string() } else { label.to_string() }];
+                let output = attribute("output", "false") == "true";
+                let mut function_box = if output {
+                    FunctionBox::new("output", position, pin_name, Vec::new())
+                } else {
+                    FunctionBox::new("input", position, Vec::new(), pin_name)
+                };
+                function_box.label = label.into();
+                let (inputs, outputs) = if output { (vec![loc], vec![]) } else { (vec![], vec![loc]) };
+                parts.push(Part { function_box, inputs, outputs, description, exactly_one: false });
+            }
+            ("#Wiring", "Constant") => {
+                let value = i64::from_str_radix(attribute("value", "0x1").trim_start_matches("0x"), 16).unwrap_or(1);
+                let kind = if value == 0 { "0" } else { "1" };
+                parts.push(Part { function_box: find_kind(kind).unwrap().create(position), inputs: vec![], outputs: vec![loc], description, exactly_one: false });
+            }
+            ("#Wiring", "Clock") => {
+                let mut function_box = find_kind("clock").unwrap().create(position);
+                function_box.params.insert("period".into(), attribute("highDuration", "1").into());
+                parts.push(Part { function_box, inputs: vec![], outputs: vec![loc], description, exactly_one: false });
+            }
+            ("#Wiring", "Tunnel") => {
+                let label = attribute("label", "").to_string();
+                match tunnels.get(&label) {
+                    Some(&other) => nets.union(loc, other),
+                    None => {
+                        tunnels.insert(label, loc);
+                    }
+                }
+            }
+            ("#Base", "Text") => annotations.push(Annotation::Label { text: attribute("text", "").into(), position }),
+            ("#Gates", _) if GATES.iter().any(|&(n, _)| n == name) => {
+                let kind = GATES.iter().find(|&&(n, _)| n == name).unwrap().1;
+                if attributes.keys().any(|k| k.starts_with("negate") && attributes[k] == "true") {
+                    warnings.push(format!("{} has negated inputs, which are not supported", description));
+                    continue;
+                }
+                let facing = attribute("facing", "east");
+                let inputs = if kind == "not" {
+                    let size = attribute("size", "30").parse().unwrap_or(NOT_SIZE);
+                    vec![offset(loc, facing, size, 0)]
+                } else {
+                    let size = attribute("size", "50").parse().unwrap_or(GATE_SIZE);
+                    let count = attribute("inputs", "5").parse().unwrap_or(5);
+                    gate_inputs(kind, loc, facing, size, count)
+                };
+                let exactly_one = kind.ends_with("xor") && attribute("xor", "1") != "odd";
+                parts.push(Part { function_box: find_kind(kind).unwrap().create(position), inputs, outputs: vec![loc], description, exactly_one });
+            }
+            _ if library.is_empty() => warnings.push(format!("sub-circuit {} is not supported", description)),
+            _ => warnings.push(format!("{} ({}) is not supported", description, library.trim_start_matches('#'))),
+        }
+    }
+
+    // the output driving each net
+    let mut drivers: HashMap<Point, (usize, usize)> = HashMap::new();
+    for (i, part) in parts.iter().enumerate() {
+        for (j, &p) in part.outputs.iter().enumerate() {
+            let net = nets.find(p);
+            if let Some(&(other, _)) = drivers.get(&net) {
+                warnings.push(format!("{} and {} drive the same wire, the second is not connected", parts[other].description, part.description));
+            } else {
+                drivers.insert(net, (i, j));
+            }
+        }
+    }
+
+    // Logisim gates ignore unconnected inputs, ours would read them as 0, so they are left out
+    for part in parts.iter_mut().filter(|p| p.function_box.name != "output" && p.inputs.len() > 1) {
+        let connected: Vec<Point> = part.inputs.iter().copied().filter(|&p| drivers.contains_key(&nets.find(p))).collect();
+        if !connected.is_empty() && connected.len() < part.inputs.len() {
+            let names = (0..connected.len()).map(|i| format!("i{}", i + 1)).collect();
+            let outputs = part.function_box.outputs_iter().map(|c| c.name.clone()).collect();
+            part.function_box.set_connectors(names, outputs);
+            part.inputs = connected;
+        }
+        if part.exactly_one && part.inputs.len() > 2 {
+            warnings.push(format!("{} is true for exactly one input, it is imported as odd parity", part.description));
+        }
+    }
+
+    let mut container = Container::new();
+    container.annotations = annotations;
+    let nodes: Vec<NodeIndex> = parts.iter().map(|p| container.add(p.function_box.clone())).collect();
+    for (i, part) in parts.iter().enumerate() {
+        for (j, &p) in part.inputs.iter().enumerate() {
+            if let Some(&(driver, output)) = drivers.get(&nets.find(p)) {
+                let output = (nodes[driver], parts[driver].function_box.inputs_len + output);
+                if container.can_connect(output, (nodes[i], j)) {
+                    container.connect(output, (nodes[i], j));
+                }
+            }
+        }
+    }
+    Ok((container, warnings))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+fn snap(value: f64) -> i64 {
+    (value / SCALE / 10.).round() as i64 * 10
+}
+
+// writes the circuit as the main circuit of a Logisim project, components are connected by tunnels
+pub fn to_logisim(container: &Container) -> (String, Vec<String>) {
+    let graph = &container.graph;
+    let mut warnings = Vec::new();
+    let mut comps: Vec<String> = Vec::new();
+    let comp = |lib: &str, name: &str, loc: Point, attributes: &[(&str, String)]| -> String {
+        let attributes: String = attributes.iter()
+            .map(|(k, v)| format!("      <a name=\"{}\" val=\"{}\"/>\n", k, escape(v)))
+            .collect();
+        format!("    <comp lib=\"{}\" loc=\"({},{})\" name=\"{}\">\n{}    </comp>\n", lib, loc.0, loc.1, name, attributes)
+    };
+    let tunnel = |loc: Point, label: &str| comp("0", "Tunnel", loc, &[("label", label.into())]);
+
+    // every output connector drives a net named after it
+    let mut net_names: HashMap<(NodeIndex, usize), String> = HashMap::new();
+    for n in graph.node_indices() {
+        for c in graph[n].outputs_iter() {
+            net_names.insert((n, c.idx), sanitize(&format!("{}_{}", graph[n].id, c.name)));
+        }
+    }
+    // unconnected inputs are 0 in our simulation, in Logisim they are tied to a shared constant
+    let mut constants_used = BTreeSet::new();
+    let mut constant = |value: bool| {
+        constants_used.insert(value);
+        format!("const_{}", value as u8)
+    };
+    let input_net = |n: NodeIndex, idx: usize| container.input_driver((n, idx)).map(|driver| net_names[&driver].clone());
+
+    for n in graph.node_indices() {
+        let fb = &graph[n];
+        let loc = (snap(fb.position[0]), snap(fb.position[1]));
+        if fb.delay != 1 {
+            warnings.push(format!("the delay of {} is not exported", fb.id));
+        }
+        match fb.name.as_str() {
+            "input" => for (i, c) in fb.outputs_iter().enumerate() {
+                let pin = (loc.0 + 20 * i as i64, loc.1);
+                comps.push(comp("0", "Pin", pin, &[("tristate", "false".into()), ("label", c.name.clone())]));
+                comps.push(tunnel(pin, &net_names[&(n, c.idx)]));
+            },
+            "output" => for (i, c) in fb.inputs_iter().enumerate() {
+                let pin = (loc.0 + 20 * i as i64, loc.1);
+                comps.push(comp("0", "Pin", pin, &[("facing", "west".into()), ("output", "true".into()), ("label", c.name.clone())]));
+                comps.push(tunnel(pin, &input_net(n, c.idx).unwrap_or_else(|| constant(false))));
+            },
+            "0" | "1" => {
+                comps.push(comp("0", "Constant", loc, &[("value", if fb.name == "1" { "0x1" } else { "0x0" }.into())]));
+                comps.push(tunnel(loc, &net_names[&(n, fb.inputs_len)]));
+            }
+            "clock" => {
+                let period = fb.param::<usize>("period").unwrap_or(1).max(1).to_string();
+                comps.push(comp("0", "Clock", loc, &[("highDuration", period.clone()), ("lowDuration", period)]));
+                comps.push(tunnel(loc, &net_names[&(n, fb.inputs_len)]));
+            }
+            kind => match GATES.iter().find(|&&(_, k)| k == kind) {
+                Some(&(name, _)) => {
+                    // the output is on the right, inputs GATE_SIZE to the left
+                    let loc = (loc.0 + GATE_SIZE + 10, loc.1 + 30);
+                    if kind == "not" {
+                        comps.push(comp("1", name, loc, &[("size", NOT_SIZE.to_string())]));
+                        comps.push(tunnel(offset(loc, "east", NOT_SIZE, 0), &input_net(n, 0).unwrap_or_else(|| constant(false))));
+                    } else {
+                        // Logisim gates have at least two inputs, the missing ones get the value that changes nothing
+                        let count = fb.inputs_len.max(2);
+                        let neutral = kind == "and" || kind == "nand";
+                        comps.push(comp("1", name, loc, &[("size", GATE_SIZE.to_string()), ("inputs", count.to_string())]));
+                        for (i, p) in gate_inputs(kind, loc, "east", GATE_SIZE, count).into_iter().enumerate() {
+                            let net = if i < fb.inputs_len { input_net(n, i) } else { None };
+                            comps.push(tunnel(p, &net.unwrap_or_else(|| constant(i >= fb.inputs_len && neutral))));
+                        }
+                    }
+                    comps.push(tunnel(loc, &net_names[&(n, fb.inputs_len)]));
+                    if fb.outputs_len > 1 {
+                        warnings.push(format!("only the first output of {} is exported", fb.id));
+                    }
+                }
+                None => warnings.push(format!("{} ({}) is not supported by the Logisim export", fb.id, kind)),
+            }
+        }
+    }
+
+    let mut y = 10;
+    for value in constants_used {
+        let loc = (10, y);
+        comps.push(comp("0", "Constant", loc, &[("value", if value { "0x1" } else { "0x0" }.into())]));
+        comps.push(tunnel(loc, &format!("const_{}", value as u8)));
+        y += 20;
+    }
+
+    for annotation in &container.annotations {
+        match annotation {
+            Annotation::Label { text, position } => {
+                comps.push(comp("2", "Text", (snap(position[0]), snap(position[1])), &[("text", text.clone())]));
+            }
+            Annotation::Frame { name, .. } => warnings.push(format!("frame {} is not supported by the Logisim export", name)),
+        }
+    }
+
+    let text = format!("<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n\
+        <project source=\"2.7.1\" version=\"1.0\">\n  \
+        <lib desc=\"#Wiring\" name=\"0\"/>\n  \
+        <lib desc=\"#Gates\" name=\"1\"/>\n  \
+        <lib desc=\"#Base\" name=\"2\"/>\n  \
+        <main name=\"main\"/>\n  \
+        <circuit name=\"main\">\n    \
+        <a name=\"circuit\" val=\"main\"/>\n\
+        {}  </circuit>\n\
+        </project>\n", comps.concat());
+    (text, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::netlist::fixtures::evaluate;
+
+    fn assert_half_adder(container: &mut Container) {
+        assert_eq!(container.input_names(), vec!["a", "b"]);
+        assert_eq!(container.output_names(), vec!["sum", "carry"]);
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert_eq!(evaluate(container, &[a, b]), vec![a != b, a && b], "a={} b={}", a, b);
+        }
+    }
+
+    #[test]
+    fn imports_half_adder() {
+        let text = include_str!("../tests/data/half_adder.circ");
+        assert!(is_logisim(text));
+        let (mut container, warnings) = import_logisim(text).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_eq!(container.annotations.len(), 1);
+        assert_half_adder(&mut container);
+    }
+
+    #[test]
+    fn exported_circuit_imports_again() {
+        let (container, _) = import_logisim(include_str!("../tests/data/half_adder.circ")).unwrap();
+        let (text, warnings) = to_logisim(&container);
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        let (mut container, warnings) = import_logisim(&text).unwrap();
+        assert!(warnings.is_empty(), "{:?}", warnings);
+        assert_half_adder(&mut container);
+    }
+
+    #[test]
+    fn reports_unsupported_components() {
+        let text = include_str!("../tests/data/half_adder.circ").replace("  </circuit>",
+            "    <comp lib=\"0\" loc=\"(400,100)\" name=\"Splitter\"/>\n    <comp lib=\"0\" loc=\"(400,200)\" name=\"Pin\">\n      <a name=\"width\" val=\"8\"/>\n    </comp>\n  </circuit>");
+        let (_, warnings) = import_logisim(&text).unwrap();
+        assert_eq!(warnings.len(), 2, "{:?}", warnings);
+        assert!(warnings[0].contains("Splitter at (400, 100)"), "{:?}", warnings);
+        assert!(warnings[1].contains("8 bits"), "{:?}", warnings);
+    }
+}
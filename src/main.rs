@@ -1,14 +1,13 @@
-mod ui;
-mod game;
-mod function_box_draw;
-mod button;
-mod util;
-mod connector;
-mod function_box;
-mod container;
-mod toggle_button_array;
-
+use rustcpu::{cli, ui};
 
 fn main() {
-    ui::ui_main();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse(&args) {
+        Ok(cli::Command::Open(path)) => ui::ui_main(path),
+        Ok(command) => std::process::exit(cli::run(command)),
+        Err(e) => {
+            eprintln!("{}\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    }
 }
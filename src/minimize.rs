@@ -0,0 +1,216 @@
+use std::collections::BTreeSet;
+
+use crate::container::Container;
+use crate::expression::{cone_gate_count, output_expressions, Expr};
+
+// Quine-McCluskey needs every minterm, beyond this it gets slow and the result unreadable
+pub const MAX_VARIABLES: usize = 12;
+
+// a product term, bits set in mask are left out, the first variable is the most significant bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Implicant {
+    pub value: u32,
+    pub mask: u32,
+}
+
+impl Implicant {
+    fn covers(self, minterm: u32) -> bool {
+        minterm & !self.mask == self.value
+    }
+
+    fn literals(self, variables: usize) -> usize {
+        variables - self.mask.count_ones() as usize
+    }
+}
+
+// a minimal sum of products
+pub struct Sop {
+    pub variables: Vec<String>,
+    pub terms: Vec<Implicant>,
+}
+
+impl Sop {
+    pub fn to_expr(&self) -> Expr {
+        let count = self.variables.len();
+        let term = |t: &Implicant| {
+            let literals: Vec<Expr> = self.variables.iter().enumerate()
+                .filter(|(i, _)| t.mask >> (count - 1 - i) & 1 == 0)
+                .map(|(i, name)| {
+                    let var = Expr::Var(name.clone());
+                    if t.value >> (count - 1 - i) & 1 == 1 { var } else { Expr::not(var) }
+                })
+                .collect();
+            match literals.len() {
+                0 => Expr::Constant(true),
+                1 => literals.into_iter().next().unwrap(),
+                _ => Expr::And(literals),
+            }
+        };
+        match self.terms.len() {
+            0 => Expr::Constant(false),
+            1 => term(&self.terms[0]),
+            _ => Expr::Or(self.terms.iter().map(term).collect()),
+        }
+    }
+
+    // built from multi-input gates: one and per product, one or and one not per negated variable
+    pub fn gate_count(&self) -> usize {
+        let count = self.variables.len();
+        let ands = self.terms.iter().filter(|t| t.literals(count) > 1).count();
+        let ors = (self.terms.len() > 1) as usize;
+        let negated = (0..count)
+            .filter(|&i| self.terms.iter().any(|t| (t.mask | t.value) >> (count - 1 - i) & 1 == 0))
+            .count();
+        ands + ors + negated
+    }
+}
+
+// the input combinations for which the expression is 1
+fn minterms(expr: &Expr, variables: &[String]) -> Vec<u32> {
+    let count = variables.len();
+    (0..1u32 << count)
+        .filter(|&m| expr.evaluate(&|name| {
+            let i = variables.iter().position(|v| v == name).unwrap();
+            m >> (count - 1 - i) & 1 == 1
+        }))
+        .collect()
+}
+
+fn prime_implicants(count: usize, minterms: &[u32]) -> Vec<Implicant> {
+    let mut primes = Vec::new();
+    let mut current: BTreeSet<Implicant> = minterms.iter().map(|&value| Implicant { value, mask: 0 }).collect();
+    while !current.is_empty() {
+        let mut combined = BTreeSet::new();
+        let mut used = BTreeSet::new();
+        for &implicant in &current {
+            for bit in (0..count).map(|i| 1 << i) {
+                if (implicant.mask | implicant.value) & bit != 0 {
+                    continue;
+                }
+                let partner = Implicant { value: implicant.value | bit, mask: implicant.mask };
+                if current.contains(&partner) {
+                    combined.insert(Implicant { value: implicant.value, mask: implicant.mask | bit });
+                    used.insert(implicant);
+                    used.insert(partner);
+                }
+            }
+        }
+        primes.extend(current.difference(&used));
+        current = combined;
+    }
+    primes
+}
+
+// the essential prime implicants, then greedily the one covering the most of what is left
+fn cover(count: usize, minterms: &[u32], primes: &[Implicant]) -> Vec<Implicant> {
+    let mut chosen: Vec<Implicant> = Vec::new();
+    for &m in minterms {
+        let covering: Vec<&Implicant> = primes.iter().filter(|p| p.covers(m)).collect();
+        if covering.len() == 1 && !chosen.contains(covering[0]) {
+            chosen.push(*covering[0]);
+        }
+    }
+    let mut uncovered: Vec<u32> = minterms.iter().copied().filter(|&m| !chosen.iter().any(|p| p.covers(m))).collect();
+    while !uncovered.is_empty() {
+        let best = *primes.iter()
+            .max_by_key(|p| (uncovered.iter().filter(|&&m| p.covers(m)).count(), usize::MAX - p.literals(count)))
+            .unwrap();
+        chosen.push(best);
+        uncovered.retain(|&m| !best.covers(m));
+    }
+    // in the order of the variables, a before !a before leaving a out
+    chosen.sort_by_key(|t| (0..count).rev().map(|i| if t.mask >> i & 1 == 1 { 2 } else { 1 - (t.value >> i & 1) }).collect::<Vec<_>>());
+    chosen
+}
+
+// the variables are given to keep the order of the circuit inputs, the ones the expression doesn't use are left out
+pub fn minimize(expr: &Expr, variables: &[String]) -> Result<Sop, String> {
+    let used = expr.variables();
+    let variables: Vec<String> = variables.iter().filter(|v| used.contains(v)).cloned().collect();
+    if variables.len() > MAX_VARIABLES {
+        return Err(format!("depends on {} inputs, at most {} can be simplified", variables.len(), MAX_VARIABLES));
+    }
+    let minterms = minterms(expr, &variables);
+    Ok(minimize_minterms(variables, &minterms))
+}
+
+// the first variable is the most significant bit of a minterm
+pub fn minimize_minterms(variables: Vec<String>, minterms: &[u32]) -> Sop {
+    let primes = prime_implicants(variables.len(), minterms);
+    let terms = cover(variables.len(), minterms, &primes);
+    Sop { variables, terms }
+}
+
+// one line per output with the simplified expression and the size of both implementations
+pub fn describe_outputs(container: &Container) -> Result<Vec<String>, String> {
+    let inputs = container.input_names();
+    let expressions = output_expressions(container)?;
+    Ok(expressions.iter().enumerate()
+        .map(|(i, (name, expr))| match minimize(expr, &inputs) {
+            Ok(sop) => format!("{} = {}   ({} gates now, {} as sum of products)",
+                               name, sop.to_expr(), cone_gate_count(container, i), sop.gate_count()),
+            Err(e) => format!("{}: {}", name, e),
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::find_kind;
+    use crate::function_box::FunctionBox;
+
+    fn var(name: &str) -> Expr {
+        Expr::Var(name.into())
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_the_minimal_sum_of_products() {
+        // a & b | a & !b | !a & b is a | b
+        let expr = Expr::Or(vec![
+            Expr::And(vec![var("a"), var("b")]),
+            Expr::And(vec![var("a"), Expr::not(var("b"))]),
+            Expr::And(vec![Expr::not(var("a")), var("b")]),
+        ]);
+        let sop = minimize(&expr, &names(&["a", "b"])).unwrap();
+        assert_eq!(sop.to_expr().to_string(), "a | b");
+        assert_eq!(sop.gate_count(), 1);
+
+        let xor = Expr::Xor(vec![var("a"), var("b")]);
+        let sop = minimize(&xor, &names(&["a", "b"])).unwrap();
+        assert_eq!(sop.to_expr().to_string(), "a & !b | !a & b");
+        assert_eq!(sop.gate_count(), 5);
+
+        let contradiction = Expr::And(vec![var("a"), Expr::not(var("a"))]);
+        assert_eq!(minimize(&contradiction, &names(&["a"])).unwrap().to_expr().to_string(), "0");
+    }
+
+    #[test]
+    fn describes_the_outputs_of_a_circuit() {
+        let mut container = Container::new();
+        let input = container.add(FunctionBox::new("input", [0., 0.], vec![], names(&["a", "b", "c"])));
+        let nand = container.add(find_kind("nand").unwrap().create([0., 0.]));
+        let or = container.add(find_kind("or").unwrap().create([0., 0.]));
+        let output = container.add(FunctionBox::new("output", [0., 0.], names(&["y"]), vec![]));
+        container.connect((input, 0), (nand, 0));
+        container.connect((input, 1), (nand, 1));
+        container.connect((nand, 2), (or, 0));
+        container.connect((input, 2), (or, 1));
+        container.connect((or, 2), (output, 0));
+
+        let expressions = output_expressions(&container).unwrap();
+        assert_eq!(expressions[0].1.to_string(), "!(a & b) | c");
+        assert_eq!(describe_outputs(&container).unwrap(), vec!["y = !a | !b | c   (2 gates now, 3 as sum of products)"]);
+
+        let not = container.add(find_kind("not").unwrap().create([0., 0.]));
+        container.graph.clear_edges();
+        container.connect((not, 1), (not, 0));
+        container.connect((not, 1), (output, 0));
+        let error = output_expressions(&container).err().unwrap();
+        assert!(error.contains("part of a loop"), "{}", error);
+    }
+}
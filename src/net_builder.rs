@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use petgraph::graph::NodeIndex;
+
+use crate::component::find_kind;
+use crate::container::Container;
+use crate::function_box::FunctionBox;
+use crate::layout::auto_place;
+
+// builds a circuit from a netlist with named signals like BLIF and BENCH, the gates are placed at the end
+pub struct NetBuilder {
+    container: Container,
+    drivers: HashMap<String, (NodeIndex, usize)>,
+    sinks: Vec<(String, (NodeIndex, usize))>,
+    inverted: HashMap<String, String>,
+    internal: usize,
+}
+
+impl NetBuilder {
+    pub fn new(inputs: &[String]) -> Result<NetBuilder, String> {
+        let mut builder = NetBuilder {
+            container: Container::new(),
+            drivers: HashMap::new(),
+            sinks: Vec::new(),
+            inverted: HashMap::new(),
+            internal: 0,
+        };
+        let input_box = builder.container.add(FunctionBox::new("input", [0., 0.], Vec::new(), inputs.to_vec()));
+        for (i, input) in inputs.iter().enumerate() {
+            builder.drive(input, (input_box, i))?;
+        }
+        Ok(builder)
+    }
+
+    fn drive(&mut self, signal: &str, connector: (NodeIndex, usize)) -> Result<(), String> {
+        if self.drivers.insert(signal.into(), connector).is_some() {
+            return Err(format!("signal {} has more than one driver", signal));
+        }
+        Ok(())
+    }
+
+    // the box is named after the signal it drives
+    pub fn add(&mut self, mut function_box: FunctionBox, inputs: &[String], outputs: &[String]) -> Result<(), String> {
+        function_box.id = outputs.first().cloned().unwrap_or_default();
+        let inputs_len = function_box.inputs_len;
+        let n = self.container.add(function_box);
+        self.sinks.extend(inputs.iter().enumerate().map(|(i, input)| (input.clone(), (n, i))));
+        for (i, output) in outputs.iter().enumerate() {
+            self.drive(output, (n, inputs_len + i))?;
+        }
+        Ok(())
+    }
+
+    pub fn gate(&mut self, kind: &str, inputs: &[String], output: &str) -> Result<(), String> {
+        let mut function_box = find_kind(kind).unwrap().create([0., 0.]);
+        if function_box.inputs_len != inputs.len() {
+            let names = (0..inputs.len()).map(|i| format!("i{}", i + 1)).collect();
+            let outputs = function_box.outputs_iter().map(|c| c.name.clone()).collect();
+            function_box.set_connectors(names, outputs);
+        }
+        self.add(function_box, inputs, &[output.into()])
+    }
+
+    pub fn constant(&mut self, value: bool, output: &str) -> Result<(), String> {
+        self.add(find_kind(if value { "1" } else { "0" }).unwrap().create([0., 0.]), &[], &[output.into()])
+    }
+
+    // a signal only used inside the circuit, the name can't clash with names of the file
+    fn internal_name(&mut self, kind: &str) -> String {
+        self.internal += 1;
+        format!("{} {}", kind, self.internal)
+    }
+
+    pub fn internal_gate(&mut self, kind: &str, inputs: &[String]) -> Result<String, String> {
+        let output = self.internal_name(kind);
+        self.gate(kind, inputs, &output)?;
+        Ok(output)
+    }
+
+    pub fn internal_constant(&mut self, value: bool) -> Result<String, String> {
+        let output = self.internal_name(if value { "1" } else { "0" });
+        self.constant(value, &output)?;
+        Ok(output)
+    }
+
+    // the negated signal, one not gate per signal
+    pub fn not(&mut self, signal: &str) -> Result<String, String> {
+        if let Some(inverted) = self.inverted.get(signal) {
+            return Ok(inverted.clone());
+        }
+        let inverted = self.internal_gate("not", &[signal.into()])?;
+        self.inverted.insert(signal.into(), inverted.clone());
+        Ok(inverted)
+    }
+
+    pub fn finish(mut self, outputs: &[String]) -> Result<Container, String> {
+        let output_box = self.container.add(FunctionBox::new("output", [0., 0.], outputs.to_vec(), Vec::new()));
+        self.sinks.extend(outputs.iter().enumerate().map(|(i, output)| (output.clone(), (output_box, i))));
+
+        let mut undriven = Vec::new();
+        for (signal, sink) in std::mem::take(&mut self.sinks) {
+            match self.drivers.get(&signal) {
+                Some(&driver) => self.container.connect(driver, sink),
+                None if !undriven.contains(&signal) => undriven.push(signal),
+                None => {}
+            }
+        }
+        if !undriven.is_empty() {
+            return Err(format!("signals without a driver: {}", undriven.join(", ")));
+        }
+
+        auto_place(&mut self.container);
+        Ok(self.container)
+    }
+}
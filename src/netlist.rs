@@ -0,0 +1,301 @@
+use std::collections::{HashMap, HashSet};
+
+use petgraph::graph::NodeIndex;
+
+use crate::component::find_kind;
+use crate::container::Container;
+
+// identifiers reserved in Verilog or VHDL, names are checked case insensitively
+const RESERVED: &[&str] = &[
+    "abs", "access", "after", "alias", "all", "always", "and", "architecture", "array", "assert", "assign",
+    "attribute", "begin", "block", "body", "buf", "buffer", "bus", "case", "component", "configuration",
+    "constant", "default", "disconnect", "downto", "else", "elsif", "end", "endcase", "endmodule", "entity",
+    "exit", "file", "for", "function", "generate", "generic", "group", "guarded", "if", "impure", "in",
+    "inertial", "initial", "inout", "input", "is", "label", "library", "linkage", "literal", "loop", "map",
+    "mod", "module", "nand", "new", "next", "nor", "not", "null", "of", "on", "open", "or", "others", "out",
+    "output", "package", "port", "postponed", "procedure", "process", "pure", "range", "record", "reg",
+    "register", "reject", "rem", "report", "return", "rol", "ror", "select", "severity", "shared", "signal",
+    "sla", "sll", "sra", "srl", "subtype", "then", "to", "transport", "type", "unaffected", "units", "until",
+    "use", "variable", "wait", "when", "while", "wire", "with", "xnor", "xor",
+];
+
+// a value in the netlist, unconnected inputs are constant 0 like in the simulation
+#[derive(Debug, Clone, PartialEq)]
+pub enum Signal {
+    Net(String),
+    Constant(bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellKind {
+    // a gate kind of the component registry like "nand"
+    Gate(&'static str),
+    // an instance of another module of the design
+    Module(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cell {
+    pub name: String,
+    pub kind: CellKind,
+    // (port, signal), for gates the ports are the connector names
+    pub inputs: Vec<(String, Signal)>,
+    pub outputs: Vec<(String, String)>,
+}
+
+// one circuit, its "input" boxes become input ports, its "output" boxes output ports
+#[derive(Debug, Clone, PartialEq)]
+pub struct Netlist {
+    pub name: String,
+    pub inputs: Vec<String>,
+    // clocks can't be synthesized, each clock box (also in sub-circuits) becomes an additional input port
+    pub clocks: Vec<String>,
+    pub outputs: Vec<(String, Signal)>,
+    pub nets: Vec<String>,
+    pub cells: Vec<Cell>,
+}
+
+// the top level netlist with every module it instantiates, modules come before their users
+pub struct Design {
+    pub modules: Vec<Netlist>,
+    module_names: Names,
+}
+
+impl Design {
+    pub fn new(container: &Container, name: &str) -> Result<Design, String> {
+        let mut design = Design {
+            modules: Vec::new(),
+            module_names: Names::default(),
+        };
+        design.add(container, name)?;
+        Ok(design)
+    }
+
+    // identical sub-circuits share a module
+    fn add(&mut self, container: &Container, name: &str) -> Result<String, String> {
+        let mut netlist = self.netlist(container)?;
+        if let Some(existing) = self.modules.iter().find(|m| Netlist { name: m.name.clone(), ..netlist.clone() } == **m) {
+            return Ok(existing.name.clone());
+        }
+        netlist.name = self.module_names.unique(name);
+        self.modules.push(netlist);
+        Ok(self.modules.last().unwrap().name.clone())
+    }
+
+    fn netlist(&mut self, container: &Container) -> Result<Netlist, String> {
+        let graph = &container.graph;
+        let mut names = Names::default();
+        let mut inputs = Vec::new();
+        let mut clocks = Vec::new();
+        let mut nets = Vec::new();
+        // net driven by each output connector
+        let mut driven: HashMap<(NodeIndex, usize), Signal> = HashMap::new();
+
+        for n in graph.node_indices() {
+            let fb = &graph[n];
+            for c in fb.outputs_iter() {
+                let signal = match fb.name.as_str() {
+                    "input" => {
+                        let port = names.unique(&port_name("in", &c.name));
+                        inputs.push(port.clone());
+                        Signal::Net(port)
+                    }
+                    "rom" => return Err(format!("{} is a rom, only gates and sub-circuits can be exported", fb.id)),
+                    "clock" => {
+                        let port = names.unique(&fb.id);
+                        clocks.push(port.clone());
+                        Signal::Net(port)
+                    }
+                    "circuit" => {
+                        let net = names.unique(&format!("{}_{}", fb.id, c.name));
+                        nets.push(net.clone());
+                        Signal::Net(net)
+                    }
+                    kind => match find_kind(kind) {
+                        Some(k) if fb.inputs_len == 0 => Signal::Constant((k.function)(&[])),
+                        Some(_) => {
+                            let net = names.unique(&format!("{}_{}", fb.id, c.name));
+                            nets.push(net.clone());
+                            Signal::Net(net)
+                        }
+                        None => return Err(format!("{} has the unknown kind {:?}", fb.id, kind)),
+                    }
+                };
+                driven.insert((n, c.idx), signal);
+            }
+        }
+
+        let input_signal = |n: NodeIndex, idx: usize| container.input_driver((n, idx))
+            .map_or(Signal::Constant(false), |driver| driven[&driver].clone());
+
+        let mut outputs = Vec::new();
+        let mut cells = Vec::new();
+        for n in graph.node_indices() {
+            let fb = &graph[n];
+            match fb.name.as_str() {
+                "input" | "clock" => {}
+                "output" => outputs.extend(fb.inputs_iter()
+                    .map(|c| (names.unique(&port_name("out", &c.name)), input_signal(n, c.idx)))),
+                "circuit" => {
+                    let circuit = fb.circuit.as_ref().ok_or_else(|| format!("{} has no circuit", fb.id))?;
+                    let module_name = if fb.label.is_empty() { "circuit" } else { &fb.label };
+                    let module = self.add(circuit, module_name)?;
+                    let ports = self.modules.iter().find(|m| m.name == module).unwrap();
+                    let mut cell_inputs: Vec<(String, Signal)> = ports.inputs.iter().cloned().zip(fb.inputs_iter().map(|c| input_signal(n, c.idx))).collect();
+                    for clock in &ports.clocks {
+                        let port = names.unique(&format!("{}_{}", fb.id, clock));
+                        clocks.push(port.clone());
+                        cell_inputs.push((clock.clone(), Signal::Net(port)));
+                    }
+                    cells.push(Cell {
+                        name: names.unique(&fb.id),
+                        kind: CellKind::Module(module),
+                        inputs: cell_inputs,
+                        outputs: ports.outputs.iter().map(|(port, _)| port.clone()).zip(fb.outputs_iter().map(|c| net_name(&driven[&(n, c.idx)]))).collect(),
+                    });
+                }
+                kind => {
+                    let kind = find_kind(kind).unwrap();
+                    if fb.inputs_len > 0 && fb.outputs_len > 0 {
+                        cells.push(Cell {
+                            name: names.unique(&fb.id),
+                            kind: CellKind::Gate(kind.name),
+                            inputs: fb.inputs_iter().map(|c| (c.name.clone(), input_signal(n, c.idx))).collect(),
+                            outputs: fb.outputs_iter().map(|c| (c.name.clone(), net_name(&driven[&(n, c.idx)]))).collect(),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(Netlist {
+            name: String::new(),
+            inputs,
+            clocks,
+            outputs,
+            nets,
+            cells,
+        })
+    }
+}
+
+fn net_name(signal: &Signal) -> String {
+    match signal {
+        Signal::Net(net) => net.clone(),
+        Signal::Constant(_) => unreachable!("outputs of cells are nets"),
+    }
+}
+
+// the default pin names are numbers, the ports get a prefix like "in1"
+fn port_name(prefix: &str, pin: &str) -> String {
+    if pin.starts_with(|c: char| c.is_ascii_alphabetic()) { pin.into() } else { format!("{}{}", prefix, pin) }
+}
+
+// identifiers valid in Verilog and VHDL, unique within a module ignoring case
+#[derive(Default)]
+struct Names {
+    used: HashSet<String>,
+}
+
+impl Names {
+    fn unique(&mut self, raw: &str) -> String {
+        let base = sanitize(raw);
+        let name = (1..)
+            .map(|i| if i == 1 { base.clone() } else { format!("{}_{}", base, i) })
+            .find(|name| !self.used.contains(&name.to_lowercase()))
+            .unwrap();
+        self.used.insert(name.to_lowercase());
+        name
+    }
+}
+
+// letters, digits and single underscores, starting with a letter and not reserved
+pub fn sanitize(raw: &str) -> String {
+    let mut name = String::new();
+    for c in raw.chars() {
+        let c = if c.is_ascii_alphanumeric() { c } else { '_' };
+        if !(c == '_' && (name.is_empty() || name.ends_with('_'))) {
+            name.push(c);
+        }
+    }
+    let mut name = name.trim_end_matches('_').to_string();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        name.insert(0, 'n');
+    }
+    if RESERVED.contains(&name.to_lowercase().as_str()) {
+        name.push_str("_0");
+    }
+    name
+}
+
+// small circuits and helpers the netlist imports, exports and analyses are tested with
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use crate::component::find_kind;
+    use crate::container::{Container, FunctionBoxRef};
+    use crate::function_box::FunctionBox;
+    use crate::simulation::settle;
+
+    // the outputs once the circuit settled for the inputs
+    pub fn evaluate(container: &mut Container, inputs: &[bool]) -> Vec<bool> {
+        container.set_inputs(inputs);
+        assert!(settle(container), "the circuit doesn't settle for {:?}", inputs);
+        container.outputs()
+    }
+
+    pub fn pins(container: &mut Container, inputs: &[&str], outputs: &[&str]) -> (FunctionBoxRef, FunctionBoxRef) {
+        let names = |names: &[&str]| names.iter().map(|n| n.to_string()).collect();
+        let input = container.add(FunctionBox::new("input", [0., 0.], vec![], names(inputs)));
+        let output = container.add(FunctionBox::new("output", [0., 0.], names(outputs), vec![]));
+        (input, output)
+    }
+
+    pub fn gate(container: &mut Container, kind: &str, a: (FunctionBoxRef, usize), b: (FunctionBoxRef, usize)) -> FunctionBoxRef {
+        let gate = container.add(find_kind(kind).unwrap().create([0., 0.]));
+        container.connect(a, (gate, 0));
+        container.connect(b, (gate, 1));
+        gate
+    }
+
+    pub fn half_adder() -> Container {
+        let mut container = Container::new();
+        let (input, output) = pins(&mut container, &["a", "b"], &["sum", "carry"]);
+        let xor = gate(&mut container, "xor", (input, 0), (input, 1));
+        let and = gate(&mut container, "and", (input, 0), (input, 1));
+        container.connect((xor, 2), (output, 0));
+        container.connect((and, 2), (output, 1));
+        container
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitizes_identifiers() {
+        assert_eq!(sanitize("carry out"), "carry_out");
+        assert_eq!(sanitize("__a--b__"), "a_b");
+        assert_eq!(sanitize("2nd"), "n2nd");
+        assert_eq!(sanitize("über"), "ber");
+        assert_eq!(sanitize("ÄÖÜ"), "n");
+        // reserved in Verilog or VHDL, in any case
+        assert_eq!(sanitize("module"), "module_0");
+        assert_eq!(sanitize("Signal"), "Signal_0");
+        assert_eq!(sanitize("nand"), "nand_0");
+    }
+
+    #[test]
+    fn unique_names_ignore_case() {
+        let mut names = Names::default();
+        assert_eq!(names.unique("a b"), "a_b");
+        assert_eq!(names.unique("a-b"), "a_b_2");
+        assert_eq!(names.unique("A_B"), "A_B_3");
+        assert_eq!(names.unique("über"), "ber");
+        assert_eq!(names.unique("ber"), "ber_2");
+        // a suffixed name taken by an earlier pin is skipped
+        assert_eq!(names.unique("x_2"), "x_2");
+        assert_eq!(names.unique("x"), "x");
+        assert_eq!(names.unique("X"), "X_3");
+    }
+}
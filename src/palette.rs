@@ -0,0 +1,74 @@
+use graphics::Rectangle;
+
+use crate::button::Button;
+use crate::component::{available_components, ComponentEntry};
+use crate::game::{Draw, DrawCtx, PosF, State, Update};
+use crate::ui::{draw_text, rgba};
+
+const ENTRY_HEIGHT: f64 = 22.;
+const HEADER_HEIGHT: f64 = 24.;
+const WIDTH: f64 = 120.;
+
+pub struct Palette {
+    position: PosF,
+    entries: Vec<ComponentEntry>,
+    headers: Vec<(String, PosF)>,
+    buttons: Vec<Button>,
+    height: f64,
+}
+
+impl Palette {
+    pub fn new(position: PosF) -> Self {
+        let mut palette = Palette {
+            position,
+            entries: Vec::new(),
+            headers: Vec::new(),
+            buttons: Vec::new(),
+            height: 0.,
+        };
+        palette.refresh();
+        palette
+    }
+
+    // rereads the component list, e.g. after a circuit was saved to the library
+    pub fn refresh(&mut self) {
+        self.entries = available_components();
+        self.headers.clear();
+        self.buttons.clear();
+
+        let [x, mut y] = self.position;
+        let mut category: Option<&str> = None;
+        for entry in &self.entries {
+            if category != Some(entry.category.as_str()) {
+                category = Some(entry.category.as_str());
+                self.headers.push((entry.category.clone(), [x + 5., y + HEADER_HEIGHT / 2.]));
+                y += HEADER_HEIGHT;
+            }
+            self.buttons.push(Button::new_with_rect(entry.name.clone(), [x + 5., y, WIDTH - 10., ENTRY_HEIGHT - 2.], 14));
+            y += ENTRY_HEIGHT;
+        }
+        self.height = y - self.position[1] + 5.;
+    }
+
+    pub fn chosen(&mut self) -> Option<ComponentEntry> {
+        self.buttons.iter_mut()
+            .position(|b| b.pressed())
+            .map(|i| self.entries[i].clone())
+    }
+}
+
+impl Update for Palette {
+    fn update(&mut self, state: &State) {
+        self.buttons.iter_mut().for_each(|b| b.update(state));
+    }
+}
+
+impl Draw for Palette {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        Rectangle::new(rgba(99, 110, 114, 0.5))
+            .draw_tri([self.position[0], self.position[1], WIDTH, self.height], &Default::default(), ctx.c.transform, ctx.g);
+        self.headers.iter()
+            .for_each(|(name, pos)| draw_text(name, 14, *pos, rgba(45, 52, 54, 1.0), ctx));
+        self.buttons.iter().for_each(|b| b.draw(ctx));
+    }
+}
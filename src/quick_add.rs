@@ -0,0 +1,149 @@
+use graphics::Rectangle;
+use piston::input::Key;
+
+use crate::component::{available_components, ComponentEntry};
+use crate::game::{Collide, Draw, DrawCtx, PosF, State, Update};
+use crate::ui::{draw_text, rgba};
+use crate::util::fuzzy_score;
+
+const ROW_HEIGHT: f64 = 20.;
+const WIDTH: f64 = 160.;
+const MAX_MATCHES: usize = 8;
+
+// search box opened with the keyboard, the chosen component is placed where the search was opened
+#[derive(Default)]
+pub struct QuickAdd {
+    position: Option<PosF>,
+    query: String,
+    selected: usize,
+    entries: Vec<ComponentEntry>,
+    matches: Vec<usize>,
+    chosen: Option<usize>,
+}
+
+impl QuickAdd {
+    pub fn new() -> Self {
+        QuickAdd {
+            position: None,
+            query: String::new(),
+            selected: 0,
+            entries: Vec::new(),
+            matches: Vec::new(),
+            chosen: None,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.position.is_some()
+    }
+
+    pub fn open(&mut self, position: PosF) {
+        self.position = Some(position);
+        self.query.clear();
+        self.selected = 0;
+        self.entries = available_components();
+        self.update_matches();
+    }
+
+    pub fn close(&mut self) {
+        self.position = None;
+    }
+
+    // the chosen component and the position to place it at
+    pub fn chosen(&mut self) -> Option<(ComponentEntry, PosF)> {
+        let position = self.position?;
+        let i = self.chosen.take()?;
+        self.close();
+        Some((self.entries[i].clone(), position))
+    }
+
+    fn update_matches(&mut self) {
+        let mut scored: Vec<(i32, usize)> = self.entries.iter().enumerate()
+            .filter_map(|(i, e)| fuzzy_score(&self.query, &e.name).map(|s| (s, i)))
+            .collect();
+        scored.sort_by_key(|s| std::cmp::Reverse(s.0));
+        self.matches = scored.into_iter().take(MAX_MATCHES).map(|(_, i)| i).collect();
+        self.selected = self.selected.min(self.matches.len().saturating_sub(1));
+    }
+
+    fn rect(&self) -> [f64; 4] {
+        let [x, y] = self.position.unwrap_or([0., 0.]);
+        [x, y, WIDTH, ROW_HEIGHT * (1 + self.matches.len()) as f64]
+    }
+}
+
+impl Collide for QuickAdd {
+    type CollideDesc = usize;
+
+    fn collide(&self, point: PosF) -> Option<usize> {
+        let rect = self.rect();
+        rect.collide(point)?;
+        let row = ((point[1] - rect[1]) / ROW_HEIGHT) as usize;
+        if row >= 1 && row <= self.matches.len() { Some(row - 1) } else { None }
+    }
+}
+
+impl Update for QuickAdd {
+    fn update(&mut self, state: &State) {
+        if !self.is_open() {
+            return;
+        }
+
+        if state.mouse_button1_pressed {
+            match self.collide(state.mouse_position) {
+                Some(row) => self.chosen = Some(self.matches[row]),
+                None => self.close(),
+            }
+            return;
+        }
+
+        let mut changed = false;
+        state.text_typed.chars()
+            .filter(|c| !c.is_control())
+            .for_each(|c| {
+                self.query.push(c);
+                changed = true;
+            });
+
+        for key in &state.keys_pressed {
+            match key {
+                Key::Backspace if self.query.is_empty() => self.close(),
+                Key::Backspace => {
+                    self.query.pop();
+                    changed = true;
+                }
+                Key::Up => self.selected = self.selected.saturating_sub(1),
+                Key::Down => self.selected = (self.selected + 1).min(self.matches.len().saturating_sub(1)),
+                Key::Return => self.chosen = self.matches.get(self.selected).copied(),
+                _ => {}
+            }
+        }
+
+        if changed {
+            self.update_matches();
+        }
+    }
+}
+
+impl Draw for QuickAdd {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        if !self.is_open() {
+            return;
+        }
+        let rect = self.rect();
+        Rectangle::new_round_border(rgba(45, 52, 54, 1.0), 2., 1.)
+            .color(rgba(223, 230, 233, 1.0))
+            .draw_tri(rect, &Default::default(), ctx.c.transform, ctx.g);
+        draw_text(&format!("> {}", self.query), 14, [rect[0] + 5., rect[1] + ROW_HEIGHT / 2.], rgba(45, 52, 54, 1.0), ctx);
+
+        self.matches.iter().enumerate().for_each(|(row, &i)| {
+            let y = rect[1] + ROW_HEIGHT * (row + 1) as f64;
+            if row == self.selected {
+                Rectangle::new(rgba(253, 203, 110, 1.0))
+                    .draw_tri([rect[0], y, WIDTH, ROW_HEIGHT], &Default::default(), ctx.c.transform, ctx.g);
+            }
+            let entry = &self.entries[i];
+            draw_text(&format!("{} ({})", entry.name, entry.category), 14, [rect[0] + 10., y + ROW_HEIGHT / 2.], rgba(45, 52, 54, 1.0), ctx);
+        });
+    }
+}
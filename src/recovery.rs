@@ -0,0 +1,273 @@
+use std::fs::{File, TryLockError};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use graphics::Rectangle;
+
+use crate::button::Button;
+use crate::container::Container;
+use crate::file_format::to_file;
+use crate::files::config_dir;
+use crate::game::{Draw, DrawCtx, PosF, State, Update};
+use crate::ui::{draw_text, rgba};
+
+const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+// every running editor locks a lock file named by its process id and autosaves to a recovery file of the same name
+fn sessions_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("sessions"))
+}
+
+fn lock_path(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("{}.lock", pid))
+}
+
+fn recovery_file(dir: &Path, pid: u32) -> PathBuf {
+    dir.join(format!("{}.json", pid))
+}
+
+fn recovery_path() -> Option<PathBuf> {
+    sessions_dir().map(|dir| recovery_file(&dir, std::process::id()))
+}
+
+// a running session keeps its lock file locked, the system releases the lock however the process ends
+fn locked(path: &Path) -> bool {
+    match File::open(path).map(|file| file.try_lock()) {
+        Ok(Ok(())) => false,
+        Ok(Err(TryLockError::WouldBlock)) => true,
+        // if in doubt the session is running
+        _ => true,
+    }
+}
+
+// the lock of the running editor, held until the session ends
+pub struct Session {
+    dir: PathBuf,
+    pid: u32,
+    _lock: File,
+}
+
+// marks the session as running, also returns the recovery file if another session did not exit cleanly
+pub fn start_session() -> Result<(Session, Option<PathBuf>), String> {
+    let dir = sessions_dir().ok_or("No config directory, the circuit is not autosaved")?;
+    Session::start(&dir, std::process::id())
+}
+
+impl Session {
+    // the recovery file of a crashed session becomes ours, so it is offered once even with several windows starting
+    fn start(dir: &Path, pid: u32) -> Result<(Session, Option<PathBuf>), String> {
+        let lock = lock_path(dir, pid);
+        let file = std::fs::create_dir_all(dir)
+            .and_then(|_| File::create(&lock))
+            .and_then(|file| file.try_lock().map(|_| file).map_err(io::Error::from))
+            .map_err(|e| format!("Cannot lock {}, the circuit is not autosaved: {}", lock.display(), e))?;
+        let session = Session { dir: dir.to_path_buf(), pid, _lock: file };
+
+        let mut crashed: Vec<u32> = std::fs::read_dir(dir).into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|e| e == "lock") && !locked(path))
+            .filter_map(|path| path.file_stem()?.to_str()?.parse().ok())
+            .filter(|&other| other != pid)
+            .collect();
+        crashed.sort();
+
+        let recovery = recovery_file(dir, pid);
+        for other in crashed {
+            let claimed = std::fs::rename(recovery_file(dir, other), &recovery).is_ok();
+            let _ = std::fs::remove_file(lock_path(dir, other));
+            // the others are offered on the next start
+            if claimed {
+                return Ok((session, Some(recovery)));
+            }
+        }
+        Ok((session, None))
+    }
+
+    pub fn end(self) {
+        let _ = std::fs::remove_file(recovery_file(&self.dir, self.pid));
+        let _ = std::fs::remove_file(lock_path(&self.dir, self.pid));
+    }
+}
+
+pub fn discard_recovery() {
+    if let Some(path) = recovery_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+// periodically writes the circuit to the recovery file when it changed
+pub struct Autosave {
+    last_save: Instant,
+    last_json: String,
+}
+
+impl Autosave {
+    pub fn new(container: &Container) -> Self {
+        Autosave {
+            last_save: Instant::now(),
+            last_json: serde_json::to_string_pretty(&to_file(container, false)).unwrap_or_default(),
+        }
+    }
+
+    // errors are reported on every attempt, so the user learns that nothing is autosaved anymore
+    pub fn update(&mut self, container: &Container) -> Result<(), String> {
+        if self.last_save.elapsed() < AUTOSAVE_INTERVAL {
+            return Ok(());
+        }
+        self.last_save = Instant::now();
+
+        let json = serde_json::to_string_pretty(&to_file(container, false)).map_err(|e| format!("Error autosaving: {}", e))?;
+        if json == self.last_json {
+            return Ok(());
+        }
+        if let Some(path) = recovery_path() {
+            // written next to it first so a crash while writing keeps the previous recovery file
+            let temp_path = path.with_extension("json.tmp");
+            File::create(&temp_path)
+                .and_then(|mut file| file.write_all(json.as_bytes()))
+                .and_then(|_| std::fs::rename(&temp_path, &path))
+                .map_err(|e| format!("Error autosaving to {}: {}", path.display(), e))?;
+            println!("Autosaved to {}", path.display());
+            self.last_json = json;
+        }
+        Ok(())
+    }
+}
+
+// asks on startup whether the autosaved circuit of a crashed session should be restored
+pub struct RecoveryPrompt {
+    position: PosF,
+    path: Option<PathBuf>,
+    restore_button: Button,
+    discard_button: Button,
+    restore: Option<PathBuf>,
+}
+
+impl RecoveryPrompt {
+    pub fn new(position: PosF) -> Self {
+        let [x, y] = position;
+        RecoveryPrompt {
+            position,
+            path: None,
+            restore_button: Button::new_with_rect("Restore".into(), [x + 140., y + 65., 80., 25.], 16),
+            discard_button: Button::new_with_rect("Discard".into(), [x + 230., y + 65., 80., 25.], 16),
+            restore: None,
+        }
+    }
+
+    pub fn open(&mut self, path: PathBuf) {
+        self.path = Some(path);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.path.is_some()
+    }
+
+    // the recovery file once the user chose to restore it
+    pub fn restore(&mut self) -> Option<PathBuf> {
+        self.restore.take()
+    }
+}
+
+impl Update for RecoveryPrompt {
+    fn update(&mut self, state: &State) {
+        if !self.is_open() {
+            return;
+        }
+        self.restore_button.update(state);
+        self.discard_button.update(state);
+
+        if self.restore_button.pressed() {
+            self.restore = self.path.take();
+        } else if self.discard_button.pressed() {
+            self.path = None;
+            discard_recovery();
+        }
+    }
+}
+
+impl Draw for RecoveryPrompt {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        if !self.is_open() {
+            return;
+        }
+        let [x, y] = self.position;
+        Rectangle::new_round_border(rgba(45, 52, 54, 1.0), 3., 1.)
+            .color(rgba(178, 190, 195, 1.0))
+            .draw_tri([x, y, 320., 100.], &Default::default(), ctx.c.transform, ctx.g);
+        draw_text("The last session did not exit cleanly.", 16, [x + 10., y + 20.], rgba(45, 52, 54, 1.0), ctx);
+        draw_text("Restore the autosaved circuit?", 16, [x + 10., y + 42.], rgba(45, 52, 54, 1.0), ctx);
+        self.restore_button.draw(ctx);
+        self.discard_button.draw(ctx);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sessions(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rustcpu-sessions-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn crash(dir: &Path, pid: u32, circuit: &str) {
+        std::fs::create_dir_all(dir).unwrap();
+        File::create(lock_path(dir, pid)).unwrap();
+        std::fs::write(recovery_file(dir, pid), circuit).unwrap();
+    }
+
+    #[test]
+    fn offers_the_recovery_file_of_a_crashed_session_once() {
+        let dir = sessions("crashed");
+        crash(&dir, 100, "circuit of 100");
+
+        let (session, recovery) = Session::start(&dir, 200).unwrap();
+        let recovery = recovery.unwrap();
+        assert_eq!(recovery, recovery_file(&dir, 200));
+        assert_eq!(std::fs::read_to_string(&recovery).unwrap(), "circuit of 100");
+        assert!(locked(&lock_path(&dir, 200)));
+        assert!(!lock_path(&dir, 100).exists() && !recovery_file(&dir, 100).exists());
+
+        // the next window doesn't offer it again
+        assert_eq!(Session::start(&dir, 300).unwrap().1, None);
+        session.end();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn a_clean_exit_leaves_nothing_to_recover() {
+        let dir = sessions("clean");
+        let (session, recovery) = Session::start(&dir, 100).unwrap();
+        assert_eq!(recovery, None);
+        std::fs::write(recovery_file(&dir, 100), "autosave").unwrap();
+        session.end();
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 0);
+
+        assert_eq!(Session::start(&dir, 200).unwrap().1, None);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn running_sessions_keep_their_recovery_files() {
+        let dir = sessions("concurrent");
+        let (first, _) = Session::start(&dir, 100).unwrap();
+        std::fs::write(recovery_file(&dir, 100), "autosave of 100").unwrap();
+
+        let (second, recovery) = Session::start(&dir, 200).unwrap();
+        assert_eq!(recovery, None);
+        assert_eq!(std::fs::read_to_string(recovery_file(&dir, 100)).unwrap(), "autosave of 100");
+        assert!(locked(&lock_path(&dir, 100)) && locked(&lock_path(&dir, 200)));
+
+        // a crash without an autosave only leaves the lock behind, unlocked
+        drop(second);
+        assert!(!locked(&lock_path(&dir, 200)));
+        assert_eq!(Session::start(&dir, 300).unwrap().1, None);
+        assert!(!lock_path(&dir, 200).exists() && lock_path(&dir, 100).exists());
+        first.end();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,104 @@
+use std::path::{Path, PathBuf};
+
+use crate::container::Container;
+use crate::function_box::FunctionBox;
+
+// whitespace separated words, binary or hex with 0x, the first word is at address 0 and # starts a comment
+pub fn parse_words(text: &str) -> Result<Vec<u64>, String> {
+    text.lines()
+        .flat_map(|line| line.split('#').next().unwrap_or("").split_whitespace())
+        .map(|word| match word.strip_prefix("0x") {
+            Some(hex) => u64::from_str_radix(hex, 16),
+            None => u64::from_str_radix(word, 2),
+        }.map_err(|_| format!("invalid word {:?}", word)))
+        .collect()
+}
+
+pub fn load_words(path: &Path) -> Result<Vec<u64>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+    parse_words(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+// a relative file is next to the circuit file, or in the working directory for a new circuit
+pub fn resolve(file: &str, circuit_dir: Option<&Path>) -> PathBuf {
+    match circuit_dir {
+        Some(dir) if Path::new(file).is_relative() => dir.join(file),
+        _ => PathBuf::from(file),
+    }
+}
+
+// reads the words of the file param into the box, they read as all 0 when it fails
+pub fn load(function_box: &mut FunctionBox, circuit_dir: Option<&Path>) -> Result<(), String> {
+    let file = function_box.params.get("file").map_or("", |f| f.trim()).to_string();
+    function_box.rom = Vec::new();
+    if !file.is_empty() {
+        function_box.rom = load_words(&resolve(&file, circuit_dir)).map_err(|e| format!("rom {}: {}", function_box.id, e))?;
+    }
+    Ok(())
+}
+
+// loads every rom of a circuit that was read from a file, also inside sub-circuits
+pub fn load_all(container: &mut Container, circuit_dir: Option<&Path>) -> Vec<String> {
+    let mut errors = Vec::new();
+    for fb in container.graph.node_weights_mut() {
+        if fb.name == "rom" {
+            errors.extend(load(fb, circuit_dir).err());
+        }
+        if let Some(circuit) = &mut fb.circuit {
+            errors.extend(load_all(circuit, circuit_dir));
+        }
+    }
+    errors
+}
+
+// the word at the address on the inputs, address and data pins start with the most significant bit
+pub fn read(function_box: &FunctionBox) -> Vec<bool> {
+    let address = function_box.inputs_iter().fold(0usize, |a, c| a.saturating_mul(2).saturating_add(c.state as usize));
+    let word = function_box.rom.get(address).copied().unwrap_or(0);
+    let bits = function_box.outputs_len;
+    (0..bits).map(|i| word.checked_shr((bits - 1 - i) as u32).unwrap_or(0) & 1 == 1).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::find_kind;
+    use crate::simulation::tick;
+
+    #[test]
+    fn parses_binary_and_hex_words() {
+        assert_eq!(parse_words("0101 0x1f # five and 31\n\n  11\n# only a comment\n"), Ok(vec![5, 31, 3]));
+        assert_eq!(parse_words("012"), Err("invalid word \"012\"".into()));
+    }
+
+    #[test]
+    fn reads_the_word_at_the_address() {
+        let dir = std::env::temp_dir();
+        let file = format!("rustcpu-rom-{}.txt", std::process::id());
+        let path = dir.join(&file);
+        std::fs::write(&path, "110 011 0x5").unwrap();
+
+        let mut container = Container::new();
+        let rom = container.add(find_kind("rom").unwrap().create([0., 0.]));
+        container.resize_connectors(rom, 2, 3);
+        // relative to the directory of the circuit
+        container.graph[rom].params.insert("file".into(), file);
+        assert_eq!(load_all(&mut container, Some(&dir)), Vec::<String>::new());
+        let read_at = |container: &mut Container, address: [bool; 2]| {
+            container.graph[rom].connectors[0].state = address[0];
+            container.graph[rom].connectors[1].state = address[1];
+            tick(container);
+            container.graph[rom].outputs_iter().map(|c| c.state).collect::<Vec<_>>()
+        };
+        assert_eq!(read_at(&mut container, [false, false]), vec![true, true, false]);
+        assert_eq!(read_at(&mut container, [false, true]), vec![false, true, true]);
+        assert_eq!(read_at(&mut container, [true, false]), vec![true, false, true]);
+        // past the end of the file
+        assert_eq!(read_at(&mut container, [true, true]), vec![false, false, false]);
+        std::fs::remove_file(&path).unwrap();
+
+        let errors = load_all(&mut container, Some(&dir));
+        assert!(errors.len() == 1 && errors[0].starts_with("rom rom1: can't read "), "{:?}", errors);
+        assert_eq!(read_at(&mut container, [false, false]), vec![false, false, false]);
+    }
+}
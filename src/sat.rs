@@ -0,0 +1,265 @@
+// a small CDCL solver for the equivalence check, clauses are learnt from conflicts with the first unique implication point
+
+// a variable v is the literal 2v, its negation 2v + 1
+pub type Lit = u32;
+
+pub fn lit(var: usize, negated: bool) -> Lit {
+    (var as u32) << 1 | negated as u32
+}
+
+fn var(lit: Lit) -> usize {
+    (lit >> 1) as usize
+}
+
+pub struct Solver {
+    clauses: Vec<Vec<Lit>>,
+    // the clauses watching a literal, the watched literals are the first two of a clause
+    watches: Vec<Vec<usize>>,
+    assignment: Vec<Option<bool>>,
+    level: Vec<usize>,
+    reason: Vec<Option<usize>>,
+    trail: Vec<Lit>,
+    trail_limits: Vec<usize>,
+    propagated: usize,
+    activity: Vec<f64>,
+    increment: f64,
+    unsatisfiable: bool,
+}
+
+impl Solver {
+    pub fn new() -> Solver {
+        Solver {
+            clauses: Vec::new(),
+            watches: Vec::new(),
+            assignment: Vec::new(),
+            level: Vec::new(),
+            reason: Vec::new(),
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            propagated: 0,
+            activity: Vec::new(),
+            increment: 1.,
+            unsatisfiable: false,
+        }
+    }
+
+    pub fn new_var(&mut self) -> usize {
+        self.watches.extend([Vec::new(), Vec::new()]);
+        self.assignment.push(None);
+        self.level.push(0);
+        self.reason.push(None);
+        self.activity.push(0.);
+        self.assignment.len() - 1
+    }
+
+    fn value(&self, lit: Lit) -> Option<bool> {
+        self.assignment[var(lit)].map(|v| v != (lit & 1 == 1))
+    }
+
+    fn assign(&mut self, lit: Lit, reason: Option<usize>) {
+        let v = var(lit);
+        self.assignment[v] = Some(lit & 1 == 0);
+        self.level[v] = self.trail_limits.len();
+        self.reason[v] = reason;
+        self.trail.push(lit);
+    }
+
+    fn watch(&mut self, clause: Vec<Lit>) -> usize {
+        let index = self.clauses.len();
+        self.watches[clause[0] as usize].push(index);
+        self.watches[clause[1] as usize].push(index);
+        self.clauses.push(clause);
+        index
+    }
+
+    // only before solving
+    pub fn add_clause(&mut self, literals: &[Lit]) {
+        let mut clause = literals.to_vec();
+        clause.sort_unstable();
+        clause.dedup();
+        if clause.windows(2).any(|w| w[0] ^ 1 == w[1]) {
+            return;
+        }
+        match clause.len() {
+            0 => self.unsatisfiable = true,
+            1 => match self.value(clause[0]) {
+                Some(true) => {}
+                Some(false) => self.unsatisfiable = true,
+                None => self.assign(clause[0], None),
+            },
+            _ => {
+                self.watch(clause);
+            }
+        }
+    }
+
+    // the conflicting clause if the assignment contradicts one
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let false_lit = self.trail[self.propagated] ^ 1;
+            self.propagated += 1;
+            let watchers = std::mem::take(&mut self.watches[false_lit as usize]);
+            let mut kept = Vec::with_capacity(watchers.len());
+            for (i, &c) in watchers.iter().enumerate() {
+                if self.clauses[c][0] == false_lit {
+                    self.clauses[c].swap(0, 1);
+                }
+                if self.value(self.clauses[c][0]) == Some(true) {
+                    kept.push(c);
+                    continue;
+                }
+                let replacement = (2..self.clauses[c].len()).find(|&k| self.value(self.clauses[c][k]) != Some(false));
+                if let Some(k) = replacement {
+                    self.clauses[c].swap(1, k);
+                    let watched = self.clauses[c][1];
+                    self.watches[watched as usize].push(c);
+                    continue;
+                }
+                kept.push(c);
+                let first = self.clauses[c][0];
+                if self.value(first) == Some(false) {
+                    kept.extend(&watchers[i + 1..]);
+                    self.watches[false_lit as usize] = kept;
+                    return Some(c);
+                }
+                self.assign(first, Some(c));
+            }
+            self.watches[false_lit as usize] = kept;
+        }
+        None
+    }
+
+    fn bump(&mut self, v: usize) {
+        self.activity[v] += self.increment;
+        if self.activity[v] > 1e100 {
+            self.activity.iter_mut().for_each(|a| *a *= 1e-100);
+            self.increment *= 1e-100;
+        }
+    }
+
+    // the learnt clause with the asserting literal first and the level to jump back to
+    fn analyze(&mut self, conflict: usize) -> (Vec<Lit>, usize) {
+        let current = self.trail_limits.len();
+        let mut seen = vec![false; self.assignment.len()];
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut implied: Option<Lit> = None;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        loop {
+            for k in 0..self.clauses[clause].len() {
+                let q = self.clauses[clause][k];
+                let v = var(q);
+                if Some(q) == implied || seen[v] || self.level[v] == 0 {
+                    continue;
+                }
+                seen[v] = true;
+                self.bump(v);
+                if self.level[v] == current {
+                    pending += 1;
+                } else {
+                    learnt.push(q);
+                }
+            }
+            loop {
+                index -= 1;
+                if seen[var(self.trail[index])] {
+                    break;
+                }
+            }
+            let p = self.trail[index];
+            seen[var(p)] = false;
+            pending -= 1;
+            if pending == 0 {
+                learnt[0] = p ^ 1;
+                break;
+            }
+            implied = Some(p);
+            clause = self.reason[var(p)].unwrap();
+        }
+
+        let mut level = 0;
+        if learnt.len() > 1 {
+            let highest = (1..learnt.len()).max_by_key(|&k| self.level[var(learnt[k])]).unwrap();
+            learnt.swap(1, highest);
+            level = self.level[var(learnt[1])];
+        }
+        (learnt, level)
+    }
+
+    fn backtrack(&mut self, level: usize) {
+        if self.trail_limits.len() <= level {
+            return;
+        }
+        for &lit in &self.trail[self.trail_limits[level]..] {
+            self.assignment[var(lit)] = None;
+            self.reason[var(lit)] = None;
+        }
+        self.trail.truncate(self.trail_limits[level]);
+        self.trail_limits.truncate(level);
+        self.propagated = self.trail.len();
+    }
+
+    // a satisfying assignment of every variable, None if there is none
+    pub fn solve(&mut self) -> Option<Vec<bool>> {
+        if self.unsatisfiable {
+            return None;
+        }
+        loop {
+            if let Some(conflict) = self.propagate() {
+                if self.trail_limits.is_empty() {
+                    self.unsatisfiable = true;
+                    return None;
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.backtrack(level);
+                let asserting = learnt[0];
+                let reason = if learnt.len() > 1 { Some(self.watch(learnt)) } else { None };
+                self.assign(asserting, reason);
+                self.increment *= 1.05;
+            } else {
+                let unassigned = (0..self.assignment.len())
+                    .filter(|&v| self.assignment[v].is_none())
+                    .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]));
+                match unassigned {
+                    Some(v) => {
+                        self.trail_limits.push(self.trail.len());
+                        self.assign(lit(v, true), None);
+                    }
+                    None => return Some(self.assignment.iter().map(|a| a.unwrap()).collect()),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn solves_and_refutes() {
+        let mut solver = Solver::new();
+        let [a, b, c] = [solver.new_var(), solver.new_var(), solver.new_var()];
+        solver.add_clause(&[lit(a, false), lit(b, false)]);
+        solver.add_clause(&[lit(a, true), lit(c, false)]);
+        solver.add_clause(&[lit(b, true), lit(c, false)]);
+        let model = solver.solve().unwrap();
+        assert!(model[c] && (model[a] || model[b]));
+
+        // the pigeonhole principle for 4 pigeons in 3 holes needs backjumping over several levels
+        let mut solver = Solver::new();
+        let holes: Vec<Vec<usize>> = (0..4).map(|_| (0..3).map(|_| solver.new_var()).collect()).collect();
+        for pigeon in &holes {
+            solver.add_clause(&pigeon.iter().map(|&v| lit(v, false)).collect::<Vec<_>>());
+        }
+        for hole in 0..3 {
+            for (p, first) in holes.iter().enumerate() {
+                for second in &holes[p + 1..] {
+                    solver.add_clause(&[lit(first[hole], true), lit(second[hole], true)]);
+                }
+            }
+        }
+        assert!(solver.solve().is_none());
+    }
+}
@@ -0,0 +1,119 @@
+use std::ops::{Index, IndexMut};
+
+use petgraph::Direction;
+use petgraph::graph::NodeIndex;
+
+use crate::component::find_kind;
+use crate::container::{Container, FBGraph};
+use crate::function_box::FunctionBox;
+use crate::rom;
+
+// evaluates every box once and passes the new output states along the wires
+pub fn tick(container: &mut Container) {
+    // removing a box moves the last one to its index, so boxes added later can be at any index
+    let generation = 1 + container.graph.raw_nodes().iter().map(|x| x.weight.generation).max().unwrap_or(0);
+
+    container.graph.node_indices().for_each(|x| {
+        if container.graph[x].generation < generation {
+            calculate_and_set_state(&mut container.graph, x);
+            container.graph.index_mut(x).generation = generation;
+        }
+    });
+
+    container.graph.node_indices().for_each(|i| {
+        assert_eq!(container.graph[i].generation, generation)
+    });
+}
+
+pub(crate) fn boxes(container: &Container) -> Vec<&FunctionBox> {
+    container.graph.raw_nodes().iter()
+        .flat_map(|n| Some(&n.weight).into_iter().chain(n.weight.circuit.iter().flat_map(|c| boxes(c))))
+        .collect()
+}
+
+// everything a tick can change, also inside sub-circuits
+fn snapshot(container: &Container, states: &mut Vec<bool>) {
+    for fb in container.graph.raw_nodes().iter().map(|n| &n.weight) {
+        states.extend(fb.connectors.iter().map(|c| c.state));
+        fb.pending_outputs.iter().for_each(|p| states.extend(p));
+        states.push(false);
+        if let Some(circuit) = &fb.circuit {
+            snapshot(circuit, states);
+        }
+    }
+}
+
+// ticks until nothing changes anymore, false if the circuit is still changing after every signal had time to pass every box
+pub fn settle(container: &mut Container) -> bool {
+    let all = boxes(container);
+    let limit = 2 * (all.len() + 1) * all.iter().map(|fb| fb.delay.max(1)).max().unwrap_or(1);
+    let mut last = Vec::new();
+    snapshot(container, &mut last);
+    for _ in 0..limit {
+        tick(container);
+        let mut now = Vec::new();
+        snapshot(container, &mut now);
+        if now == last {
+            return true;
+        }
+        last = now;
+    }
+    false
+}
+
+fn evaluate(function_box: &mut FunctionBox) -> Vec<bool> {
+    let inputs: Vec<bool> = function_box.inputs_iter().map(|c| c.state).collect();
+
+    match function_box.name.as_str() {
+        // input pins keep the value they were set to
+        "input" => function_box.outputs_iter().map(|c| c.state).collect(),
+        "clock" => {
+            let period = function_box.param("period").unwrap_or(1).max(1);
+            vec![(function_box.generation / period) % 2 == 1; function_box.outputs_len]
+        }
+        "circuit" => {
+            let circuit = function_box.circuit.as_mut().expect("circuit box without circuit");
+            circuit.set_inputs(&inputs);
+            tick(circuit);
+            circuit.outputs()
+        }
+        "rom" => rom::read(function_box),
+        name => match find_kind(name) {
+            Some(kind) => vec![(kind.function)(&inputs); function_box.outputs_len],
+            None => panic!("Unknown function {:?}", name)
+        }
+    }
+}
+
+// delays the computed output states by the delay of the box, None while nothing arrived yet
+fn delay(function_box: &mut FunctionBox, result_states: Vec<bool>) -> Option<Vec<bool>> {
+    if function_box.delay <= 1 {
+        return Some(result_states);
+    }
+    function_box.pending_outputs.push_back(result_states);
+    let mut arrived = None;
+    while function_box.pending_outputs.len() >= function_box.delay {
+        arrived = function_box.pending_outputs.pop_front();
+    }
+    arrived
+}
+
+fn calculate_and_set_state(graph: &mut FBGraph, x: NodeIndex) {
+    let result_states = evaluate(graph.index_mut(x));
+
+    if let Some(result_states) = delay(graph.index_mut(x), result_states) {
+        graph.index_mut(x).outputs_iter_mut().zip(result_states)
+            .for_each(|(c, state)| { c.state = state });
+    }
+
+    let mut neighbors = graph.neighbors_directed(x, Direction::Outgoing)
+        .detach();
+    while let Some((edge, node)) = neighbors.next(graph) {
+        (0..graph[edge].len()).for_each(|i| {
+            let wire = &graph[edge][i];
+            let state = graph.index(x).connectors[wire.output].state;
+            let connector_idx = wire.input;
+            graph.node_weight_mut(node).unwrap().connectors[connector_idx].state = state;
+        });
+    }
+}
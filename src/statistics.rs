@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, HashMap};
+
+use petgraph::algo::toposort;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use petgraph::Direction;
+
+use crate::component::find_kind;
+use crate::container::Container;
+
+pub struct Statistics {
+    // the gates of every kind, also inside sub-circuits
+    pub gates: BTreeMap<String, usize>,
+    pub transistors: usize,
+    // the output connector driving the most inputs, on the top level
+    pub max_fan_out: Option<(String, usize)>,
+    // the delay summed up along the slowest path from an input box to an output box and its boxes
+    pub critical_path: Result<(usize, Vec<NodeIndex>), String>,
+}
+
+// static CMOS: two transistors per input of nand and nor, an inverter more for and and or, 12 per two input xor
+fn transistors(kind: &str, inputs: usize) -> usize {
+    match kind {
+        "nand" | "nor" => 2 * inputs,
+        "and" | "or" => 2 * inputs + 2,
+        "not" => 2,
+        "xor" | "xnor" => 12 * inputs.saturating_sub(1),
+        _ => 0,
+    }
+}
+
+fn count(container: &Container, gates: &mut BTreeMap<String, usize>) -> usize {
+    let mut total = 0;
+    for fb in container.graph.raw_nodes().iter().map(|n| &n.weight) {
+        match &fb.circuit {
+            Some(circuit) => total += count(circuit, gates),
+            None if find_kind(&fb.name).is_some_and(|k| k.category == "Gates") => {
+                *gates.entry(fb.name.clone()).or_default() += 1;
+                total += transistors(&fb.name, fb.inputs_len);
+            }
+            None => {}
+        }
+    }
+    total
+}
+
+// a sub-circuit takes as long as its own critical path
+fn delay(container: &Container, n: NodeIndex, sub_circuits: &HashMap<NodeIndex, usize>) -> usize {
+    let fb = &container.graph[n];
+    match sub_circuits.get(&n) {
+        Some(&length) => length,
+        None if find_kind(&fb.name).is_some_and(|k| k.category != "I/O") => fb.delay,
+        None => 0,
+    }
+}
+
+pub fn critical_path(container: &Container) -> Result<(usize, Vec<NodeIndex>), String> {
+    let graph = &container.graph;
+    let order = toposort(graph, None).map_err(|_| "the circuit has combinational loops, it has no critical path".to_string())?;
+    // the longest path from an input box ending in each box and the box before it
+    let mut longest: Vec<Option<(usize, Option<NodeIndex>)>> = vec![None; graph.node_count()];
+    // the critical paths of the sub-circuits are found once for the whole search
+    let sub_circuits: HashMap<NodeIndex, usize> = graph.node_indices()
+        .filter_map(|n| graph[n].circuit.as_ref().map(|circuit| (n, critical_path(circuit).map_or(0, |(length, _)| length))))
+        .collect();
+    for n in order {
+        let from = graph.edges_directed(n, Direction::Incoming)
+            .filter_map(|e| longest[e.source().index()].map(|(length, _)| (length, Some(e.source()))))
+            .max_by_key(|&(length, _)| length);
+        longest[n.index()] = match from {
+            Some((length, previous)) => Some((length + delay(container, n, &sub_circuits), previous)),
+            None if graph[n].name == "input" => Some((0, None)),
+            None => None,
+        };
+    }
+    let end = graph.node_indices()
+        .filter(|&n| graph[n].name == "output")
+        .filter_map(|n| longest[n.index()].map(|(length, _)| (length, n)))
+        .max_by_key(|&(length, _)| length);
+    let (length, end) = end.ok_or("no output is driven from an input")?;
+    let mut path = vec![end];
+    while let Some((_, Some(previous))) = longest[path.last().unwrap().index()] {
+        path.push(previous);
+    }
+    path.reverse();
+    Ok((length, path))
+}
+
+pub fn statistics(container: &Container) -> Statistics {
+    let graph = &container.graph;
+    let mut gates = BTreeMap::new();
+    let transistors = count(container, &mut gates);
+
+    let max_fan_out = graph.node_indices()
+        .flat_map(|n| graph[n].outputs_iter().map(move |c| (n, c)))
+        .map(|(n, c)| {
+            let fan_out = graph.edges_directed(n, Direction::Outgoing).flat_map(|e| e.weight()).filter(|w| w.output == c.idx).count();
+            (format!("{}.{}", graph[n].id, c.name), fan_out)
+        })
+        .filter(|&(_, fan_out)| fan_out > 0)
+        .fold(None, |max: Option<(String, usize)>, (name, fan_out)| match max {
+            Some((_, most)) if most >= fan_out => max,
+            _ => Some((name, fan_out)),
+        });
+
+    Statistics { gates, transistors, max_fan_out, critical_path: critical_path(container) }
+}
+
+impl Statistics {
+    pub fn report(&self, container: &Container) -> Vec<String> {
+        let total: usize = self.gates.values().sum();
+        let kinds: Vec<String> = self.gates.iter().map(|(kind, count)| format!("{} {}", count, kind)).collect();
+        let mut lines = vec![
+            if total == 0 { "No gates".to_string() } else { format!("{} gates: {}", total, kinds.join(", ")) },
+            format!("{} transistors in static CMOS", self.transistors),
+        ];
+        if let Some((connector, fan_out)) = &self.max_fan_out {
+            lines.push(format!("Maximum fan-out {} at {}", fan_out, connector));
+        }
+        lines.push(match &self.critical_path {
+            Ok((length, path)) => {
+                let names: Vec<&str> = path.iter().map(|&n| container.graph[n].id.as_str()).collect();
+                format!("Critical path of {} gate delays: {}", length, names.join(" -> "))
+            }
+            Err(e) => format!("No critical path, {}", e),
+        });
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::function_box::FunctionBox;
+    use crate::synthesis::synthesize_text;
+
+    #[test]
+    fn counts_gates_and_finds_the_critical_path() {
+        let mut container = synthesize_text("sum = a ^ b; carry = a & b").unwrap();
+        let stats = statistics(&container);
+        assert_eq!(stats.gates.get("nand"), Some(&7));
+        assert_eq!(stats.transistors, 28);
+        // a feeds both inputs of its inverter, a product of sum and the carry nand
+        assert_eq!(stats.max_fan_out.as_ref().map(|m| m.1), Some(4));
+        let (length, path) = stats.critical_path.as_ref().unwrap();
+        assert_eq!(*length, 3);
+        assert_eq!(path.len(), 5);
+
+        let slow = path[1];
+        container.graph[slow].delay = 5;
+        assert_eq!(critical_path(&container).unwrap().0, 7);
+    }
+
+    #[test]
+    fn a_sub_circuit_takes_as_long_as_its_critical_path() {
+        let mut container = Container::new();
+        let input = container.add(FunctionBox::new("input", [0., 0.], vec![], vec!["a".into(), "b".into()]));
+        let output = container.add(FunctionBox::new("output", [0., 0.], vec!["y".into()], vec![]));
+        let xor = container.add(FunctionBox::new_circuit("xor", [0., 0.], synthesize_text("y = a ^ b").unwrap()));
+        container.connect((input, 0), (xor, 0));
+        container.connect((input, 1), (xor, 1));
+        container.connect((xor, 2), (output, 0));
+
+        assert_eq!(critical_path(&container).unwrap(), (3, vec![input, xor, output]));
+    }
+}
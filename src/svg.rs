@@ -0,0 +1,182 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use petgraph::visit::EdgeRef;
+use vecmath::vec2_add;
+
+use crate::annotation::{Annotation, FRAME_TITLE_HEIGHT};
+use crate::connector::ConnectorDirection;
+use crate::container::Container;
+use crate::function_box_draw::{FunctionBoxDraw, wire_path};
+use crate::game::PosF;
+
+const MARGIN: f64 = 30.;
+const FONT: &str = "Fira Sans, sans-serif";
+
+// the colours of the canvas
+const BACKGROUND: (u8, u8, u8) = (178, 190, 195);
+const BOX: (u8, u8, u8) = (9, 132, 227);
+const TEXT: (u8, u8, u8) = (223, 230, 233);
+const HIGH: (u8, u8, u8) = (214, 48, 49);
+const LOW: (u8, u8, u8) = (99, 110, 114);
+const UNCONNECTED: (u8, u8, u8) = (178, 190, 195);
+const ANNOTATION: (u8, u8, u8) = (45, 52, 54);
+
+fn rgb((r, g, b): (u8, u8, u8)) -> String {
+    format!("rgb({},{},{})", r, g, b)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// the text is measured by the viewer, for the bounds it is estimated
+fn text_width(text: &str, size: f64) -> f64 {
+    text.chars().count() as f64 * size * 0.6
+}
+
+// the area covering everything drawn, at least the margin around the origin for an empty circuit
+fn bounds(container: &Container) -> [f64; 4] {
+    let mut points: Vec<PosF> = Vec::new();
+    for n in container.graph.node_indices() {
+        let rect = FunctionBoxDraw::new(&container.graph[n], n).rect();
+        // connector names are drawn outside of the box
+        points.push([rect[0], rect[1] - 25.]);
+        points.push([rect[0] + rect[2], rect[1] + rect[3] + 25.]);
+    }
+    for wire in container.graph.raw_edges().iter().flat_map(|e| &e.weight) {
+        points.extend(&wire.bend_points);
+    }
+    for annotation in &container.annotations {
+        let rect = match annotation {
+            Annotation::Label { text, position } => [position[0], position[1], text_width(text, 16.), 20.],
+            Annotation::Frame { rect, .. } => *rect,
+        };
+        points.push([rect[0], rect[1]]);
+        points.push([rect[0] + rect[2], rect[1] + rect[3]]);
+    }
+    if points.is_empty() {
+        return [-MARGIN, -MARGIN, 2. * MARGIN, 2. * MARGIN];
+    }
+    let min_x = points.iter().map(|p| p[0]).fold(f64::INFINITY, f64::min) - MARGIN;
+    let min_y = points.iter().map(|p| p[1]).fold(f64::INFINITY, f64::min) - MARGIN;
+    let max_x = points.iter().map(|p| p[0]).fold(f64::NEG_INFINITY, f64::max) + MARGIN;
+    let max_y = points.iter().map(|p| p[1]).fold(f64::NEG_INFINITY, f64::max) + MARGIN;
+    [min_x, min_y, max_x - min_x, max_y - min_y]
+}
+
+// the schematic as drawn on the canvas, without the grid and the highlighting of the pointer
+pub fn to_svg(container: &Container, orthogonal: bool) -> String {
+    let mut out = String::new();
+    write_svg(&mut out, container, orthogonal).unwrap();
+    out
+}
+
+fn write_svg(out: &mut String, container: &Container, orthogonal: bool) -> std::fmt::Result {
+    let graph = &container.graph;
+    let [x, y, width, height] = bounds(container);
+    writeln!(out, "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"{} {} {} {}\" font-family=\"{}\">",
+             width.ceil(), height.ceil(), x, y, width.ceil(), height.ceil(), FONT)?;
+    writeln!(out, "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>", x, y, width.ceil(), height.ceil(), rgb(BACKGROUND))?;
+
+    for annotation in &container.annotations {
+        match annotation {
+            Annotation::Label { text, position } => {
+                writeln!(out, "  <text x=\"{}\" y=\"{}\" font-size=\"16\" dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+                         position[0], position[1] + 10., rgb(ANNOTATION), escape(text))?;
+            }
+            Annotation::Frame { name, rect } => {
+                writeln!(out, "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"none\" stroke=\"{}\"/>",
+                         rect[0], rect[1], rect[2], rect[3], rgb(ANNOTATION))?;
+                writeln!(out, "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" fill-opacity=\"0.3\"/>",
+                         rect[0], rect[1], rect[2], FRAME_TITLE_HEIGHT, rgb(LOW))?;
+                writeln!(out, "  <text x=\"{}\" y=\"{}\" font-size=\"14\" dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+                         rect[0] + 5., rect[1] + FRAME_TITLE_HEIGHT / 2., rgb(ANNOTATION), escape(name))?;
+            }
+        }
+    }
+
+    let connected: HashSet<(usize, usize)> = graph.edge_indices()
+        .flat_map(|e| {
+            let (source, target) = graph.edge_endpoints(e).unwrap();
+            graph[e].iter().flat_map(move |w| vec![(source.index(), w.output), (target.index(), w.input)])
+        })
+        .collect();
+
+    // every box is drawn before its outgoing wires like on the canvas
+    for n in graph.node_indices() {
+        let draw = FunctionBoxDraw::new(&graph[n], n);
+        let rect = draw.rect();
+        writeln!(out, "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" rx=\"3\" fill=\"{}\"/>", rect[0], rect[1], rect[2], rect[3], rgb(BOX))?;
+        writeln!(out, "  <text x=\"{}\" y=\"{}\" font-size=\"16\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+                 rect[0] + rect[2] / 2., rect[1] + rect[3] / 2., rgb(TEXT), escape(graph[n].display_name()))?;
+
+        for c in &graph[n].connectors {
+            let [cx, cy] = draw.connector_position(c);
+            writeln!(out, "  <circle cx=\"{}\" cy=\"{}\" r=\"4\" fill=\"{}\"/>", cx, cy, rgb(if c.state { HIGH } else { LOW }))?;
+            if !connected.contains(&(n.index(), c.idx)) {
+                writeln!(out, "  <circle cx=\"{}\" cy=\"{}\" r=\"2\" fill=\"{}\"/>", cx, cy, rgb(UNCONNECTED))?;
+            }
+            let offset = if matches!(c.direction, ConnectorDirection::Input) { -15. } else { 15. };
+            let [tx, ty] = vec2_add([cx, cy], [0., offset]);
+            writeln!(out, "  <text x=\"{}\" y=\"{}\" font-size=\"12\" text-anchor=\"middle\" dominant-baseline=\"central\" fill=\"{}\">{}</text>",
+                     tx, ty, rgb(TEXT), escape(&c.name))?;
+        }
+
+        for e in graph.edges(n) {
+            let target = FunctionBoxDraw::new(&graph[e.target()], e.target());
+            for wire in e.weight() {
+                let connector = &graph[n].connectors[wire.output];
+                let color = rgb(if connector.state { HIGH } else { LOW });
+                let end = target.connector_position(&graph[e.target()].connectors[wire.input]);
+                let points: Vec<String> = wire_path(draw.connector_position(connector), &wire.bend_points, end, orthogonal).iter()
+                    .map(|p| format!("{},{}", p[0], p[1]))
+                    .collect();
+                writeln!(out, "  <polyline points=\"{}\" fill=\"none\" stroke=\"{}\"/>", points.join(" "), color)?;
+                for p in &wire.bend_points {
+                    writeln!(out, "  <circle cx=\"{}\" cy=\"{}\" r=\"2.5\" fill=\"{}\"/>", p[0], p[1], color)?;
+                }
+            }
+        }
+    }
+    writeln!(out, "</svg>")
+}
+
+// rasterizes the svg with the font of the canvas
+#[cfg(feature = "png")]
+pub fn to_png(container: &Container, orthogonal: bool) -> Result<Vec<u8>, String> {
+    let mut options = resvg::usvg::Options::default();
+    options.fontdb_mut().load_font_file("assets/FiraSans-Regular.ttf").map_err(|e| e.to_string())?;
+    let tree = resvg::usvg::Tree::from_str(&to_svg(container, orthogonal), &options).map_err(|e| e.to_string())?;
+    let size = tree.size().to_int_size();
+    let mut pixmap = resvg::tiny_skia::Pixmap::new(size.width(), size.height()).ok_or("the circuit is too large for an image")?;
+    resvg::render(&tree, resvg::tiny_skia::Transform::default(), &mut pixmap.as_mut());
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+#[cfg(not(feature = "png"))]
+pub fn to_png(_container: &Container, _orthogonal: bool) -> Result<Vec<u8>, String> {
+    Err("PNG export is not built in, build with --features png".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::find_kind;
+
+    #[test]
+    fn draws_boxes_connectors_and_wires() {
+        let mut container = Container::new();
+        let one = container.add(find_kind("1").unwrap().create([0., 200.]));
+        let not = container.add(find_kind("not").unwrap().create([0., 0.]));
+        container.connect((one, 0), (not, 0));
+        container.graph[one].connectors[0].state = true;
+
+        let svg = to_svg(&container, true);
+        assert_eq!(svg.matches("<rect x=\"0\"").count(), 2, "{}", svg);
+        // the not output is unconnected and gets the inner dot
+        assert_eq!(svg.matches("r=\"4\"").count(), 3);
+        assert_eq!(svg.matches("r=\"2\"").count(), 1);
+        assert!(svg.contains("<polyline points=\"24,200 24,140 24,140 24,80\" fill=\"none\" stroke=\"rgb(214,48,49)\"/>"), "{}", svg);
+    }
+}
@@ -0,0 +1,171 @@
+use std::collections::HashMap;
+
+use crate::container::Container;
+use crate::expression::{parse_equations, Expr};
+use crate::minimize::{minimize, minimize_minterms, Implicant, Sop, MAX_VARIABLES};
+use crate::net_builder::NetBuilder;
+use crate::truth_table::TruthTable;
+
+// builds everything from nand gates, a not is a nand with both inputs on the same signal
+struct Synthesizer {
+    builder: NetBuilder,
+    inverted: HashMap<String, String>,
+}
+
+impl Synthesizer {
+    // the last gate of an output is named after it so the output box finds it
+    fn nand(&mut self, inputs: Vec<String>, name: Option<&str>) -> Result<String, String> {
+        match name {
+            Some(name) => {
+                self.builder.gate("nand", &inputs, name)?;
+                Ok(name.into())
+            }
+            None => self.builder.internal_gate("nand", &inputs),
+        }
+    }
+
+    fn not(&mut self, signal: &str) -> Result<String, String> {
+        if let Some(inverted) = self.inverted.get(signal) {
+            return Ok(inverted.clone());
+        }
+        let inverted = self.nand(vec![signal.into(), signal.into()], None)?;
+        self.inverted.insert(signal.into(), inverted.clone());
+        Ok(inverted)
+    }
+
+    fn signal(&mut self, expr: &Expr, name: Option<&str>) -> Result<String, String> {
+        match expr {
+            Expr::Constant(value) => match name {
+                Some(name) => self.builder.constant(*value, name).map(|_| name.into()),
+                None => self.builder.internal_constant(*value),
+            },
+            Expr::Var(var) if name.is_none() => Ok(var.clone()),
+            Expr::Not(e) => self.inverted(e, name),
+            // a | b is !(!a & !b), a sum of products becomes two levels of nand gates
+            Expr::Or(es) => {
+                let inputs = es.iter().map(|e| self.inverted(e, None)).collect::<Result<_, _>>()?;
+                self.nand(inputs, name)
+            }
+            Expr::Xor(es) => {
+                let mut signal = self.signal(&es[0], None)?;
+                for (i, e) in es.iter().enumerate().skip(1) {
+                    let other = self.signal(e, None)?;
+                    let both = self.nand(vec![signal.clone(), other.clone()], None)?;
+                    let left = self.nand(vec![signal, both.clone()], None)?;
+                    let right = self.nand(vec![other, both], None)?;
+                    signal = self.nand(vec![left, right], if i == es.len() - 1 { name } else { None })?;
+                }
+                Ok(signal)
+            }
+            Expr::Var(_) | Expr::And(_) => {
+                let inverted = self.inverted(expr, None)?;
+                self.nand(vec![inverted.clone(), inverted], name)
+            }
+        }
+    }
+
+    fn inverted(&mut self, expr: &Expr, name: Option<&str>) -> Result<String, String> {
+        match expr {
+            Expr::Constant(value) => self.signal(&Expr::Constant(!value), name),
+            Expr::Var(var) if name.is_none() => self.not(var),
+            Expr::Not(e) => self.signal(e, name),
+            Expr::And(es) => {
+                let inputs = es.iter().map(|e| self.signal(e, None)).collect::<Result<_, _>>()?;
+                self.nand(inputs, name)
+            }
+            _ => {
+                let signal = self.signal(expr, None)?;
+                match name {
+                    Some(_) => self.nand(vec![signal.clone(), signal], name),
+                    None => self.not(&signal),
+                }
+            }
+        }
+    }
+}
+
+// a nand-only circuit, the expressions are simplified first when they depend on few enough inputs
+pub fn synthesize(inputs: &[String], outputs: &[(String, Expr)]) -> Result<Container, String> {
+    if let Some((name, _)) = outputs.iter().find(|(name, _)| inputs.contains(name)) {
+        return Err(format!("{} is an input and an output", name));
+    }
+    let mut synthesizer = Synthesizer { builder: NetBuilder::new(inputs)?, inverted: HashMap::new() };
+    for (name, expr) in outputs {
+        let simplified = match minimize(expr, inputs) {
+            Ok(sop) => sop.to_expr(),
+            Err(_) => expr.clone(),
+        };
+        synthesizer.signal(&simplified, Some(name))?;
+    }
+    let names: Vec<String> = outputs.iter().map(|(name, _)| name.clone()).collect();
+    synthesizer.builder.finish(&names)
+}
+
+pub fn synthesize_table(table: &TruthTable) -> Result<Container, String> {
+    let outputs = (0..table.outputs.len())
+        .map(|o| {
+            let minterms: Vec<u32> = table.rows.iter()
+                .filter(|(_, outputs)| outputs[o])
+                .map(|(inputs, _)| inputs.iter().fold(0, |m, &v| m << 1 | v as u32))
+                .collect();
+            // wide tables keep one product per row
+            let sop = if table.inputs.len() <= MAX_VARIABLES {
+                minimize_minterms(table.inputs.clone(), &minterms)
+            } else {
+                Sop { variables: table.inputs.clone(), terms: minterms.iter().map(|&value| Implicant { value, mask: 0 }).collect() }
+            };
+            (table.outputs[o].clone(), sop.to_expr())
+        })
+        .collect::<Vec<_>>();
+    synthesize(&table.inputs, &outputs)
+}
+
+// a truth table has only 0 and 1 below the header, anything else is read as equations
+pub fn synthesize_text(text: &str) -> Result<Container, String> {
+    let lines: Vec<&str> = text.lines().filter(|l| !l.trim().is_empty()).collect();
+    let is_table = lines.len() > 1 && lines[1..].iter()
+        .all(|l| l.chars().all(|c| "01-:|, \t".contains(c)));
+    if is_table {
+        return synthesize_table(&TruthTable::parse(text)?);
+    }
+    let equations = parse_equations(text)?;
+    let mut inputs: Vec<String> = Vec::new();
+    for variable in equations.iter().flat_map(|(_, expr)| expr.variables()) {
+        if !inputs.contains(&variable) {
+            inputs.push(variable);
+        }
+    }
+    synthesize(&inputs, &equations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::truth_table::truth_table;
+
+    fn nand_count(container: &Container) -> usize {
+        container.graph.raw_nodes().iter().filter(|n| n.weight.name == "nand").count()
+    }
+
+    #[test]
+    fn builds_nand_gates_from_an_expression() {
+        let container = synthesize_text("a & !b | c").unwrap();
+        assert!(container.graph.raw_nodes().iter().all(|n| ["input", "output", "nand"].contains(&n.weight.name.as_str())));
+        // !b, the product, !c and the sum
+        assert_eq!(nand_count(&container), 4);
+        let table = truth_table(&container).unwrap();
+        assert_eq!(table.inputs, vec!["a", "b", "c"]);
+        let expected = [false, true, false, true, true, true, false, true];
+        assert_eq!(table.rows.iter().map(|(_, o)| o[0]).collect::<Vec<_>>(), expected);
+    }
+
+    #[test]
+    fn builds_the_circuit_of_a_truth_table() {
+        let text = "a,b,sum,carry\n0,0,0,0\n0,1,1,0\n1,0,1,0\n1,1,0,1\n";
+        let container = synthesize_text(text).unwrap();
+        assert_eq!(truth_table(&container).unwrap().to_csv(), text);
+
+        let container = synthesize_text("x = a; y = !a; z = 1").unwrap();
+        assert_eq!(truth_table(&container).unwrap().to_csv(), "a,x,y,z\n0,0,1,1\n1,1,0,1\n");
+    }
+}
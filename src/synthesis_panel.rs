@@ -0,0 +1,85 @@
+use graphics::Rectangle;
+
+use crate::button::Button;
+use crate::game::{Draw, DrawCtx, PosF, State, Update};
+use crate::text_field::TextField;
+use crate::ui::{draw_text, rgba};
+
+const WIDTH: f64 = 460.;
+const HEIGHT: f64 = 110.;
+
+// asks for the expression to build a nand circuit from
+pub struct SynthesisPanel {
+    open: bool,
+    position: PosF,
+    field: TextField,
+    build_button: Button,
+    cancel_button: Button,
+    requested: Option<String>,
+}
+
+impl SynthesisPanel {
+    pub fn new(position: PosF) -> Self {
+        let [x, y] = position;
+        SynthesisPanel {
+            open: false,
+            position,
+            field: TextField::new(String::new(), [x + 10., y + 40., WIDTH - 20., 25.]),
+            build_button: Button::new_with_rect("Build".into(), [x + WIDTH - 170., y + 75., 80., 25.], 16),
+            cancel_button: Button::new_with_rect("Cancel".into(), [x + WIDTH - 85., y + 75., 75., 25.], 16),
+            requested: None,
+        }
+    }
+
+    // the text of the last build stays for corrections
+    pub fn open(&mut self) {
+        self.open = true;
+        self.field.focus();
+    }
+
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    // the expression once build was pressed
+    pub fn requested(&mut self) -> Option<String> {
+        self.requested.take()
+    }
+}
+
+impl Update for SynthesisPanel {
+    fn update(&mut self, state: &State) {
+        if !self.open {
+            return;
+        }
+        self.field.update(state);
+        self.build_button.update(state);
+        self.cancel_button.update(state);
+
+        if self.build_button.pressed() || self.field.submitted() {
+            self.requested = Some(self.field.text.clone());
+        } else if self.cancel_button.pressed() {
+            self.open = false;
+        }
+    }
+}
+
+impl Draw for SynthesisPanel {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        if !self.open {
+            return;
+        }
+        let [x, y] = self.position;
+        Rectangle::new_round_border(rgba(45, 52, 54, 1.0), 3., 1.)
+            .color(rgba(178, 190, 195, 1.0))
+            .draw_tri([x, y, WIDTH, HEIGHT], &Default::default(), ctx.c.transform, ctx.g);
+        draw_text("Build from nand gates, e.g. sum = a ^ b; carry = a & b", 16, [x + 10., y + 20.], rgba(45, 52, 54, 1.0), ctx);
+        self.field.draw(ctx);
+        self.build_button.draw(ctx);
+        self.cancel_button.draw(ctx);
+    }
+}
@@ -0,0 +1,193 @@
+use crate::container::Container;
+use crate::simulation::{settle, tick};
+
+// one line of a vector file, None is - for an input that keeps its value or x for an output that isn't checked
+struct Step {
+    line: usize,
+    inputs: Vec<Option<bool>>,
+    expected: Vec<Option<bool>>,
+    run: Run,
+}
+
+// how long a step runs before the outputs are checked
+enum Run {
+    Settle,
+    // exactly this many ticks, for delays
+    Ticks(usize),
+    // this many periods of the clock, a clock with the period p is 0 for p ticks and 1 for p ticks
+    Cycles(usize),
+}
+
+// a text file like
+//
+//   # half adder
+//   a b -> sum carry
+//   0 0 -> 0 0
+//   1 1 -> 0 1
+//   - 0 -> 1 x @ 3
+//   1 - -> x 1 @ 2 cycles
+//
+// the first line names the inputs and outputs to drive and check, the others are applied in order to the same circuit
+pub struct TestVectors {
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    steps: Vec<Step>,
+}
+
+fn values(text: &str, dont_care: char, line: usize) -> Result<Vec<Option<bool>>, String> {
+    text.split_whitespace()
+        .map(|value| match value {
+            "0" => Ok(Some(false)),
+            "1" => Ok(Some(true)),
+            v if v.len() == 1 && v.starts_with(dont_care) => Ok(None),
+            v => Err(format!("line {}: {:?} is not 0, 1 or {}", line, v, dont_care)),
+        })
+        .collect()
+}
+
+// the period of the clocks of the circuit, they have to agree for a step in clock cycles
+fn clock_period(container: &Container) -> Result<usize, String> {
+    let mut periods: Vec<usize> = container.graph.raw_nodes().iter().map(|n| &n.weight)
+        .filter(|fb| fb.name == "clock")
+        .map(|fb| fb.param("period").unwrap_or(1).max(1))
+        .collect();
+    periods.sort();
+    periods.dedup();
+    match periods[..] {
+        [period] => Ok(period),
+        [] => Err("clock cycles need a clock in the circuit".into()),
+        _ => Err("clock cycles need all clocks to have the same period".into()),
+    }
+}
+
+impl TestVectors {
+    pub fn parse(text: &str) -> Result<TestVectors, String> {
+        let mut lines = text.lines().enumerate()
+            .map(|(i, l)| (i + 1, l.split('#').next().unwrap().trim()))
+            .filter(|(_, l)| !l.is_empty());
+        let (_, header) = lines.next().ok_or("the test vectors are empty")?;
+        let (inputs, outputs) = header.split_once("->").ok_or("the first line names the inputs and outputs like a b -> y")?;
+        let mut vectors = TestVectors {
+            inputs: inputs.split_whitespace().map(String::from).collect(),
+            outputs: outputs.split_whitespace().map(String::from).collect(),
+            steps: Vec::new(),
+        };
+
+        for (line, text) in lines {
+            let (text, run) = match text.split_once('@') {
+                Some((text, count)) => {
+                    let count = count.trim();
+                    let (number, cycles) = match count.strip_suffix("cycles").or_else(|| count.strip_suffix("cycle")) {
+                        Some(number) => (number.trim(), true),
+                        None => (count, false),
+                    };
+                    let number = number.parse().map_err(|_| format!("line {}: {:?} is not a number of ticks or clock cycles", line, count))?;
+                    (text, if cycles { Run::Cycles(number) } else { Run::Ticks(number) })
+                }
+                None => (text, Run::Settle),
+            };
+            let (inputs, expected) = text.split_once("->").ok_or_else(|| format!("line {}: the inputs and outputs are separated by ->", line))?;
+            let step = Step { line, inputs: values(inputs, '-', line)?, expected: values(expected, 'x', line)?, run };
+            if step.inputs.len() != vectors.inputs.len() || step.expected.len() != vectors.outputs.len() {
+                return Err(format!("line {}: expected {} inputs and {} outputs", line, vectors.inputs.len(), vectors.outputs.len()));
+            }
+            vectors.steps.push(step);
+        }
+        Ok(vectors)
+    }
+
+    // the number of steps checked, or the first mismatch with the actual and expected values
+    pub fn run(&self, container: &Container) -> Result<usize, String> {
+        let position = |names: &[String], name: &String, kind: &str| {
+            names.iter().position(|n| n == name).ok_or_else(|| format!("the circuit has no {} {}", kind, name))
+        };
+        let input_names = container.input_names();
+        let inputs = self.inputs.iter().map(|name| position(&input_names, name, "input")).collect::<Result<Vec<_>, _>>()?;
+        let output_names = container.output_names();
+        let outputs = self.outputs.iter().map(|name| position(&output_names, name, "output")).collect::<Result<Vec<_>, _>>()?;
+
+        let mut circuit = container.clone();
+        let mut values = vec![false; input_names.len()];
+        for step in &self.steps {
+            for (&i, value) in inputs.iter().zip(&step.inputs) {
+                if let Some(value) = value {
+                    values[i] = *value;
+                }
+            }
+            circuit.set_inputs(&values);
+            match step.run {
+                Run::Ticks(ticks) => (0..ticks).for_each(|_| tick(&mut circuit)),
+                Run::Cycles(cycles) => {
+                    let period = clock_period(container).map_err(|e| format!("line {}: {}", step.line, e))?;
+                    (0..cycles * 2 * period).for_each(|_| tick(&mut circuit));
+                }
+                Run::Settle if !settle(&mut circuit) => {
+                    return Err(format!("line {}: the outputs don't settle, give the ticks or clock cycles to run like @ 10 or @ 2 cycles", step.line));
+                }
+                Run::Settle => {}
+            }
+
+            let actual = circuit.outputs();
+            let failed = outputs.iter().zip(&step.expected).any(|(&o, expected)| expected.is_some_and(|e| e != actual[o]));
+            if failed {
+                let show = |values: &mut dyn Iterator<Item=(&String, Option<bool>)>| values
+                    .map(|(name, v)| format!("{}={}", name, v.map_or("x".to_string(), |v| (v as u8).to_string())))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                let applied: Vec<String> = self.inputs.iter().zip(&inputs).map(|(name, &i)| format!("{}={}", name, values[i] as u8)).collect();
+                return Err(format!("line {}: for {} expected {} but got {}", step.line, applied.join(" "),
+                                   show(&mut self.outputs.iter().zip(step.expected.iter().copied())),
+                                   show(&mut self.outputs.iter().zip(outputs.iter().map(|&o| Some(actual[o]))))));
+            }
+        }
+        Ok(self.steps.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::find_kind;
+    use crate::function_box::FunctionBox;
+    use crate::synthesis::synthesize_text;
+
+    #[test]
+    fn runs_vectors_and_reports_the_first_mismatch() {
+        let half_adder = synthesize_text("sum = a ^ b; carry = a & b").unwrap();
+        let vectors = TestVectors::parse(include_str!("../tests/data/half_adder.vectors")).unwrap();
+        assert_eq!(vectors.run(&half_adder), Ok(5));
+
+        let wrong = TestVectors::parse("b a -> carry\n0 0 -> 0\n1 1 -> 1\n- 0 -> 1 # carry is 0 now").unwrap();
+        assert_eq!(wrong.run(&half_adder), Err("line 4: for b=1 a=0 expected carry=1 but got carry=0".into()));
+
+        let unknown = TestVectors::parse("a b -> y\n0 0 -> 0").unwrap();
+        assert_eq!(unknown.run(&half_adder), Err("the circuit has no output y".into()));
+        assert!(TestVectors::parse("a -> y\n0 1 -> 0").is_err());
+    }
+
+    #[test]
+    fn runs_a_fixed_number_of_ticks() {
+        let mut not = synthesize_text("y = !a").unwrap();
+        for fb in not.graph.node_weights_mut().filter(|fb| fb.name == "nand") {
+            fb.delay = 3;
+        }
+        // the change needs three ticks through the nand
+        let vectors = TestVectors::parse("a -> y\n0 -> 1\n1 -> 1 @ 2\n- -> 0 @ 1").unwrap();
+        assert_eq!(vectors.run(&not), Ok(3));
+    }
+
+    #[test]
+    fn runs_clock_cycles() {
+        let mut container = Container::new();
+        let clock = container.add(find_kind("clock").unwrap().create([0., 0.]));
+        let output = container.add(FunctionBox::new("output", [0., 0.], vec!["clk".into()], vec![]));
+        container.connect((clock, 0), (output, 0));
+        container.graph[clock].params.insert("period".into(), "3".into());
+
+        // a cycle ends with the clock at 1, a tick later it falls
+        let vectors = TestVectors::parse("-> clk\n-> 1 @ 1 cycle\n-> 1 @ 2 cycles\n-> 0 @ 1").unwrap();
+        assert_eq!(vectors.run(&container), Ok(3));
+        assert_eq!(TestVectors::parse("-> clk\n-> 1 @ 1 cycle").unwrap().run(&synthesize_text("clk = a").unwrap()),
+                   Err("line 2: clock cycles need a clock in the circuit".into()));
+    }
+}
@@ -0,0 +1,94 @@
+use graphics::Rectangle;
+use piston::input::Key;
+
+use crate::game::{Collide, Draw, DrawCtx, State, Update};
+use crate::ui::{draw_text, rgba};
+
+#[derive(Debug)]
+pub struct TextField {
+    rect: [f64; 4],
+    pub text: String,
+    focused: bool,
+    changed: bool,
+    submitted: bool,
+}
+
+impl TextField {
+    pub fn new(text: String, rect: [f64; 4]) -> Self {
+        TextField {
+            rect,
+            text,
+            focused: false,
+            changed: false,
+            submitted: false,
+        }
+    }
+
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn focus(&mut self) {
+        self.focused = true;
+    }
+
+    // true once after editing finished with return or by clicking somewhere else
+    pub fn changed(&mut self) -> bool {
+        std::mem::replace(&mut self.changed, false)
+    }
+
+    // true once after editing finished with return
+    pub fn submitted(&mut self) -> bool {
+        std::mem::replace(&mut self.submitted, false)
+    }
+}
+
+impl Collide for TextField {
+    type CollideDesc = ();
+
+    fn collide(&self, point: [f64; 2]) -> Option<Self::CollideDesc> {
+        self.rect.collide(point)
+    }
+}
+
+impl Update for TextField {
+    fn update(&mut self, state: &State) {
+        if state.mouse_button1_pressed {
+            let inside = self.collide(state.mouse_position).is_some();
+            if self.focused && !inside {
+                self.changed = true;
+            }
+            self.focused = inside;
+        }
+
+        if !self.focused {
+            return;
+        }
+
+        self.text.extend(state.text_typed.chars().filter(|c| !c.is_control()));
+        for key in &state.keys_pressed {
+            match key {
+                Key::Backspace => {
+                    self.text.pop();
+                }
+                Key::Return | Key::Tab => {
+                    self.focused = false;
+                    self.changed = true;
+                    self.submitted = *key == Key::Return;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+impl Draw for TextField {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        let border = if self.focused { rgba(253, 203, 110, 1.0) } else { rgba(45, 52, 54, 1.0) };
+        Rectangle::new_round_border(border, 1., 1.)
+            .color(rgba(223, 230, 233, 1.0))
+            .draw_tri(self.rect, &Default::default(), ctx.c.transform, ctx.g);
+        let text = if self.focused { format!("{}|", self.text) } else { self.text.clone() };
+        draw_text(&text, 14, [self.rect[0] + 4., self.rect[1] + self.rect[3] / 2.], rgba(45, 52, 54, 1.0), ctx);
+    }
+}
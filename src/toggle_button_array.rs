@@ -1,7 +1,4 @@
-use crate::game::{Update, State, Collide, Draw, DrawCtx, PosF, Entity};
-use crate::ui::{rgba, draw_text_centered};
-use graphics::Rectangle;
-use crate::util::rect_center;
+use crate::game::{Update, State, Collide, Draw, DrawCtx};
 use crate::function_box_draw::FunctionBoxDraw;
 use crate::container::ConnectorRef;
 use crate::button::Button;
@@ -24,8 +21,6 @@ impl<'a> ToggleButtonsDraw<'a> {
     }
 }
 
-impl Entity for ToggleButtonsDraw<'_> {}
-
 impl Collide for ToggleButtonsDraw<'_>  {
     type CollideDesc = ConnectorRef;
 
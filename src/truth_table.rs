@@ -0,0 +1,161 @@
+use crate::container::Container;
+use crate::simulation::{boxes, settle};
+
+// 2^16 rows is about what is still useful to look at
+pub const MAX_INPUTS: usize = 16;
+
+pub struct TruthTable {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    // (input values, output values), the first input is the most significant bit of the row number
+    pub rows: Vec<(Vec<bool>, Vec<bool>)>,
+}
+
+fn bits(value: usize, count: usize) -> Vec<bool> {
+    (0..count).map(|i| value >> (count - 1 - i) & 1 == 1).collect()
+}
+
+// every input combination applied to the circuit as it is now, each row starts from the same state
+pub fn truth_table(container: &Container) -> Result<TruthTable, String> {
+    let inputs = container.input_names();
+    if inputs.len() > MAX_INPUTS {
+        return Err(format!("{} inputs are too many for a truth table, at most {} are possible", inputs.len(), MAX_INPUTS));
+    }
+    if boxes(container).iter().any(|fb| fb.name == "clock") {
+        return Err("a circuit with a clock never settles, it has no truth table".into());
+    }
+
+    let mut rows = Vec::new();
+    for value in 0..1usize << inputs.len() {
+        let values = bits(value, inputs.len());
+        let mut circuit = container.clone();
+        circuit.set_inputs(&values);
+        if !settle(&mut circuit) {
+            let assignment: Vec<String> = inputs.iter().zip(&values).map(|(name, &v)| format!("{}={}", name, v as u8)).collect();
+            return Err(format!("the outputs don't settle for {}, the circuit oscillates", assignment.join(" ")));
+        }
+        rows.push((values, circuit.outputs()));
+    }
+    Ok(TruthTable { inputs, outputs: container.output_names(), rows })
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.into()
+    }
+}
+
+// the header and the values of a pasted or saved table, csv and markdown tables are both split here
+fn cells(line: &str) -> Vec<String> {
+    line.trim().trim_matches('|')
+        .split([',', '|', '\t', ' '])
+        .map(|c| c.trim().trim_matches('"').to_string())
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+fn digits(values: &[bool]) -> impl Iterator<Item=String> + '_ {
+    values.iter().map(|&v| (v as u8).to_string())
+}
+
+impl TruthTable {
+    pub fn columns(&self) -> impl Iterator<Item=&String> {
+        self.inputs.iter().chain(&self.outputs)
+    }
+
+    // the inputs are the leading columns counting through every combination, the rest are outputs
+    pub fn parse(text: &str) -> Result<TruthTable, String> {
+        let mut lines = text.lines().map(cells).filter(|c| !c.is_empty());
+        let header = lines.next().ok_or("the truth table is empty")?;
+        let mut values: Vec<Vec<bool>> = Vec::new();
+        for (i, line) in lines.enumerate() {
+            if line.iter().all(|c| c.chars().all(|c| c == '-' || c == ':')) {
+                continue;
+            }
+            if line.len() != header.len() {
+                return Err(format!("row {} has {} values but there are {} columns", i + 1, line.len(), header.len()));
+            }
+            let row = line.iter()
+                .map(|c| match c.as_str() {
+                    "0" => Ok(false),
+                    "1" => Ok(true),
+                    _ => Err(format!("row {} has {:?}, only 0 and 1 are possible", i + 1, c)),
+                })
+                .collect::<Result<Vec<bool>, String>>()?;
+            values.push(row);
+        }
+
+        let inputs = values.len().trailing_zeros() as usize;
+        let mut combinations: Vec<&[bool]> = values.iter().map(|r| &r[..inputs.min(r.len())]).collect();
+        combinations.sort();
+        combinations.dedup();
+        if !values.len().is_power_of_two() || inputs >= header.len() || combinations.len() != values.len() {
+            return Err("a truth table needs one row for every combination of the inputs, the inputs are the first columns".into());
+        }
+        Ok(TruthTable {
+            inputs: header[..inputs].to_vec(),
+            outputs: header[inputs..].to_vec(),
+            rows: values.into_iter().map(|mut r| {
+                let outputs = r.split_off(inputs);
+                (r, outputs)
+            }).collect(),
+        })
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = self.columns().map(|c| csv_field(c)).collect::<Vec<_>>().join(",");
+        out.push('\n');
+        for (inputs, outputs) in &self.rows {
+            out.push_str(&digits(inputs).chain(digits(outputs)).collect::<Vec<_>>().join(","));
+            out.push('\n');
+        }
+        out
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let row = |cells: Vec<String>| format!("| {} |\n", cells.join(" | "));
+        let mut out = row(self.columns().map(|c| c.replace('|', "\\|")).collect());
+        out.push_str(&row(self.columns().map(|_| "---".to_string()).collect()));
+        for (inputs, outputs) in &self.rows {
+            out.push_str(&row(digits(inputs).chain(digits(outputs)).collect()));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::find_kind;
+    use crate::netlist::fixtures::half_adder;
+
+    #[test]
+    fn enumerates_every_input_combination() {
+        let table = truth_table(&half_adder()).unwrap();
+        assert_eq!(table.to_csv(), "a,b,sum,carry\n0,0,0,0\n0,1,1,0\n1,0,1,0\n1,1,0,1\n");
+        assert_eq!(table.to_markdown().lines().take(3).collect::<Vec<_>>(),
+                   vec!["| a | b | sum | carry |", "| --- | --- | --- | --- |", "| 0 | 0 | 0 | 0 |"]);
+    }
+
+    #[test]
+    fn parses_saved_tables() {
+        let table = truth_table(&half_adder()).unwrap();
+        for text in [table.to_csv(), table.to_markdown()] {
+            let parsed = TruthTable::parse(&text).unwrap();
+            assert_eq!((parsed.inputs, parsed.outputs), (vec!["a".to_string(), "b".into()], vec!["sum".to_string(), "carry".into()]));
+            assert_eq!(parsed.rows, table.rows);
+        }
+        assert!(TruthTable::parse("a b y\n0 0 0\n0 1 1\n1 1 1").is_err());
+    }
+
+    #[test]
+    fn reports_oscillating_circuits() {
+        let mut container = half_adder();
+        let not = container.add(find_kind("not").unwrap().create([0., 0.]));
+        container.connect((not, 1), (not, 0));
+        let error = truth_table(&container).err().unwrap();
+        assert!(error.contains("don't settle for a=0 b=0"), "{}", error);
+    }
+}
@@ -0,0 +1,161 @@
+use graphics::{line_from_to, Rectangle};
+
+use crate::button::Button;
+use crate::game::{Draw, DrawCtx, PosF, State, Update};
+use crate::truth_table::TruthTable;
+use crate::ui::{draw_text, draw_text_centered, rgba};
+
+const ROW_HEIGHT: f64 = 20.;
+const COLUMN_WIDTH: f64 = 44.;
+const ROWS_PER_PAGE: usize = 16;
+const MIN_WIDTH: f64 = 400.;
+const MAX_WIDTH: f64 = 900.;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TableFormat {
+    Csv,
+    Markdown,
+}
+
+impl TableFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            TableFormat::Csv => "csv",
+            TableFormat::Markdown => "md",
+        }
+    }
+}
+
+// shows the truth table of the circuit page by page, it can be saved next to the circuit file
+pub struct TruthTablePanel {
+    position: PosF,
+    table: Option<TruthTable>,
+    page: usize,
+    width: f64,
+    previous_button: Button,
+    next_button: Button,
+    csv_button: Button,
+    markdown_button: Button,
+    close_button: Button,
+    export: Option<TableFormat>,
+}
+
+impl TruthTablePanel {
+    pub fn new(position: PosF) -> Self {
+        TruthTablePanel {
+            position,
+            table: None,
+            page: 0,
+            width: MIN_WIDTH,
+            previous_button: Button::new(String::new(), position),
+            next_button: Button::new(String::new(), position),
+            csv_button: Button::new(String::new(), position),
+            markdown_button: Button::new(String::new(), position),
+            close_button: Button::new(String::new(), position),
+            export: None,
+        }
+    }
+
+    fn height() -> f64 {
+        70. + (ROWS_PER_PAGE + 1) as f64 * ROW_HEIGHT
+    }
+
+    pub fn open(&mut self, table: TruthTable) {
+        let columns = table.inputs.len() + table.outputs.len();
+        self.width = (20. + columns as f64 * COLUMN_WIDTH).clamp(MIN_WIDTH, MAX_WIDTH);
+        self.table = Some(table);
+        self.page = 0;
+
+        let [x, y] = self.position;
+        let bottom = y + TruthTablePanel::height() - 35.;
+        self.previous_button = Button::new_with_rect("<".into(), [x + 10., bottom, 30., 25.], 16);
+        self.next_button = Button::new_with_rect(">".into(), [x + 45., bottom, 30., 25.], 16);
+        self.csv_button = Button::new_with_rect("CSV".into(), [x + self.width - 230., bottom, 60., 25.], 16);
+        self.markdown_button = Button::new_with_rect("Markdown".into(), [x + self.width - 165., bottom, 80., 25.], 16);
+        self.close_button = Button::new_with_rect("Close".into(), [x + self.width - 80., bottom, 70., 25.], 16);
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.table.is_some()
+    }
+
+    pub fn table(&self) -> Option<&TruthTable> {
+        self.table.as_ref()
+    }
+
+    // the format to save the table in once a save button was pressed
+    pub fn export(&mut self) -> Option<TableFormat> {
+        self.export.take()
+    }
+
+    fn pages(&self) -> usize {
+        self.table.as_ref().map_or(1, |t| t.rows.len().div_ceil(ROWS_PER_PAGE))
+    }
+}
+
+impl Update for TruthTablePanel {
+    fn update(&mut self, state: &State) {
+        if !self.is_open() {
+            return;
+        }
+        self.previous_button.update(state);
+        self.next_button.update(state);
+        self.csv_button.update(state);
+        self.markdown_button.update(state);
+        self.close_button.update(state);
+
+        if self.previous_button.pressed() {
+            self.page = self.page.saturating_sub(1);
+        } else if self.next_button.pressed() {
+            self.page = (self.page + 1).min(self.pages() - 1);
+        } else if self.csv_button.pressed() {
+            self.export = Some(TableFormat::Csv);
+        } else if self.markdown_button.pressed() {
+            self.export = Some(TableFormat::Markdown);
+        } else if self.close_button.pressed() {
+            self.table = None;
+        }
+    }
+}
+
+impl Draw for TruthTablePanel {
+    fn draw(&self, ctx: &mut DrawCtx) {
+        let table = match &self.table {
+            Some(table) => table,
+            None => return,
+        };
+        let [x, y] = self.position;
+        let text_color = rgba(45, 52, 54, 1.0);
+        Rectangle::new_round_border(text_color, 3., 1.)
+            .color(rgba(178, 190, 195, 1.0))
+            .draw_tri([x, y, self.width, TruthTablePanel::height()], &Default::default(), ctx.c.transform, ctx.g);
+        draw_text(&format!("Truth table, {} rows", table.rows.len()), 16, [x + 10., y + 15.], text_color, ctx);
+
+        // columns that don't fit are left out, the outputs are separated by a line
+        let fitting = ((self.width - 20.) / COLUMN_WIDTH) as usize;
+        let column_x = |i: usize| x + 10. + (i as f64 + 0.5) * COLUMN_WIDTH;
+        let top = y + 35.;
+        for (i, name) in table.columns().enumerate().take(fitting) {
+            let name: String = name.chars().take(5).collect();
+            draw_text_centered(&name, 14, [column_x(i), top + ROW_HEIGHT / 2.], text_color, ctx);
+        }
+        let separator = x + 10. + table.inputs.len() as f64 * COLUMN_WIDTH;
+        line_from_to(text_color, 0.5, [separator, top], [separator, top + (ROWS_PER_PAGE + 1) as f64 * ROW_HEIGHT], ctx.c.transform, ctx.g);
+
+        let rows = table.rows.iter().skip(self.page * ROWS_PER_PAGE).take(ROWS_PER_PAGE);
+        for (r, (inputs, outputs)) in rows.enumerate() {
+            let row_y = top + (r + 1) as f64 * ROW_HEIGHT + ROW_HEIGHT / 2.;
+            for (i, &value) in inputs.iter().chain(outputs).enumerate().take(fitting) {
+                let color = if value { rgba(214, 48, 49, 1.0) } else { text_color };
+                draw_text_centered(if value { "1" } else { "0" }, 14, [column_x(i), row_y], color, ctx);
+            }
+        }
+
+        draw_text(&format!("{}/{}", self.page + 1, self.pages()), 14, [x + 85., y + TruthTablePanel::height() - 22.], text_color, ctx);
+        self.previous_button.draw(ctx);
+        self.next_button.draw(ctx);
+        self.csv_button.draw(ctx);
+        self.markdown_button.draw(ctx);
+        self.close_button.draw(ctx);
+    }
+}
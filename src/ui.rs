@@ -4,92 +4,159 @@ extern crate graphics;
 extern crate glutin_window;
 
 
-use std::cell::RefCell;
-use std::cmp::max;
-use std::collections::HashMap;
 use std::f64::consts::PI;
-use std::mem::replace;
-use std::ops::IndexMut;
-use std::ptr;
-use std::ptr::eq;
+use std::path::PathBuf;
 
 use glutin_window::GlutinWindow as AppWindow;
-use graphics::{Context, Graphics};
 use opengl_graphics::{GlGraphics, OpenGL};
-use petgraph::graph::{Node, NodeIndex};
 use piston::event_loop::*;
 use piston::input::*;
-use piston::window::{AdvancedWindow, Window, WindowSettings};
-use vecmath::{col_mat3x2_transform_pos2, mat2x3_add, mat2x3_sub, row_mat2x3_mul, vec2_add, vec2_mul, vec2_sub};
+use piston::window::WindowSettings;
+use vecmath::{row_mat2x3_mul, vec2_mul, vec2_sub};
 
 use crate::button::Button;
-use crate::connector::Connector;
+use crate::component::find_kind;
 use crate::container::Container;
-use crate::function_box::FunctionBox;
-use crate::game::{DrawCtx, Entities, PosF};
+use crate::file_dialog::FileDialog;
+use crate::files::RecentFiles;
+use crate::game::{DrawCtx, Entities, Message, PosF};
+use crate::inspector::Inspector;
+use crate::keymap::KeyMap;
+use crate::palette::Palette;
+use crate::quick_add::QuickAdd;
+use crate::recovery::{self, Autosave, RecoveryPrompt};
+use crate::synthesis_panel::SynthesisPanel;
+use crate::truth_table_panel::TruthTablePanel;
 use crate::game;
 
-use self::graphics::{CharacterCache, CircleArc, color, line_from_to, Rectangle, Text};
+use self::graphics::{CharacterCache, CircleArc, Text};
 use self::graphics::math::{translate, Vec2d};
-use self::graphics::rectangle::{Border, square};
 use self::graphics::types::{Color, FontSize, Radius};
 use self::opengl_graphics::GlyphCache;
-use self::piston::{Button as PistonButton, Position, Size};
+use self::piston::{Button as PistonButton, Size};
+use self::piston::keyboard::ModifierKey;
 
 pub fn rgba(r: i32, g: i32, b: i32, a: f32) -> Color {
     [r as f32 / 255., g as f32 / 255., b as f32 / 255., a]
 }
 
-pub fn ui_main() {
+pub fn ui_main(initial_path: Option<PathBuf>) {
     let opengl = OpenGL::V3_2;
     let mut window: AppWindow = WindowSettings::new("Rust CPU", [1024, 768])
         .exit_on_esc(true).graphics_api(opengl).build().unwrap();
 
-    let ref mut gl = GlGraphics::new(opengl);
+    let gl = &mut GlGraphics::new(opengl);
 
     let mut font_normal = GlyphCache::new("assets/FiraSans-Regular.ttf", (), opengl_graphics::TextureSettings::new()).unwrap();
 
+    // the default keys are used when the user's key map is broken
+    let (key_map, key_map_error) = match KeyMap::load() {
+        Ok(key_map) => (key_map, None),
+        Err(e) => (KeyMap::default(), Some(e)),
+    };
     let mut entities = Entities {
         add_fb_button: Button::new("+".into(), [50., 500.]),
         save_button: Button::new("Save".into(), [50. + 1.*(70. +5.), 500.]),
         load_button: Button::new("Load".into(), [50. + 2.*(70. +5.), 500.]),
+        grid_button: Button::new("Grid".into(), [50. + 3.*(70. +5.), 500.]),
+        route_button: Button::new("Route".into(), [50. + 4.*(70. +5.), 500.]),
+        label_button: Button::new("Text".into(), [50. + 5.*(70. +5.), 500.]),
+        frame_button: Button::new("Frame".into(), [50. + 6.*(70. +5.), 500.]),
+        save_as_button: Button::new_with_rect("Save as".into(), [50. + 7.*(70. +5.), 500., 70., 35.], 16),
+        palette: Palette::new([894., 10.]),
+        quick_add: QuickAdd::new(),
+        inspector: Inspector::new([724., 10.]),
+        key_map,
+        file_dialog: FileDialog::new([250., 100.]),
+        recovery_prompt: RecoveryPrompt::new([250., 100.]),
+        table_button: Button::new_with_rect("Table".into(), [50. + 8.*(70. +5.), 500., 70., 35.], 16),
+        truth_table_panel: TruthTablePanel::new([50., 60.]),
+        expression_button: Button::new_with_rect("Expr".into(), [50. + 9.*(70. +5.), 500., 70., 35.], 16),
+        synthesis_button: Button::new_with_rect("NAND".into(), [50. + 10.*(70. +5.), 500., 70., 35.], 16),
+        synthesis_panel: SynthesisPanel::new([250., 100.]),
+        compare_button: Button::new_with_rect("Compare".into(), [50. + 11.*(70. +5.), 500., 70., 35.], 16),
+    };
+    let (session, session_error) = match recovery::start_session() {
+        Ok((session, recovery)) => {
+            if let Some(path) = recovery {
+                entities.recovery_prompt.open(path);
+            }
+            (Some(session), None)
+        }
+        Err(e) => (None, Some(e)),
     };
 
     let mut container = Container::new();
-    let output_fb = container.add(FunctionBox::new("output", [50., 400.], vec![], vec!["1".into(), "2".into(), "3".into()]));
-    let input_fb = container.add(FunctionBox::new("input", [50., 20.], vec!["1".into(), "2".into(), "3".into()], vec![]));
+    container.add(find_kind("input").unwrap().create([50., 400.]));
+    container.add(find_kind("output").unwrap().create([50., 20.]));
 
     let mut state = crate::game::State {
         container,
+        file_path: None,
+        recent_files: RecentFiles::load(),
+        save_simulation_state: false,
+        message: key_map_error.or(session_error).map(Message::Error),
+        modal_open: false,
         mouse_button1_pressed: false,
+        mouse_button2_clicked: false,
         mouse_position: [0., 0.],
         mouse_delta: [0., 0.],
         window_size: Size { width: 0., height: 0. },
+        keys_pressed: Vec::new(),
+        modifiers: ModifierKey::NO_MODIFIER,
+        text_typed: String::new(),
+        grid_snap: false,
+        orthogonal_wires: false,
+        design_checks: false,
+        findings: None,
+        statistics: false,
+        critical_path: None,
+        waveform: None,
+        simulation_running: true,
+        step_requested: false,
+        undo_stack: Vec::new(),
+        drag_modified: false,
         dragged_function_box: None,
         dragged_connector: None,
         dragged_connector_target: None,
+        dragged_bend_point: None,
+        dragged_annotation: None,
+        selected_function_box: None,
+        selected_annotation: None,
         dragged_entity_kind: None,
-
-        input_fb,
-        output_fb
     };
+    if let Some(path) = initial_path {
+        game::load(&mut state, &path);
+    }
+
+    let mut autosave = Autosave::new(&state.container);
 
     let mut mouse_position = state.mouse_position;
     let mut mouse_delta = state.mouse_delta;
     let mut mouse_button1_pressed = state.mouse_button1_pressed;
+    let mut mouse_button2_clicked = false;
     let mut window_size = state.window_size;
+    let mut keys_pressed = Vec::new();
+    let mut modifiers = ModifierKey::NO_MODIFIER;
+    let mut text_typed = String::new();
 
     let mut events = Events::new(EventSettings::new().lazy(true));
     while let Some(e) = events.next(&mut window) {
+        modifiers.event(&e);
         if let Some(PistonButton::Mouse(button)) = e.press_args() {
             println!("Pressed mouse button '{:?}'", button);
             if let MouseButton::Left = button {
                 mouse_button1_pressed = true;
             }
+            if let MouseButton::Right = button {
+                mouse_button2_clicked = true;
+            }
         }
         if let Some(PistonButton::Keyboard(key)) = e.press_args() {
             println!("Pressed keyboard key '{:?}'", key);
+            keys_pressed.push(key);
         };
+        e.text(|text| text_typed.push_str(text));
         if let Some(args) = e.button_args() {
             println!("Scancode {:?}", args.scancode);
         }
@@ -127,7 +194,12 @@ pub fn ui_main() {
             state.window_size = window_size;
             state.mouse_position = mouse_position;
             state.mouse_button1_pressed = mouse_button1_pressed;
+            state.mouse_button2_clicked = mouse_button2_clicked;
             state.mouse_delta = mouse_delta;
+            mouse_button2_clicked = false;
+            state.keys_pressed = std::mem::take(&mut keys_pressed);
+            state.modifiers = modifiers;
+            state.text_typed = std::mem::take(&mut text_typed);
 
             gl.draw(args.viewport(), |c, g| {
                 graphics::clear(rgba(178, 190, 195, 1.0), g);
@@ -139,6 +211,9 @@ pub fn ui_main() {
                 };
                 game::update_entities(&mut entities, &mut state);
                 game::update(&mut state);
+                if let Err(e) = autosave.update(&state.container) {
+                    state.message = Some(Message::Error(e));
+                }
                 game::draw(&state, &mut ctx);
                 game::draw_entities(&entities, &state, &mut ctx);
             },
@@ -147,24 +222,22 @@ pub fn ui_main() {
         if let Some(_args) = e.idle_args() {}
         if let Some(_args) = e.update_args() {}
     }
+
+    if let Some(session) = session {
+        session.end();
+    }
 }
 
 pub fn draw_text_centered(text: &str, font_size: FontSize, pos: PosF, color: Color, ctx: &mut DrawCtx) {
     let text_dims = measure_text(text, font_size, ctx.font_normal);
-    Text::new_color(color, font_size)
+    let _ = Text::new_color(color, font_size)
         .draw(text, ctx.font_normal, &Default::default(),
               row_mat2x3_mul(ctx.c.transform,
                              translate(vec2_sub(pos, vec2_mul(text_dims, [0.5, 0.5])))), ctx.g);
 }
 
-pub fn measure_text_old(text: &str, font_size: FontSize, font: &mut GlyphCache) -> Vec2d {
-    text.chars().into_iter()
-        .map(|x| { font.character(font_size, x).unwrap().advance_size })
-        .fold([0., 0.], |s, x| { vec2_add(s, x) })
-}
-
 pub fn measure_text(text: &str, font_size: FontSize, font: &mut GlyphCache) -> Vec2d {
-    [text.chars().into_iter()
+    [text.chars()
         .map(|x| { font.character(font_size, x).unwrap().advance_width() })
         .sum(),
         text.chars().next().map_or(0., |x| -font.character(font_size, x).unwrap().top())]
@@ -175,3 +248,11 @@ pub fn draw_arc_centered(center: PosF, circle_radius: Radius, color: Color, ctx:
         .draw_tri([center[0] - circle_radius, center[1] - circle_radius, circle_radius * 2., circle_radius * 2.], &Default::default(), ctx.c.transform, ctx.g);
 }
 
+
+pub fn draw_text(text: &str, font_size: FontSize, pos: PosF, color: Color, ctx: &mut DrawCtx) {
+    let text_dims = measure_text(text, font_size, ctx.font_normal);
+    let _ = Text::new_color(color, font_size)
+        .draw(text, ctx.font_normal, &Default::default(),
+              row_mat2x3_mul(ctx.c.transform,
+                             translate(vec2_sub(pos, [0., text_dims[1] * 0.5]))), ctx.g);
+}
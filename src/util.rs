@@ -1,5 +1,7 @@
 use crate::game::{Collide, PosF};
 
+pub const GRID_SIZE: f64 = 8.;
+
 impl Collide for [f64; 4] {
     type CollideDesc = ();
 
@@ -19,4 +21,73 @@ impl Collide for [f64; 4] {
 
 pub fn rect_center(rect: [f64; 4]) -> PosF {
     [rect[0] + rect[2]/2., rect[1] + rect[3]/2.]
-}
\ No newline at end of file
+}
+
+pub fn snap_to_grid(pos: PosF) -> PosF {
+    [(pos[0] / GRID_SIZE).round() * GRID_SIZE, (pos[1] / GRID_SIZE).round() * GRID_SIZE]
+}
+
+pub fn distance(p1: PosF, p2: PosF) -> f64 {
+    f64::sqrt((p1[0] - p2[0]) * (p1[0] - p2[0]) + (p1[1] - p2[1]) * (p1[1] - p2[1]))
+}
+
+pub fn distance_to_segment(point: PosF, from: PosF, to: PosF) -> f64 {
+    let d = [to[0] - from[0], to[1] - from[1]];
+    let len2 = d[0] * d[0] + d[1] * d[1];
+    if len2 == 0. {
+        return distance(point, from);
+    }
+    let t = (((point[0] - from[0]) * d[0] + (point[1] - from[1]) * d[1]) / len2).clamp(0., 1.);
+    distance(point, [from[0] + t * d[0], from[1] + t * d[1]])
+}
+
+// subsequence match of the query in the candidate, consecutive and leading matches score higher
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+
+    for q in query.to_lowercase().chars() {
+        let found = pos + candidate[pos..].iter().position(|&c| c == q)?;
+        score += if found == 0 { 3 } else if last_match == Some(found - 1) { 2 } else { 1 };
+        last_match = Some(found);
+        pos = found + 1;
+    }
+
+    Some(score * 10 - candidate.len() as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snaps_to_the_nearest_grid_point() {
+        assert_eq!(snap_to_grid([3.9, 4.1]), [0., 8.]);
+        assert_eq!(snap_to_grid([-3.9, -4.1]), [0., -8.]);
+        assert_eq!(snap_to_grid([16., 20.]), [16., 24.]);
+    }
+
+    #[test]
+    fn measures_the_distance_to_the_closest_point_of_a_segment() {
+        // beside, before and after the segment
+        assert_eq!(distance_to_segment([5., 3.], [0., 0.], [10., 0.]), 3.);
+        assert_eq!(distance_to_segment([-3., 4.], [0., 0.], [10., 0.]), 5.);
+        assert_eq!(distance_to_segment([13., -4.], [0., 0.], [10., 0.]), 5.);
+        // a segment of length zero is a point
+        assert_eq!(distance_to_segment([3., 4.], [0., 0.], [0., 0.]), 5.);
+    }
+
+    #[test]
+    fn fuzzy_matches_rank_leading_and_consecutive_letters_higher() {
+        assert_eq!(fuzzy_score("xr", "xor"), Some((3 + 1) * 10 - 3));
+        assert_eq!(fuzzy_score("XO", "xor"), Some((3 + 2) * 10 - 3));
+        assert_eq!(fuzzy_score("", "nand"), Some(-4));
+        // the letters must appear in order
+        assert_eq!(fuzzy_score("rx", "xor"), None);
+        assert_eq!(fuzzy_score("nandx", "nand"), None);
+        assert!(fuzzy_score("and", "and") > fuzzy_score("and", "nand"));
+        assert!(fuzzy_score("nd", "nand") > fuzzy_score("nd", "not and"));
+    }
+}
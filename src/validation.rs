@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use crate::component::find_kind;
+use crate::file_format::{CircuitDesc, ComponentDesc, PinDesc};
+
+// checks a circuit read from a file before it is built, returns every problem found
+pub fn validate(circuit: &CircuitDesc) -> Vec<String> {
+    let mut problems = Vec::new();
+    validate_circuit(circuit, "", &mut problems);
+    problems
+}
+
+// nested circuits are prefixed with the path of the circuit components containing them, e.g. "adder1/"
+fn validate_circuit(circuit: &CircuitDesc, prefix: &str, problems: &mut Vec<String>) {
+    let mut components: HashMap<&str, &ComponentDesc> = HashMap::new();
+    for component in &circuit.components {
+        let name = format!("{}{}", prefix, component.id);
+        if component.id.is_empty() {
+            problems.push(format!("{} component at {:?} has no id", component.kind, component.position));
+        } else if components.contains_key(component.id.as_str()) {
+            problems.push(format!("component id {} is used more than once", name));
+        } else {
+            components.insert(&component.id, component);
+        }
+        validate_component(component, &name, problems);
+    }
+
+    let pin_name = |pin: &PinDesc, output: bool| -> String {
+        let names = components.get(pin.component.as_str())
+            .map(|c| if output { &c.outputs } else { &c.inputs });
+        match names.and_then(|n| n.get(pin.pin)) {
+            Some(name) => format!("{}{}.{}", prefix, pin.component, name),
+            None => format!("{}{}.#{}", prefix, pin.component, pin.pin),
+        }
+    };
+
+    let mut drivers: HashMap<(&str, usize), String> = HashMap::new();
+    for wire in &circuit.wires {
+        let description = format!("wire {} -> {}", pin_name(&wire.from, true), pin_name(&wire.to, false));
+        let mut valid = true;
+        for (pin, output) in [(&wire.from, true), (&wire.to, false)] {
+            let direction = if output { "output" } else { "input" };
+            match components.get(pin.component.as_str()) {
+                None => {
+                    problems.push(format!("{}: component {}{} does not exist", description, prefix, pin.component));
+                    valid = false;
+                }
+                Some(component) => {
+                    let (len, other_len) = if output { (component.outputs.len(), component.inputs.len()) } else { (component.inputs.len(), component.outputs.len()) };
+                    if pin.pin >= len {
+                        let hint = if pin.pin < other_len { format!(", pin {} is an {} of the wrong direction", pin.pin, if output { "input" } else { "output" }) } else { String::new() };
+                        problems.push(format!("{}: {}{} has {} {} pins{}", description, prefix, pin.component, len, direction, hint));
+                        valid = false;
+                    }
+                }
+            }
+        }
+
+        if valid {
+            let driver = pin_name(&wire.from, true);
+            if let Some(other) = drivers.insert((wire.to.component.as_str(), wire.to.pin), driver.clone()) {
+                problems.push(format!("input {} is driven by both {} and {}", pin_name(&wire.to, false), other, driver));
+            }
+        }
+    }
+}
+
+fn validate_component(component: &ComponentDesc, name: &str, problems: &mut Vec<String>) {
+    if component.kind == "circuit" {
+        match &component.circuit {
+            None => problems.push(format!("circuit component {} has no embedded circuit", name)),
+            Some(circuit) => {
+                let pins = |kind: &str, output: bool| circuit.components.iter()
+                    .filter(|c| c.kind == kind)
+                    .map(|c| if output { c.outputs.len() } else { c.inputs.len() })
+                    .sum::<usize>();
+                if component.inputs.len() != pins("input", true) || component.outputs.len() != pins("output", false) {
+                    problems.push(format!("circuit component {} has {} inputs and {} outputs but its circuit has {} and {}",
+                                          name, component.inputs.len(), component.outputs.len(), pins("input", true), pins("output", false)));
+                }
+                validate_circuit(circuit, &format!("{}/", name), problems);
+            }
+        }
+    } else {
+        match find_kind(&component.kind) {
+            None => problems.push(format!("component {} has the unknown kind {:?}", name, component.kind)),
+            Some(kind) => {
+                if !kind.resizable_inputs && component.inputs.len() != kind.inputs.len() {
+                    problems.push(format!("{} component {} has {} inputs instead of {}", kind.name, name, component.inputs.len(), kind.inputs.len()));
+                }
+                if !kind.resizable_outputs && component.outputs.len() != kind.outputs.len() {
+                    problems.push(format!("{} component {} has {} outputs instead of {}", kind.name, name, component.outputs.len(), kind.outputs.len()));
+                }
+            }
+        }
+    }
+
+    if let Some(state) = &component.state {
+        if state.len() != component.inputs.len() + component.outputs.len() {
+            problems.push(format!("component {} has a state for {} pins instead of {}", name, state.len(), component.inputs.len() + component.outputs.len()));
+        }
+    }
+    if let Some(pin) = component.probes.iter().find(|&&pin| pin >= component.inputs.len() + component.outputs.len()) {
+        problems.push(format!("component {} probes the pin {} but has only {}", name, pin, component.inputs.len() + component.outputs.len()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::{json, Value};
+
+    fn problems(components: Value, wires: Value) -> Vec<String> {
+        validate(&serde_json::from_value(json!({ "components": components, "wires": wires })).unwrap())
+    }
+
+    fn wire(from: &str, from_pin: usize, to: &str, to_pin: usize) -> Value {
+        json!({ "from": { "component": from, "pin": from_pin }, "to": { "component": to, "pin": to_pin } })
+    }
+
+    fn component(id: &str, kind: &str, inputs: &[&str], outputs: &[&str]) -> Value {
+        json!({ "id": id, "kind": kind, "position": [0.0, 0.0], "inputs": inputs, "outputs": outputs })
+    }
+
+    fn not_gate(id: &str) -> Value {
+        component(id, "not", &["i"], &["not"])
+    }
+
+    #[test]
+    fn reports_wires_to_missing_components_and_pins() {
+        let components = json!([component("input1", "input", &[], &["a"]), not_gate("not1")]);
+        assert_eq!(problems(components.clone(), json!([wire("input1", 0, "not2", 0)])),
+                   vec!["wire input1.a -> not2.#0: component not2 does not exist"]);
+        assert_eq!(problems(components.clone(), json!([wire("input1", 0, "not1", 3)])),
+                   vec!["wire input1.a -> not1.#3: not1 has 1 input pins"]);
+        // the input box has only outputs, it drives the circuit
+        assert_eq!(problems(components, json!([wire("not1", 0, "input1", 0)])),
+                   vec!["wire not1.not -> input1.#0: input1 has 0 input pins, pin 0 is an output of the wrong direction"]);
+    }
+
+    #[test]
+    fn reports_inputs_with_two_drivers() {
+        let components = json!([component("input1", "input", &[], &["a", "b"]), not_gate("not1")]);
+        assert_eq!(problems(components, json!([wire("input1", 0, "not1", 0), wire("input1", 1, "not1", 0)])),
+                   vec!["input not1.i is driven by both input1.a and input1.b"]);
+    }
+
+    #[test]
+    fn reports_unknown_kinds_and_wrong_pin_counts() {
+        let components = json!([
+            component("flip1", "flipflop", &["d"], &["q"]),
+            component("not1", "not", &["a", "b"], &["not"]),
+            component("clock1", "clock", &[], &[]),
+            // resizable inputs may have any number
+            component("and1", "and", &["a", "b", "c"], &["and"]),
+        ]);
+        assert_eq!(problems(components, json!([])), vec![
+            "component flip1 has the unknown kind \"flipflop\"",
+            "not component not1 has 2 inputs instead of 1",
+            "clock component clock1 has 0 outputs instead of 1",
+        ]);
+    }
+
+    #[test]
+    fn prefixes_problems_in_sub_circuits() {
+        let mut adder = component("adder1", "circuit", &["a"], &["y"]);
+        adder["circuit"] = json!({
+            "components": [component("input1", "input", &[], &["a"]), component("output1", "output", &["y"], &[]), not_gate("not1")],
+            "wires": [wire("output1", 0, "not1", 0), wire("not1", 0, "output2", 0)],
+        });
+        assert_eq!(problems(json!([adder]), json!([])), vec![
+            "wire adder1/output1.#0 -> adder1/not1.i: adder1/output1 has 0 output pins, pin 0 is an input of the wrong direction",
+            "wire adder1/not1.not -> adder1/output2.#0: component adder1/output2 does not exist",
+        ]);
+
+        let mut broken = component("adder1", "circuit", &["a", "b"], &["y"]);
+        broken["circuit"] = json!({ "components": [component("input1", "input", &[], &["a"]), component("output1", "output", &["y"], &[])] });
+        assert_eq!(problems(json!([broken, component("adder2", "circuit", &[], &[])]), json!([])), vec![
+            "circuit component adder1 has 2 inputs and 1 outputs but its circuit has 1 and 1",
+            "circuit component adder2 has no embedded circuit",
+        ]);
+    }
+}
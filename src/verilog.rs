@@ -0,0 +1,162 @@
+use std::fmt::Write;
+
+use crate::container::Container;
+use crate::netlist::{CellKind, Design, Netlist, Signal};
+
+// structural Verilog with one module per (sub-)circuit, the last one is the top module
+pub fn to_verilog(container: &Container, name: &str) -> Result<String, String> {
+    let design = Design::new(container, name)?;
+    let mut out = String::new();
+    for (i, module) in design.modules.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_module(&mut out, module).map_err(|e| e.to_string())?;
+    }
+    Ok(out)
+}
+
+fn signal(signal: &Signal) -> String {
+    match signal {
+        Signal::Net(net) => net.clone(),
+        Signal::Constant(value) => format!("1'b{}", *value as u8),
+    }
+}
+
+fn write_module(out: &mut String, module: &Netlist) -> std::fmt::Result {
+    let ports: Vec<String> = module.inputs.iter().chain(&module.clocks).map(|p| format!("input wire {}", p))
+        .chain(module.outputs.iter().map(|(p, _)| format!("output wire {}", p)))
+        .collect();
+    if ports.is_empty() {
+        writeln!(out, "module {};", module.name)?;
+    } else {
+        writeln!(out, "module {} (\n    {}\n);", module.name, ports.join(",\n    "))?;
+    }
+
+    for net in &module.nets {
+        writeln!(out, "    wire {};", net)?;
+    }
+    for cell in &module.cells {
+        match &cell.kind {
+            // gate primitives take the output first, boxes with several outputs drive them all
+            CellKind::Gate(gate) => {
+                let (_, first) = &cell.outputs[0];
+                let inputs: Vec<String> = cell.inputs.iter().map(|(_, s)| signal(s)).collect();
+                writeln!(out, "    {} {} ({}, {});", gate, cell.name, first, inputs.join(", "))?;
+                for (_, net) in &cell.outputs[1..] {
+                    writeln!(out, "    assign {} = {};", net, first)?;
+                }
+            }
+            CellKind::Module(module_name) => {
+                let connections: Vec<String> = cell.inputs.iter().map(|(port, s)| format!(".{}({})", port, signal(s)))
+                    .chain(cell.outputs.iter().map(|(port, net)| format!(".{}({})", port, net)))
+                    .collect();
+                writeln!(out, "    {} {} ({});", module_name, cell.name, connections.join(", "))?;
+            }
+        }
+    }
+    for (port, value) in &module.outputs {
+        writeln!(out, "    assign {} = {};", port, signal(value))?;
+    }
+    writeln!(out, "endmodule")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::find_kind;
+    use crate::function_box::FunctionBox;
+    use crate::netlist::fixtures::{gate, half_adder, pins};
+
+    // two half adders share a module
+    fn full_adder() -> Container {
+        let mut container = Container::new();
+        let (input, output) = pins(&mut container, &["a", "b", "cin"], &["sum", "cout"]);
+        let first = container.add(FunctionBox::new_circuit("half adder", [0., 0.], half_adder()));
+        let second = container.add(FunctionBox::new_circuit("half adder", [0., 0.], half_adder()));
+        container.connect((input, 0), (first, 0));
+        container.connect((input, 1), (first, 1));
+        container.connect((first, 2), (second, 0));
+        container.connect((input, 2), (second, 1));
+        let or = gate(&mut container, "or", (first, 3), (second, 3));
+        container.connect((second, 2), (output, 0));
+        container.connect((or, 2), (output, 1));
+        container
+    }
+
+    #[test]
+    fn writes_a_half_adder() {
+        assert_eq!(to_verilog(&half_adder(), "half adder").unwrap(), "\
+module half_adder (
+    input wire a,
+    input wire b,
+    output wire sum,
+    output wire carry
+);
+    wire xor1_xor;
+    wire and1_and;
+    xor xor1 (xor1_xor, a, b);
+    and and1 (and1_and, a, b);
+    assign sum = xor1_xor;
+    assign carry = and1_and;
+endmodule
+");
+    }
+
+    #[test]
+    fn writes_sub_circuits_as_modules_before_their_users() {
+        let verilog = to_verilog(&full_adder(), "full_adder").unwrap();
+        let (half_adder_module, top) = verilog.split_once("\n\n").unwrap();
+        assert_eq!(format!("{}\n", half_adder_module), to_verilog(&half_adder(), "half adder").unwrap());
+        assert_eq!(top, "\
+module full_adder (
+    input wire a,
+    input wire b,
+    input wire cin,
+    output wire sum,
+    output wire cout
+);
+    wire circuit1_sum;
+    wire circuit1_carry;
+    wire circuit2_sum;
+    wire circuit2_carry;
+    wire or1_or;
+    half_adder circuit1 (.a(a), .b(b), .sum(circuit1_sum), .carry(circuit1_carry));
+    half_adder circuit2 (.a(circuit1_sum), .b(cin), .sum(circuit2_sum), .carry(circuit2_carry));
+    or or1 (or1_or, circuit1_carry, circuit2_carry);
+    assign sum = circuit2_sum;
+    assign cout = or1_or;
+endmodule
+");
+    }
+
+    #[test]
+    fn makes_pin_names_valid_and_unique() {
+        let mut container = Container::new();
+        let (input, output) = pins(&mut container, &["a b", "a-b", "A_B", "über", "wire", "1"], &["y", "Y", "2"]);
+        let not = container.add(find_kind("not").unwrap().create([0., 0.]));
+        container.connect((input, 3), (not, 0));
+        container.connect((not, 1), (output, 0));
+        container.connect((input, 4), (output, 1));
+        container.connect((input, 5), (output, 2));
+        assert_eq!(to_verilog(&container, "module").unwrap(), "\
+module module_0 (
+    input wire a_b,
+    input wire a_b_2,
+    input wire A_B_3,
+    input wire in_ber,
+    input wire wire_0,
+    input wire in1,
+    output wire y,
+    output wire Y_2,
+    output wire out2
+);
+    wire not1_not;
+    not not1 (not1_not, in_ber);
+    assign y = not1_not;
+    assign Y_2 = wire_0;
+    assign out2 = in1;
+endmodule
+");
+    }
+}
@@ -0,0 +1,168 @@
+use crate::container::Container;
+use crate::netlist::{CellKind, Design, Netlist, Signal};
+
+// one entity/architecture pair per (sub-)circuit, the last one is the top entity
+pub fn to_vhdl(container: &Container, name: &str) -> Result<String, String> {
+    let design = Design::new(container, name)?;
+    let mut out = String::new();
+    for (i, module) in design.modules.iter().enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        write_entity(&mut out, module)?;
+    }
+    Ok(out)
+}
+
+fn signal(signal: &Signal) -> String {
+    match signal {
+        Signal::Net(net) => net.clone(),
+        Signal::Constant(value) => format!("'{}'", *value as u8),
+    }
+}
+
+// the gates as expressions, VHDL has no n-ary nand so it is the negated and
+fn gate_expression(gate: &str, inputs: &[String]) -> Result<String, String> {
+    let (operator, negate) = match gate {
+        "and" | "or" | "xor" => (gate, false),
+        "nand" => ("and", true),
+        "nor" => ("or", true),
+        "xnor" => ("xor", true),
+        "not" => return Ok(format!("not {}", inputs[0])),
+        _ => return Err(format!("no VHDL for the gate {:?}", gate)),
+    };
+    let expression = inputs.join(&format!(" {} ", operator));
+    Ok(if negate { format!("not ({})", expression) } else { expression })
+}
+
+fn write_entity(out: &mut String, module: &Netlist) -> Result<(), String> {
+    out.push_str("library ieee;\nuse ieee.std_logic_1164.all;\n\n");
+
+    let ports: Vec<String> = module.inputs.iter().chain(&module.clocks).map(|p| format!("{} : in std_logic", p))
+        .chain(module.outputs.iter().map(|(p, _)| format!("{} : out std_logic", p)))
+        .collect();
+    out.push_str(&format!("entity {} is\n", module.name));
+    if !ports.is_empty() {
+        out.push_str(&format!("    port (\n        {}\n    );\n", ports.join(";\n        ")));
+    }
+    out.push_str(&format!("end entity {};\n\n", module.name));
+
+    out.push_str(&format!("architecture structural of {} is\n", module.name));
+    for net in &module.nets {
+        out.push_str(&format!("    signal {} : std_logic;\n", net));
+    }
+    out.push_str("begin\n");
+    for cell in &module.cells {
+        match &cell.kind {
+            // boxes with several outputs drive them all
+            CellKind::Gate(gate) => {
+                // constants are qualified, otherwise an expression of literals is ambiguous between bit and std_logic
+                let inputs: Vec<String> = cell.inputs.iter().map(|(_, s)| match s {
+                    Signal::Constant(_) => format!("std_logic'({})", signal(s)),
+                    Signal::Net(_) => signal(s),
+                }).collect();
+                let expression = gate_expression(gate, &inputs)?;
+                let (_, first) = &cell.outputs[0];
+                out.push_str(&format!("    {}: {} <= {};\n", cell.name, first, expression));
+                for (_, net) in &cell.outputs[1..] {
+                    out.push_str(&format!("    {} <= {};\n", net, first));
+                }
+            }
+            CellKind::Module(entity) => {
+                let connections: Vec<String> = cell.inputs.iter().map(|(port, s)| format!("{} => {}", port, signal(s)))
+                    .chain(cell.outputs.iter().map(|(port, net)| format!("{} => {}", port, net)))
+                    .collect();
+                out.push_str(&format!("    {}: entity work.{} port map ({});\n", cell.name, entity, connections.join(", ")));
+            }
+        }
+    }
+    for (port, value) in &module.outputs {
+        out.push_str(&format!("    {} <= {};\n", port, signal(value)));
+    }
+    out.push_str("end architecture structural;\n");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::component::find_kind;
+    use crate::function_box::FunctionBox;
+    use crate::netlist::fixtures::{half_adder, pins};
+
+    #[test]
+    fn writes_a_half_adder() {
+        assert_eq!(to_vhdl(&half_adder(), "half adder").unwrap(), "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity half_adder is
+    port (
+        a : in std_logic;
+        b : in std_logic;
+        sum : out std_logic;
+        carry : out std_logic
+    );
+end entity half_adder;
+
+architecture structural of half_adder is
+    signal xor1_xor : std_logic;
+    signal and1_and : std_logic;
+begin
+    xor1: xor1_xor <= a xor b;
+    and1: and1_and <= a and b;
+    sum <= xor1_xor;
+    carry <= and1_and;
+end architecture structural;
+");
+    }
+
+    // VHDL ignores case, so names that differ only in case need a suffix
+    #[test]
+    fn writes_sub_circuits_and_names_differing_in_case() {
+        let mut container = Container::new();
+        let (input, output) = pins(&mut container, &["a", "A", "in", "IN"], &["y", "Y"]);
+        let half = container.add(FunctionBox::new_circuit("Half Adder", [0., 0.], half_adder()));
+        container.connect((input, 0), (half, 0));
+        container.connect((input, 1), (half, 1));
+        let nand = container.add(find_kind("nand").unwrap().create([0., 0.]));
+        container.connect((input, 2), (nand, 0));
+        container.connect((half, 2), (output, 0));
+        container.connect((nand, 2), (output, 1));
+
+        let vhdl = to_vhdl(&container, "top").unwrap();
+        let (half_adder_entity, top) = vhdl.split_at(vhdl.match_indices("library ieee;").nth(1).unwrap().0);
+        assert_eq!(half_adder_entity, format!("{}\n", to_vhdl(&half_adder(), "Half Adder").unwrap()));
+        assert_eq!(top, "\
+library ieee;
+use ieee.std_logic_1164.all;
+
+entity top is
+    port (
+        a : in std_logic;
+        A_2 : in std_logic;
+        in_0 : in std_logic;
+        IN_0_2 : in std_logic;
+        y : out std_logic;
+        Y_2 : out std_logic
+    );
+end entity top;
+
+architecture structural of top is
+    signal circuit1_sum : std_logic;
+    signal circuit1_carry : std_logic;
+    signal nand1_nand : std_logic;
+begin
+    circuit1: entity work.Half_Adder port map (a => a, b => A_2, sum => circuit1_sum, carry => circuit1_carry);
+    nand1: nand1_nand <= not (in_0 and std_logic'('0'));
+    y <= circuit1_sum;
+    Y_2 <= nand1_nand;
+end architecture structural;
+");
+
+        // the entity of the top circuit can't take the name of a sub-circuit either
+        let vhdl = to_vhdl(&container, "HALF_ADDER").unwrap();
+        let entities: Vec<&str> = vhdl.lines().filter(|l| l.starts_with("entity ")).collect();
+        assert_eq!(entities, vec!["entity Half_Adder is", "entity HALF_ADDER_2 is"]);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use crate::container::{ConnectorRef, Container};
+
+struct Signal {
+    // boxes are found by id, removing a box while recording moves the others to new indices
+    id: String,
+    connector: ConnectorRef,
+    name: String,
+    value: bool,
+}
+
+// the value changes of the probed connectors over the ticks of the simulation
+pub struct Waveform {
+    signals: Vec<Signal>,
+    // (tick, signal, new value), every signal has one at tick 0
+    changes: Vec<(usize, usize, bool)>,
+    ticks: usize,
+}
+
+// vcd identifiers are short codes from the printable characters ! to ~
+fn identifier(mut i: usize) -> String {
+    let mut code = String::new();
+    loop {
+        code.push((b'!' + (i % 94) as u8) as char);
+        i /= 94;
+        if i == 0 {
+            return code;
+        }
+        i -= 1;
+    }
+}
+
+// names in the dump can't contain whitespace
+fn reference(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+impl Waveform {
+    // starts with the current values of the probed connectors
+    pub fn new(container: &Container) -> Waveform {
+        let graph = &container.graph;
+        let signals: Vec<Signal> = graph.node_indices()
+            .flat_map(|n| graph[n].connectors.iter().filter(|c| c.probe).map(move |c| Signal {
+                id: graph[n].id.clone(),
+                connector: c.idx,
+                name: c.name.clone(),
+                value: c.state,
+            }))
+            .collect();
+        let changes = signals.iter().enumerate().map(|(i, s)| (0, i, s.value)).collect();
+        Waveform { signals, changes, ticks: 0 }
+    }
+
+    pub fn signal_count(&self) -> usize {
+        self.signals.len()
+    }
+
+    pub fn ticks(&self) -> usize {
+        self.ticks
+    }
+
+    // called after every tick, signals of removed boxes keep their last value
+    pub fn record(&mut self, container: &Container) {
+        self.ticks += 1;
+        for (i, signal) in self.signals.iter_mut().enumerate() {
+            let value = container.find_id(&signal.id)
+                .and_then(|n| container.graph[n].connectors.get(signal.connector))
+                .map_or(signal.value, |c| c.state);
+            if value != signal.value {
+                signal.value = value;
+                self.changes.push((self.ticks, i, value));
+            }
+        }
+    }
+
+    // a value change dump for GTKWave, every box is a scope of its probed connectors and a tick is one nanosecond
+    pub fn to_vcd(&self) -> String {
+        let mut vcd = String::from("$version rustcpu $end\n$timescale 1ns $end\n$scope module circuit $end\n");
+        let mut scopes: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+        for (i, signal) in self.signals.iter().enumerate() {
+            scopes.entry(&signal.id).or_default().push(i);
+        }
+        for (id, signals) in scopes {
+            vcd += &format!("$scope module {} $end\n", reference(id));
+            for i in signals {
+                vcd += &format!("$var wire 1 {} {} $end\n", identifier(i), reference(&self.signals[i].name));
+            }
+            vcd += "$upscope $end\n";
+        }
+        vcd += "$upscope $end\n$enddefinitions $end\n";
+
+        let mut time = None;
+        for &(tick, i, value) in &self.changes {
+            if time != Some(tick) {
+                if time == Some(0) {
+                    vcd += "$end\n";
+                }
+                vcd += &format!("#{}\n", tick);
+                if tick == 0 {
+                    vcd += "$dumpvars\n";
+                }
+                time = Some(tick);
+            }
+            vcd += &format!("{}{}\n", value as u8, identifier(i));
+        }
+        if time == Some(0) {
+            vcd += "$end\n";
+        }
+        // the end of the recording, so the last values are shown for as long as they lasted
+        if time.is_some_and(|t| t < self.ticks) {
+            vcd += &format!("#{}\n", self.ticks);
+        }
+        vcd
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::tick;
+    use crate::synthesis::synthesize_text;
+
+    #[test]
+    fn records_the_changes_of_probed_connectors() {
+        let mut container = synthesize_text("y = !a").unwrap();
+        let input = container.find_id("input1").unwrap();
+        let output = container.find_id("output1").unwrap();
+        container.graph[input].connectors[0].probe = true;
+        container.graph[output].connectors[0].probe = true;
+        tick(&mut container);
+
+        let mut waveform = Waveform::new(&container);
+        assert_eq!(waveform.signal_count(), 2);
+        container.set_inputs(&[true]);
+        for _ in 0..3 {
+            tick(&mut container);
+            waveform.record(&container);
+        }
+        assert_eq!(waveform.to_vcd(), "\
+$version rustcpu $end
+$timescale 1ns $end
+$scope module circuit $end
+$scope module input1 $end
+$var wire 1 ! a $end
+$upscope $end
+$scope module output1 $end
+$var wire 1 \" y $end
+$upscope $end
+$upscope $end
+$enddefinitions $end
+#0
+$dumpvars
+0!
+1\"
+$end
+#1
+1!
+0\"
+#3
+");
+        assert_eq!((identifier(93), identifier(94), identifier(94 + 94 * 94)), ("~".into(), "!!".into(), "!!!".into()));
+    }
+}
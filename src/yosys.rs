@@ -0,0 +1,316 @@
+use std::collections::HashMap;
+
+use petgraph::graph::NodeIndex;
+use serde_json::{Map, Value};
+
+use crate::component::find_kind;
+use crate::container::Container;
+use crate::function_box::FunctionBox;
+use crate::layout::auto_place;
+
+// a bit of a yosys netlist, numbers are nets and the strings "0", "1", "x" and "z" constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Bit {
+    Net(u64),
+    Constant(bool),
+    Undefined,
+}
+
+fn parse_bit(value: &Value) -> Result<Bit, String> {
+    match value {
+        Value::Number(n) => n.as_u64().map(Bit::Net).ok_or_else(|| format!("invalid bit {}", value)),
+        Value::String(s) if s == "0" => Ok(Bit::Constant(false)),
+        Value::String(s) if s == "1" => Ok(Bit::Constant(true)),
+        Value::String(s) if s == "x" || s == "z" => Ok(Bit::Undefined),
+        _ => Err(format!("invalid bit {}", value)),
+    }
+}
+
+fn bits(value: Option<&Value>) -> Result<Vec<Bit>, String> {
+    value.and_then(Value::as_array).ok_or("missing bits")?.iter().map(parse_bit).collect()
+}
+
+// the output of `yosys write_json` has a "modules" object
+pub fn is_yosys_netlist(value: &Value) -> bool {
+    value.get("modules").is_some_and(Value::is_object) && value.get("components").is_none()
+}
+
+// imports the top module, cells instantiating other modules of the file become sub-circuits
+pub fn import_yosys(value: &Value) -> Result<Container, String> {
+    let modules = value.get("modules").and_then(Value::as_object).ok_or("missing modules")?;
+    let top = top_module(modules)?;
+    Importer { modules, imported: HashMap::new() }.import(top, &mut Vec::new())
+}
+
+// the module marked as top, otherwise the one no other module instantiates
+fn top_module(modules: &Map<String, Value>) -> Result<&str, String> {
+    let marked = modules.iter().find(|(_, m)| {
+        m.pointer("/attributes/top").is_some_and(|top| match top {
+            Value::String(s) => s.contains('1'),
+            Value::Number(n) => n.as_u64() != Some(0),
+            _ => false,
+        })
+    });
+    if let Some((name, _)) = marked {
+        return Ok(name);
+    }
+    let instantiated: Vec<&str> = modules.values()
+        .filter_map(|m| m.get("cells").and_then(Value::as_object))
+        .flat_map(|cells| cells.values().filter_map(|c| c.get("type").and_then(Value::as_str)))
+        .collect();
+    modules.keys().find(|name| !instantiated.contains(&name.as_str()))
+        .map(String::as_str)
+        .ok_or_else(|| "no top module".into())
+}
+
+// the pins of the ports in one direction in declaration order, a port of several bits gets one pin per bit like "a[0]"
+fn port_pins(module: &Value, direction: &str) -> Result<Vec<(String, Bit, String)>, String> {
+    let mut pins = Vec::new();
+    for (name, port) in module.get("ports").and_then(Value::as_object).into_iter().flatten() {
+        let port_direction = port.get("direction").and_then(Value::as_str).unwrap_or("");
+        if port_direction == "inout" {
+            return Err(format!("inout port {} is not supported", name));
+        }
+        if port_direction != direction {
+            continue;
+        }
+        let port_bits = bits(port.get("bits"))?;
+        let width = port_bits.len();
+        for (i, bit) in port_bits.into_iter().enumerate() {
+            let pin = if width == 1 { name.clone() } else { format!("{}[{}]", name, i) };
+            pins.push((name.clone(), bit, pin));
+        }
+    }
+    Ok(pins)
+}
+
+struct Importer<'a> {
+    modules: &'a Map<String, Value>,
+    imported: HashMap<String, Container>,
+}
+
+// the boxes of one module while they are created, wires are added once every driver is known
+struct ModuleBuilder {
+    container: Container,
+    drivers: HashMap<u64, (NodeIndex, usize)>,
+    sinks: Vec<(Bit, (NodeIndex, usize))>,
+    // nets which are just another name of a bit, from buffers
+    aliases: HashMap<u64, Bit>,
+    constants: HashMap<bool, NodeIndex>,
+}
+
+impl ModuleBuilder {
+    fn gate(&mut self, kind: &str, inputs: &[Bit], output: Bit) -> NodeIndex {
+        let mut function_box = find_kind(kind).unwrap().create([0., 0.]);
+        if function_box.inputs_len != inputs.len() {
+            let names = (0..inputs.len()).map(|i| format!("i{}", i + 1)).collect();
+            let outputs = function_box.outputs_iter().map(|c| c.name.clone()).collect();
+            function_box.set_connectors(names, outputs);
+        }
+        let n = self.container.add(function_box);
+        self.sinks.extend(inputs.iter().enumerate().map(|(i, &bit)| (bit, (n, i))));
+        self.drive(output, (n, inputs.len()));
+        n
+    }
+
+    // a gate whose output is only used inside a composed cell
+    fn internal_gate(&mut self, kind: &str, inputs: &[Bit]) -> Bit {
+        let n = self.gate(kind, inputs, Bit::Undefined);
+        let net = u64::MAX - n.index() as u64;
+        self.drivers.insert(net, (n, inputs.len()));
+        Bit::Net(net)
+    }
+
+    fn drive(&mut self, bit: Bit, connector: (NodeIndex, usize)) {
+        if let Bit::Net(net) = bit {
+            self.drivers.insert(net, connector);
+        }
+    }
+
+    fn resolve(&self, mut bit: Bit) -> Bit {
+        while let Bit::Net(net) = bit {
+            match self.aliases.get(&net) {
+                Some(&alias) if alias != bit => bit = alias,
+                _ => break,
+            }
+        }
+        bit
+    }
+
+    fn connect_sinks(&mut self) {
+        for (bit, sink) in std::mem::take(&mut self.sinks) {
+            let driver = match self.resolve(bit) {
+                Bit::Net(net) => self.drivers.get(&net).copied(),
+                Bit::Constant(value) => {
+                    let container = &mut self.container;
+                    let n = *self.constants.entry(value)
+                        .or_insert_with(|| container.add(find_kind(if value { "1" } else { "0" }).unwrap().create([0., 0.])));
+                    Some((n, 0))
+                }
+                Bit::Undefined => None,
+            };
+            if let Some(driver) = driver {
+                if self.container.can_connect(driver, sink) {
+                    self.container.connect(driver, sink);
+                }
+            }
+        }
+    }
+}
+
+impl Importer<'_> {
+    fn import(&mut self, name: &str, stack: &mut Vec<String>) -> Result<Container, String> {
+        if let Some(container) = self.imported.get(name) {
+            return Ok(container.clone());
+        }
+        if stack.iter().any(|s| s == name) {
+            return Err(format!("module {} instantiates itself", name));
+        }
+        stack.push(name.into());
+        let module = &self.modules[name];
+
+        let mut builder = ModuleBuilder {
+            container: Container::new(),
+            drivers: HashMap::new(),
+            sinks: Vec::new(),
+            aliases: HashMap::new(),
+            constants: HashMap::new(),
+        };
+
+        let inputs = port_pins(module, "input")?;
+        let outputs = port_pins(module, "output")?;
+        let input_box = builder.container.add(FunctionBox::new("input", [0., 0.], Vec::new(), inputs.iter().map(|p| p.2.clone()).collect()));
+        for (i, (_, bit, _)) in inputs.iter().enumerate() {
+            builder.drive(*bit, (input_box, i));
+        }
+
+        let mut unsupported = Vec::new();
+        for (cell_name, cell) in module.get("cells").and_then(Value::as_object).into_iter().flatten() {
+            let cell_type = cell.get("type").and_then(Value::as_str).unwrap_or("");
+            let connections = cell.get("connections").and_then(Value::as_object);
+            let port = |name: &str| bits(connections.and_then(|c| c.get(name)))
+                .map_err(|e| format!("cell {} port {}: {}", cell_name, name, e));
+            let bit = |name: &str| port(name).map(|b| b.first().copied().unwrap_or(Bit::Undefined));
+
+            match cell_type {
+                "$_AND_" | "$_OR_" | "$_XOR_" | "$_NAND_" | "$_NOR_" | "$_XNOR_" => {
+                    let kind = cell_type.trim_matches(|c| c == '$' || c == '_').to_lowercase();
+                    builder.gate(&kind, &[bit("A")?, bit("B")?], bit("Y")?);
+                }
+                "$_NOT_" => {
+                    builder.gate("not", &[bit("A")?], bit("Y")?);
+                }
+                "$_BUF_" => {
+                    if let Bit::Net(net) = bit("Y")? {
+                        builder.aliases.insert(net, bit("A")?);
+                    }
+                }
+                "$_ANDNOT_" | "$_ORNOT_" => {
+                    let not_b = builder.internal_gate("not", &[bit("B")?]);
+                    builder.gate(if cell_type == "$_ANDNOT_" { "and" } else { "or" }, &[bit("A")?, not_b], bit("Y")?);
+                }
+                // Y = S ? B : A
+                "$_MUX_" => {
+                    let not_s = builder.internal_gate("not", &[bit("S")?]);
+                    let a = builder.internal_gate("and", &[bit("A")?, not_s]);
+                    let b = builder.internal_gate("and", &[bit("B")?, bit("S")?]);
+                    builder.gate("or", &[a, b], bit("Y")?);
+                }
+                // word level cells are imported bit by bit, shorter operands are extended with 0
+                "$and" | "$or" | "$xor" | "$xnor" | "$not" => {
+                    let (a, y) = (port("A")?, port("Y")?);
+                    let b = if cell_type == "$not" { Vec::new() } else { port("B")? };
+                    for (i, &out) in y.iter().enumerate() {
+                        let operand = |bits: &[Bit]| bits.get(i).copied().unwrap_or(Bit::Constant(false));
+                        if cell_type == "$not" {
+                            builder.gate("not", &[operand(&a)], out);
+                        } else {
+                            builder.gate(&cell_type[1..], &[operand(&a), operand(&b)], out);
+                        }
+                    }
+                }
+                "$reduce_and" | "$reduce_or" | "$reduce_xor" | "$reduce_xnor" => {
+                    let a = port("A")?;
+                    builder.gate(&cell_type["$reduce_".len()..], &a, bit("Y")?);
+                }
+                _ if self.modules.contains_key(cell_type) => {
+                    let circuit = self.import(cell_type, stack)?;
+                    let sub_module = &self.modules[cell_type];
+                    let n = builder.container.add(FunctionBox::new_circuit(cell_type, [0., 0.], circuit));
+                    let inputs_len = builder.container.graph[n].inputs_len;
+                    let pin_bit = |(port_name, _, pin): &(String, Bit, String)| -> Result<Bit, String> {
+                        let index = pin.strip_prefix(port_name.as_str())
+                            .and_then(|rest| rest.trim_start_matches('[').trim_end_matches(']').parse().ok())
+                            .unwrap_or(0);
+                        Ok(port(port_name)?.get(index).copied().unwrap_or(Bit::Undefined))
+                    };
+                    for (i, pin) in port_pins(sub_module, "input")?.iter().enumerate() {
+                        let bit = pin_bit(pin)?;
+                        builder.sinks.push((bit, (n, i)));
+                    }
+                    for (i, pin) in port_pins(sub_module, "output")?.iter().enumerate() {
+                        let bit = pin_bit(pin)?;
+                        builder.drive(bit, (n, inputs_len + i));
+                    }
+                }
+                _ => unsupported.push(format!("{} ({})", cell_name, cell_type)),
+            }
+        }
+        if !unsupported.is_empty() {
+            return Err(format!("module {} has unsupported cells: {}", name, unsupported.join(", ")));
+        }
+
+        let output_box = builder.container.add(FunctionBox::new("output", [0., 0.], outputs.iter().map(|p| p.2.clone()).collect(), Vec::new()));
+        builder.sinks.extend(outputs.iter().enumerate().map(|(i, (_, bit, _))| (*bit, (output_box, i))));
+        builder.connect_sinks();
+
+        let mut container = builder.container;
+        auto_place(&mut container);
+        stack.pop();
+        self.imported.insert(name.into(), container.clone());
+        Ok(container)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::netlist::fixtures::evaluate;
+
+    #[test]
+    fn imports_half_adder() {
+        let netlist: Value = serde_json::from_str(include_str!("../tests/data/half_adder.yosys.json")).unwrap();
+        assert!(is_yosys_netlist(&netlist));
+        let mut container = import_yosys(&netlist).unwrap();
+
+        assert_eq!(container.input_names(), vec!["a", "b"]);
+        assert_eq!(container.output_names(), vec!["sum", "carry"]);
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            assert_eq!(evaluate(&mut container, &[a, b]), vec![a != b, a && b], "a={} b={}", a, b);
+        }
+    }
+
+    #[test]
+    fn imports_sub_modules_and_reports_unsupported_cells() {
+        let mut netlist: Value = serde_json::from_str(include_str!("../tests/data/half_adder.yosys.json")).unwrap();
+        netlist["modules"]["half_adder"]["attributes"] = serde_json::json!({});
+        netlist["modules"]["top"] = serde_json::json!({
+            "attributes": { "top": "00000000000000000000000000000001" },
+            "ports": {
+                "x": { "direction": "input", "bits": [2, 3] },
+                "y": { "direction": "output", "bits": [4] }
+            },
+            "cells": {
+                "ha": { "type": "half_adder", "connections": { "a": [2], "b": [3], "sum": [4], "carry": [5] } }
+            }
+        });
+        let mut container = import_yosys(&netlist).unwrap();
+        assert_eq!(container.graph.raw_nodes().iter().filter(|n| n.weight.name == "circuit").count(), 1);
+        assert_eq!(evaluate(&mut container, &[true, false]), vec![true]);
+        assert_eq!(evaluate(&mut container, &[true, true]), vec![false]);
+
+        netlist["modules"]["top"]["cells"]["ff"] = serde_json::json!({ "type": "$_DFF_P_", "connections": {} });
+        let error = import_yosys(&netlist).unwrap_err();
+        assert!(error.contains("ff ($_DFF_P_)"), "{}", error);
+    }
+}
@@ -0,0 +1,157 @@
+use rustcpu::component::find_kind;
+use rustcpu::container::{Container, FunctionBoxRef};
+use rustcpu::function_box::FunctionBox;
+use rustcpu::simulation::{settle, tick};
+
+// a nand has the inputs 0 and 1 and the output 2
+const OUT: usize = 2;
+
+fn names(names: &[&str]) -> Vec<String> {
+    names.iter().map(|n| n.to_string()).collect()
+}
+
+// one input box and one output box with the given pins
+fn pins(container: &mut Container, inputs: &[&str], outputs: &[&str]) -> (FunctionBoxRef, FunctionBoxRef) {
+    let input = container.add(FunctionBox::new("input", [0., 0.], vec![], names(inputs)));
+    let output = container.add(FunctionBox::new("output", [0., 0.], names(outputs), vec![]));
+    (input, output)
+}
+
+fn nand(container: &mut Container, a: (FunctionBoxRef, usize), b: (FunctionBoxRef, usize)) -> FunctionBoxRef {
+    let gate = container.add(find_kind("nand").unwrap().create([0., 0.]));
+    container.connect(a, (gate, 0));
+    container.connect(b, (gate, 1));
+    gate
+}
+
+fn not(container: &mut Container, a: (FunctionBoxRef, usize)) -> FunctionBoxRef {
+    nand(container, a, a)
+}
+
+// settles the circuit for every row and compares all outputs
+fn assert_rows(container: &mut Container, rows: &[(&[bool], &[bool])]) {
+    for &(inputs, outputs) in rows {
+        container.set_inputs(inputs);
+        assert!(settle(container), "the circuit doesn't settle for {:?}", inputs);
+        assert_eq!(container.outputs(), outputs, "for the inputs {:?}", inputs);
+    }
+}
+
+#[test]
+fn not_from_a_nand() {
+    let mut container = Container::new();
+    let (input, output) = pins(&mut container, &["a"], &["y"]);
+    let y = not(&mut container, (input, 0));
+    container.connect((y, OUT), (output, 0));
+
+    assert_rows(&mut container, &[(&[false], &[true]), (&[true], &[false])]);
+}
+
+#[test]
+fn and_from_nands() {
+    let mut container = Container::new();
+    let (input, output) = pins(&mut container, &["a", "b"], &["y"]);
+    let n = nand(&mut container, (input, 0), (input, 1));
+    let y = not(&mut container, (n, OUT));
+    container.connect((y, OUT), (output, 0));
+
+    assert_rows(&mut container, &[
+        (&[false, false], &[false]),
+        (&[false, true], &[false]),
+        (&[true, false], &[false]),
+        (&[true, true], &[true]),
+    ]);
+}
+
+#[test]
+fn xor_from_four_nands() {
+    let mut container = Container::new();
+    let (input, output) = pins(&mut container, &["a", "b"], &["y"]);
+    let both = nand(&mut container, (input, 0), (input, 1));
+    let a = nand(&mut container, (input, 0), (both, OUT));
+    let b = nand(&mut container, (input, 1), (both, OUT));
+    let y = nand(&mut container, (a, OUT), (b, OUT));
+    container.connect((y, OUT), (output, 0));
+
+    assert_rows(&mut container, &[
+        (&[false, false], &[false]),
+        (&[false, true], &[true]),
+        (&[true, false], &[true]),
+        (&[true, true], &[false]),
+    ]);
+}
+
+#[test]
+fn half_adder_shares_the_first_nand() {
+    let mut container = Container::new();
+    let (input, output) = pins(&mut container, &["a", "b"], &["sum", "carry"]);
+    let both = nand(&mut container, (input, 0), (input, 1));
+    let a = nand(&mut container, (input, 0), (both, OUT));
+    let b = nand(&mut container, (input, 1), (both, OUT));
+    let sum = nand(&mut container, (a, OUT), (b, OUT));
+    let carry = not(&mut container, (both, OUT));
+    container.connect((sum, OUT), (output, 0));
+    container.connect((carry, OUT), (output, 1));
+
+    assert_eq!(container.input_names(), names(&["a", "b"]));
+    assert_eq!(container.output_names(), names(&["sum", "carry"]));
+    assert_rows(&mut container, &[
+        (&[false, false], &[false, false]),
+        (&[false, true], &[true, false]),
+        (&[true, false], &[true, false]),
+        (&[true, true], &[false, true]),
+    ]);
+}
+
+#[test]
+fn sr_latch_keeps_its_state() {
+    // set and reset are active low, the two nands feed each other
+    let mut container = Container::new();
+    let (input, output) = pins(&mut container, &["s", "r"], &["q", "nq"]);
+    let q = container.add(find_kind("nand").unwrap().create([0., 0.]));
+    let nq = nand(&mut container, (input, 1), (q, OUT));
+    container.connect((input, 0), (q, 0));
+    container.connect((nq, OUT), (q, 1));
+    container.connect((q, OUT), (output, 0));
+    container.connect((nq, OUT), (output, 1));
+
+    assert_rows(&mut container, &[
+        (&[false, true], &[true, false]),
+        (&[true, true], &[true, false]),
+        (&[true, false], &[false, true]),
+        (&[true, true], &[false, true]),
+        (&[false, true], &[true, false]),
+    ]);
+}
+
+#[test]
+fn delays_take_as_many_ticks() {
+    let mut container = Container::new();
+    let (input, output) = pins(&mut container, &["a"], &["y"]);
+    let y = not(&mut container, (input, 0));
+    container.connect((y, OUT), (output, 0));
+    container.graph[y].delay = 3;
+
+    container.set_inputs(&[false]);
+    tick(&mut container);
+    tick(&mut container);
+    assert_eq!(container.outputs(), vec![false]);
+    tick(&mut container);
+    assert_eq!(container.outputs(), vec![true]);
+}
+
+#[test]
+fn ticks_after_removing_the_first_box() {
+    let mut container = Container::new();
+    let (input, output) = pins(&mut container, &["a"], &["y"]);
+    let y = not(&mut container, (input, 0));
+    container.connect((y, OUT), (output, 0));
+    tick(&mut container);
+    tick(&mut container);
+
+    // the new box takes the index of the removed one
+    not(&mut container, (input, 0));
+    container.graph.remove_node(input);
+    assert_eq!(container.graph[input].generation, 0);
+    tick(&mut container);
+}